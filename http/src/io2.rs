@@ -3,6 +3,7 @@ use std::io;
 
 use atomic::AtomicOption;
 use futures::*;
+use futures::stream::Sink;
 use futuremio::{TcpStream, Error};
 
 pub trait ReadFuture: Send + 'static {
@@ -167,3 +168,36 @@ impl<T: WriteFuture> WriteFuture for BufWriter<T> {
         }).boxed()
     }
 }
+
+// Each item is buffered up as a whole, so a send finishes once all of it has
+// been buffered or written out. Closing flushes the buffer and then closes
+// the underlying writer, which therefore has to be a sink itself.
+impl<T> Sink for BufWriter<T>
+    where T: WriteFuture + Sink<Error=io::Error>,
+{
+    type Item = Vec<u8>;
+    type Error = io::Error;
+
+    fn start_send(&mut self, buf: Vec<u8>)
+                  -> Box<Future<Item=(), Error=io::Error>> {
+        let me = BufWriter { inner: self.inner.clone() };
+        me.write_all(0, buf).map(|_| ()).map_err(|e| e.into_pair().0).boxed()
+    }
+
+    fn flush(&mut self) -> Box<Future<Item=(), Error=io::Error>> {
+        BufWriter::flush(self)
+    }
+
+    fn close(&mut self) -> Box<Future<Item=(), Error=io::Error>> {
+        let me = BufWriter { inner: self.inner.clone() };
+        BufWriter::flush(self).and_then(move |()| {
+            let mut inner = match me.inner() {
+                Ok(inner) => inner,
+                Err(e) => return failed(e).boxed(),
+            };
+            let close = inner.inner.close();
+            me.inner.put(inner);
+            close
+        }).boxed()
+    }
+}
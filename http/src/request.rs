@@ -25,7 +25,7 @@ impl Request {
              mut buf: Vec<u8>) -> Box<IoFuture<(Request, TcpStream)>> {
         buf.reserve(1);
         let contents = s.read(buf);
-        contents.map_err(From::from).and_then(move |buf| {
+        contents.from_err().and_then(move |buf| {
             {
                 let mut headers = [httparse::EMPTY_HEADER; 16];
                 let mut r = httparse::Request::new(&mut headers);
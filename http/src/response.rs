@@ -47,7 +47,7 @@ impl Response {
             s.write_all(0, buf.into_bytes())
         }).and_then(move |(s, _, _)| {
             s.write_all(0, self.response.into_bytes())
-        }).map_err(From::from).and_then(|(mut s, _, _)| {
+        }).from_err().and_then(|(mut s, _, _)| {
             s.flush()
         }).boxed()
         // }).map_err(From::from).map(|_| ()).boxed()
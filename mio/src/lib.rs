@@ -3,13 +3,16 @@ extern crate futures;
 
 use std::collections::HashMap;
 use std::io::{self, Read, Write};
-use std::net::SocketAddr;
+use std::net::{Shutdown, SocketAddr};
 use std::panic;
 use std::slice;
 use std::sync::Arc;
 use std::sync::mpsc::{channel, TryRecvError};
+use std::time::{Duration, Instant};
 
-use futures::{Future, promise, Complete, PollError};
+use futures::{Future, promise, Complete, PollError, PollResult};
+use futures::stream::Sink;
+use futures::timer::Wheel;
 
 pub type IoFuture<T> = Future<Item=T, Error=io::Error>;
 
@@ -19,6 +22,7 @@ pub struct Loop {
     rx: mio::channel::Receiver<Message>,
     next: usize,
     done: HashMap<usize, Complete<(), io::Error>>,
+    timeouts: Wheel<Complete<(), io::Error>>,
 }
 
 enum Message {
@@ -188,6 +192,47 @@ impl TcpStream {
     }
 }
 
+impl TcpStream {
+    fn write_all(&self, offset: usize, data: Vec<u8>)
+                 -> Box<Future<Item=(), Error=io::Error>> {
+        let me2 = TcpStream {
+            tcp: self.tcp.clone(),
+            tx: self.tx.clone(),
+        };
+        self.write(offset, data).then(move |res| {
+            match res {
+                Ok((offset, data)) => {
+                    if offset == data.len() {
+                        futures::finished(()).boxed()
+                    } else {
+                        me2.write_all(offset, data)
+                    }
+                }
+                Err(e) => futures::failed(e.into_pair().0).boxed(),
+            }
+        }).boxed()
+    }
+}
+
+// Writes go straight to the socket, so there's never anything to flush, and
+// closing shuts down the write half of the connection.
+impl Sink for TcpStream {
+    type Item = Vec<u8>;
+    type Error = io::Error;
+
+    fn start_send(&mut self, data: Vec<u8>) -> Box<IoFuture<()>> {
+        self.write_all(0, data)
+    }
+
+    fn flush(&mut self) -> Box<IoFuture<()>> {
+        futures::finished(()).boxed()
+    }
+
+    fn close(&mut self) -> Box<IoFuture<()>> {
+        futures::done(self.tcp.shutdown(Shutdown::Write)).boxed()
+    }
+}
+
 impl Loop {
     pub fn new() -> io::Result<Loop> {
         let (tx, rx) = mio::channel::from_std_channel(channel());
@@ -199,6 +244,7 @@ impl Loop {
         Ok(Loop {
             io: io,
             done: HashMap::new(),
+            timeouts: Wheel::new(),
             next: 1,
             tx: tx,
             rx: rx,
@@ -207,6 +253,12 @@ impl Loop {
 
     pub fn await<F: Future>(&mut self, mut f: F)
                             -> Result<F::Item, F::Error> {
+        // If the future is already done there's no need to go through the
+        // event loop at all.
+        if let Some(r) = f.poll() {
+            return unwrap(r)
+        }
+
         let (tx, rx) = channel();
         f.schedule(move |r| {
             drop(tx.send(r))
@@ -221,17 +273,17 @@ impl Loop {
             }
             ret.is_some()
         });
-        match ret.unwrap() {
-            Ok(e) => Ok(e),
-            Err(PollError::Other(e)) => Err(e),
-            Err(PollError::Panicked(p)) => panic::resume_unwind(p),
-            Err(PollError::Canceled) => panic!("canceled"),
-        }
+        unwrap(ret.unwrap())
     }
 
     fn _await(&mut self, done: &mut FnMut() -> bool) {
         while !done() {
-            let amt = self.io.poll(None).unwrap();
+            // Block no longer than the nearest pending timeout
+            let timeout = self.timeouts.next_timeout().map(|at| {
+                let now = Instant::now();
+                if at > now {at - now} else {Duration::new(0, 0)}
+            });
+            let amt = self.io.poll(timeout).unwrap();
 
             for i in 0..amt {
                 let event = self.io.events().get(i).unwrap();
@@ -244,6 +296,11 @@ impl Loop {
                     complete.finish(());
                 }
             }
+
+            let now = Instant::now();
+            while let Some(complete) = self.timeouts.poll(now) {
+                complete.finish(());
+            }
         }
     }
 
@@ -276,6 +333,12 @@ impl Loop {
         }
     }
 
+    pub fn timeout(&mut self, dur: Duration) -> Box<IoFuture<()>> {
+        let (p, c) = promise();
+        self.timeouts.insert(Instant::now() + dur, c);
+        p.boxed()
+    }
+
     pub fn tcp_connect(&mut self, addr: &SocketAddr)
                        -> Box<IoFuture<TcpStream>> {
         let pair = mio::tcp::TcpStream::connect(addr).and_then(|tcp| {
@@ -318,6 +381,15 @@ impl Loop {
     }
 }
 
+fn unwrap<T, E>(r: PollResult<T, E>) -> Result<T, E> {
+    match r {
+        Ok(e) => Ok(e),
+        Err(PollError::Other(e)) => Err(e),
+        Err(PollError::Panicked(p)) => panic::resume_unwind(p),
+        Err(PollError::Canceled) => panic!("canceled"),
+    }
+}
+
 impl<T> Error<T> {
     pub fn new(err: io::Error, data: T) -> Error<T> {
         Error {
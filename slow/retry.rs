@@ -1,11 +1,13 @@
 extern crate futures;
 
+use std::time::Duration;
+
 use futures::*;
 
 type MyFuture<T> = Future<Item=T,Error=()>;
 
 fn fetch_item() -> Box<MyFuture<Option<Foo>>> {
-    return fetch_item2(fetch_replica_a(), fetch_replica_b());
+    fetch_item2(fetch_replica_a(), fetch_replica_b())
 }
 
 fn fetch_item2(a: Box<MyFuture<Foo>>, b: Box<MyFuture<Foo>>) -> Box<MyFuture<Option<Foo>>> {
@@ -48,16 +50,32 @@ fn reambiguate<T, E, U>(r: Result<(T, U), (E, U)>) -> Result<T, E> {
     }
 }
 
-struct Foo;
+struct Foo(bool);
 
 impl Foo {
-    fn is_valid(&self) -> bool { false }
+    fn is_valid(&self) -> bool { self.0 }
+}
+
+// Replica A is slow but eventually hands back a valid response, while replica
+// B answers quickly with garbage and gets restarted each time.
+fn fetch_replica_a() -> Box<MyFuture<Foo>> {
+    timer::delay(Duration::from_millis(50)).map(|()| Foo(true)).boxed()
 }
 
-fn fetch_replica_a() -> Box<MyFuture<Foo>> { loop {} }
-fn fetch_replica_b() -> Box<MyFuture<Foo>> { loop {} }
-fn timeout<T, E>() -> Box<Future<Item=Option<T>,Error=E>> { loop {} }
+fn fetch_replica_b() -> Box<MyFuture<Foo>> {
+    timer::delay(Duration::from_millis(10)).map(|()| Foo(false)).boxed()
+}
+
+fn timeout<T, E>() -> Box<Future<Item=Option<T>,Error=E>>
+    where T: Send + 'static, E: Send + 'static,
+{
+    timer::delay(Duration::from_millis(500)).then(|_| Ok(None)).boxed()
+}
 
 fn main() {
-    fetch_item();
+    match fetch_item().wait() {
+        Ok(Some(_)) => println!("got a valid response"),
+        Ok(None) => println!("timed out"),
+        Err(e) => println!("error: {:?}", e),
+    }
 }
@@ -0,0 +1,159 @@
+use std::sync::{Arc, Mutex};
+
+use {Future, Callback, PollResult, PollError};
+use executor::{self, Executor, Current};
+use util;
+
+// A future which can be canceled from elsewhere through its `AbortHandle`.
+pub struct Abortable<A: Future> {
+    inner: Arc<Inner<A>>,
+}
+
+// A handle to abort an `Abortable` future, which can be cloned and used from
+// any thread.
+#[derive(Clone)]
+pub struct AbortHandle {
+    inner: Arc<Abort>,
+}
+
+trait Abort: Send + Sync + 'static {
+    fn abort(&self);
+}
+
+struct Inner<A: Future> {
+    state: Mutex<State<A>>,
+}
+
+struct State<A: Future> {
+    // `None` while being polled or scheduled, or once we're done
+    future: Option<A>,
+    cb: Option<(Box<Callback<A::Item, A::Error>>, Current)>,
+    aborted: bool,
+}
+
+pub fn new<A: Future>(future: A) -> (Abortable<A>, AbortHandle) {
+    let inner = Arc::new(Inner {
+        state: Mutex::new(State {
+            future: Some(future),
+            cb: None,
+            aborted: false,
+        }),
+    });
+    (Abortable { inner: inner.clone() }, AbortHandle { inner: inner })
+}
+
+impl AbortHandle {
+    // Cancels the associated future, dropping it and resolving it to
+    // `PollError::Canceled` if it hasn't finished yet.
+    pub fn abort(&self) {
+        self.inner.abort()
+    }
+}
+
+impl<A: Future> Future for Abortable<A> {
+    type Item = A::Item;
+    type Error = A::Error;
+
+    fn poll(&mut self) -> Option<PollResult<A::Item, A::Error>> {
+        let mut future = {
+            let mut state = self.inner.state.lock().unwrap();
+            if state.aborted {
+                return Some(Err(PollError::Canceled))
+            }
+            if state.cb.is_some() {
+                return Some(Err(util::reused()))
+            }
+            match util::opt2poll(state.future.take()) {
+                Ok(f) => f,
+                Err(e) => return Some(Err(e)),
+            }
+        };
+        let res = future.poll();
+        if res.is_some() {
+            return res
+        }
+        let mut state = self.inner.state.lock().unwrap();
+        if state.aborted {
+            drop(state);
+            drop(future);
+            return Some(Err(PollError::Canceled))
+        }
+        state.future = Some(future);
+        None
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<A::Item, A::Error>) + Send + 'static
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<A::Item, A::Error>>) {
+        let mut future = {
+            let mut state = self.inner.state.lock().unwrap();
+            if state.aborted {
+                drop(state);
+                return executor::current().execute(|| {
+                    cb.call(Err(PollError::Canceled))
+                })
+            }
+            let future = match state.future.take() {
+                Some(f) if state.cb.is_none() => f,
+                other => {
+                    state.future = other;
+                    drop(state);
+                    return executor::current().execute(|| {
+                        cb.call(Err(util::reused()))
+                    })
+                }
+            };
+            state.cb = Some((cb, executor::current()));
+            future
+        };
+        let inner = self.inner.clone();
+        future.schedule(move |r| inner.done(r));
+
+        // If we finished or were aborted in the meantime then there's no need
+        // to hang on to the future any more.
+        let mut state = self.inner.state.lock().unwrap();
+        if state.cb.is_some() {
+            state.future = Some(future);
+        } else {
+            drop(state);
+            drop(future);
+        }
+    }
+}
+
+impl<A: Future> Inner<A> {
+    fn done(&self, res: PollResult<A::Item, A::Error>) {
+        let (future, cb) = {
+            let mut state = self.state.lock().unwrap();
+            (state.future.take(), state.cb.take())
+        };
+        drop(future);
+        if let Some((cb, _)) = cb {
+            cb.call(res)
+        }
+    }
+}
+
+impl<A: Future> Abort for Inner<A> {
+    fn abort(&self) {
+        let (future, cb) = {
+            let mut state = self.state.lock().unwrap();
+            state.aborted = true;
+            (state.future.take(), state.cb.take())
+        };
+        drop(future);
+        if let Some((cb, executor)) = cb {
+            executor.execute(|| cb.call(Err(PollError::Canceled)))
+        }
+    }
+}
+
+impl<A: Future> Drop for Abortable<A> {
+    fn drop(&mut self) {
+        self.inner.abort()
+    }
+}
@@ -24,16 +24,27 @@ impl<A, B, F> Future for AndThen<A, B, F>
     type Item = B::Item;
     type Error = B::Error;
 
+    fn poll(&mut self) -> Option<PollResult<B::Item, B::Error>> {
+        self.state.poll(and_then)
+    }
+
     fn schedule<G>(&mut self, g: G)
         where G: FnOnce(PollResult<B::Item, B::Error>) + Send + 'static
     {
-        self.state.schedule(g, |a, f| {
-            let e = try!(a);
-            util::recover(|| f(e)).map(|b| Err(b.into_future()))
-        })
+        self.state.schedule(g, and_then)
     }
 
     fn schedule_boxed(&mut self, cb: Box<Callback<B::Item, B::Error>>) {
         self.schedule(|r| cb.call(r))
     }
 }
+
+fn and_then<T, E, B, F>(a: PollResult<T, E>, f: F)
+                        -> PollResult<Result<B::Item, B::Future>, E>
+    where F: FnOnce(T) -> B + Send + 'static,
+          B: IntoFuture<Error=E>,
+          T: Send + 'static,
+{
+    let e = try!(a);
+    util::recover(|| f(e)).map(|b| Err(b.into_future()))
+}
@@ -2,14 +2,16 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::mem;
 
-use executor::{Executor, DEFAULT};
+use executor::{self, Executor};
 use slot::Slot;
 use util;
 use {Future, PollResult};
 
 pub enum Chain<A, B, C> where A: Send + 'static, B: Send + 'static {
     First(A, C),
+    Second(B),
     Slot(DropSlot<A, B>),
+    Done,
     Moved,
 }
 
@@ -32,6 +34,52 @@ impl<A, B, C> Chain<A, B, C>
         Chain::First(a, c)
     }
 
+    pub fn poll<F>(&mut self, f: F) -> Option<PollResult<B::Item, B::Error>>
+        where F: FnOnce(PollResult<A::Item, A::Error>, C)
+                        -> PollResult<Result<B::Item, B>, B::Error> + Send + 'static,
+    {
+        let mut b = match mem::replace(self, Chain::Moved) {
+            Chain::First(mut a, data) => {
+                let r = match a.poll() {
+                    Some(r) => r,
+                    None => {
+                        *self = Chain::First(a, data);
+                        return None
+                    }
+                };
+                // Like `schedule` below we eagerly drop the first future as
+                // soon as it's done.
+                drop(a);
+                match f(r, data) {
+                    Ok(Ok(e)) => {
+                        *self = Chain::Done;
+                        return Some(Ok(e))
+                    }
+                    Ok(Err(b)) => b,
+                    Err(e) => {
+                        *self = Chain::Done;
+                        return Some(Err(e))
+                    }
+                }
+            }
+            Chain::Second(b) => b,
+            Chain::Slot(s) => {
+                *self = Chain::Slot(s);
+                return Some(Err(util::reused()))
+            }
+            Chain::Done => {
+                *self = Chain::Done;
+                return Some(Err(util::reused()))
+            }
+
+            // should be unreachable
+            Chain::Moved => panic!(),
+        };
+        let ret = b.poll();
+        *self = Chain::Second(b);
+        ret
+    }
+
     pub fn schedule<G, F>(&mut self, g: G, f: F)
         where G: FnOnce(PollResult<B::Item, B::Error>) + Send + 'static,
               F: FnOnce(PollResult<A::Item, A::Error>, C)
@@ -54,6 +102,7 @@ impl<A, B, C> Chain<A, B, C>
                     b: Slot::new(None),
                 });
                 let slot2 = slot.clone();
+                let executor = executor::current();
                 a.schedule(move |r| {
                     // Eagerly drop the first future in case it's holding on to
                     // any resources, it's done and we don't need it any more.
@@ -69,23 +118,37 @@ impl<A, B, C> Chain<A, B, C>
                     slot.drop_a();
 
                     let mut b = match f(r, data) {
-                        Ok(Ok(e)) => return DEFAULT.execute(|| g(Ok(e))),
+                        Ok(Ok(e)) => return executor.execute(|| g(Ok(e))),
                         Ok(Err(b)) => b,
-                        Err(e) => return DEFAULT.execute(|| g(Err(e))),
+                        Err(e) => return executor.execute(|| g(Err(e))),
                     };
-                    b.schedule(g);
+                    // We may be on some other thread by now, so make sure
+                    // that `b` picks up the same executor we were scheduled
+                    // with.
+                    executor.enter(|| b.schedule(g));
                     slot.b.try_produce(b).ok().unwrap();
                 });
                 slot2.a.try_produce(a).ok().unwrap();
                 *self = Chain::Slot(DropSlot { slot: slot2 });
             }
 
+            // if we've been polled to the point that the first future is
+            // done, then the second future is the only one left to schedule
+            Chain::Second(mut b) => {
+                b.schedule(g);
+                *self = Chain::Second(b);
+            }
+
             // if we see `Slot` then `schedule` has already been called, and
             // we're not allowed to schedule again after that, so just return a
             // panicked error as this is a contract violation
             Chain::Slot(s) => {
                 *self = Chain::Slot(s);
-                return DEFAULT.execute(|| g(Err(util::reused())))
+                return executor::current().execute(|| g(Err(util::reused())))
+            }
+            Chain::Done => {
+                *self = Chain::Done;
+                executor::current().execute(|| g(Err(util::reused())))
             }
 
             // should be unreachable
@@ -106,7 +169,7 @@ impl<A, B> ChainSlot<A, B>
         }
         self.a.on_full(|slot| {
             drop(slot.try_consume().ok().unwrap());
-        });
+        }).unwrap();
     }
 }
 
@@ -118,6 +181,6 @@ impl<A, B> Drop for DropSlot<A, B>
         self.slot.drop_a();
         self.slot.b.on_full(|slot| {
             drop(slot.try_consume().ok().unwrap());
-        });
+        }).unwrap();
     }
 }
@@ -1,9 +1,10 @@
 use std::mem;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+use executor::{self, Executor, Current};
 use lock::Lock;
-use {Future, Callback, PollResult, IntoFuture};
+use {Future, Callback, PollResult, PollError, IntoFuture};
 use util;
 
 pub struct Collect<I>
@@ -35,6 +36,7 @@ struct Scheduled<I>
 {
     state: AtomicUsize,
     slot: Lock<Option<<I::Item as IntoFuture>::Future>>,
+    executor: Current,
 }
 
 const CANCELED: usize = !0;
@@ -62,6 +64,35 @@ impl<I> Future for Collect<I>
     type Item = Vec<<I::Item as IntoFuture>::Item>;
     type Error = <I::Item as IntoFuture>::Error;
 
+    fn poll(&mut self) -> Option<PollResult<Self::Item, Self::Error>> {
+        let res = match self.state {
+            State::Local { ref mut cur, ref mut remaining, ref mut result } => {
+                loop {
+                    let res = match *cur {
+                        Some(ref mut f) => f.poll(),
+                        None => break result.take().ok_or_else(util::reused),
+                    };
+                    match res {
+                        Some(Ok(item)) => {
+                            match *result {
+                                Some(ref mut v) => v.push(item),
+                                None => break Err(util::reused()),
+                            }
+                            *cur = remaining.next().map(IntoFuture::into_future);
+                        }
+                        Some(Err(e)) => break Err(e),
+                        None => return None,
+                    }
+                }
+            }
+            State::Scheduled(..) | State::Done => Err(util::reused()),
+        };
+        if let State::Local { .. } = self.state {
+            self.state = State::Done;
+        }
+        Some(res)
+    }
+
     fn schedule<G>(&mut self, g: G)
         where G: FnOnce(PollResult<Self::Item, Self::Error>) + Send + 'static
     {
@@ -87,6 +118,7 @@ impl<I> Future for Collect<I>
         let state = Arc::new(Scheduled {
             state: AtomicUsize::new(result.len()),
             slot: Lock::new(None),
+            executor: executor::current(),
         });
         Scheduled::run(&state, cur, remaining, result, g);
 
@@ -169,7 +201,11 @@ impl<I> Scheduled<I>
             Err(e) => return g(Err(e)),
         }
         match remaining.next() {
-            Some(f) => Scheduled::run(state, f.into_future(), remaining, result, g),
+            Some(f) => {
+                state.executor.enter(|| {
+                    Scheduled::run(state, f.into_future(), remaining, result, g)
+                })
+            }
             None => return g(Ok(result)),
         }
     }
@@ -201,3 +237,293 @@ impl<I> Drop for Collect<I>
         }
     }
 }
+
+pub struct CollectConcurrent<I>
+    where I: IntoIterator + Send + 'static,
+          I::Item: IntoFuture,
+          I::IntoIter: Send + 'static,
+{
+    state: ConcurrentState<I>,
+}
+
+enum ConcurrentState<I>
+    where I: IntoIterator + Send + 'static,
+          I::Item: IntoFuture,
+          I::IntoIter: Send + 'static,
+{
+    Local {
+        in_flight: Vec<(usize, <I::Item as IntoFuture>::Future)>,
+        remaining: I::IntoIter,
+        results: Vec<Option<<I::Item as IntoFuture>::Item>>,
+        limit: usize,
+    },
+    Scheduled(Arc<ScheduledConcurrent<I>>),
+    Done,
+}
+
+struct ScheduledConcurrent<I>
+    where I: IntoIterator + Send + 'static,
+          I::Item: IntoFuture,
+          I::IntoIter: Send + 'static,
+{
+    state: AtomicUsize,
+    inner: Mutex<Concurrent<I>>,
+    executor: Current,
+}
+
+struct Concurrent<I>
+    where I: IntoIterator + Send + 'static,
+          I::Item: IntoFuture,
+          I::IntoIter: Send + 'static,
+{
+    // Indexed by position in the original iterator, in-flight futures are
+    // stored here so they can be canceled.
+    futures: Vec<Option<<I::Item as IntoFuture>::Future>>,
+    results: Vec<Option<<I::Item as IntoFuture>::Item>>,
+    remaining: I::IntoIter,
+    active: usize,
+    limit: usize,
+    cb: Option<Box<Callback<Vec<<I::Item as IntoFuture>::Item>,
+                            <I::Item as IntoFuture>::Error>>>,
+}
+
+// Like `collect`, but keeps up to `limit` futures from `i` running at once.
+// Results are still in the same order as `i`, and the first error cancels
+// all other futures in flight.
+pub fn collect_concurrent<I>(i: I, limit: usize) -> CollectConcurrent<I>
+    where I: IntoIterator + Send + 'static,
+          I::Item: IntoFuture,
+          I::IntoIter: Send + 'static,
+{
+    assert!(limit > 0, "cannot collect with a limit of 0");
+    CollectConcurrent {
+        state: ConcurrentState::Local {
+            in_flight: Vec::new(),
+            remaining: i.into_iter(),
+            results: Vec::new(),
+            limit: limit,
+        },
+    }
+}
+
+impl<I> Future for CollectConcurrent<I>
+    where I: IntoIterator + Send + 'static,
+          I::IntoIter: Send + 'static,
+          I::Item: IntoFuture,
+{
+    type Item = Vec<<I::Item as IntoFuture>::Item>;
+    type Error = <I::Item as IntoFuture>::Error;
+
+    fn poll(&mut self) -> Option<PollResult<Self::Item, Self::Error>> {
+        let res = match self.state {
+            ConcurrentState::Local { ref mut in_flight, ref mut remaining,
+                                     ref mut results, limit } => {
+                loop {
+                    while in_flight.len() < limit {
+                        match remaining.next() {
+                            Some(f) => {
+                                in_flight.push((results.len(), f.into_future()));
+                                results.push(None);
+                            }
+                            None => break,
+                        }
+                    }
+                    if in_flight.is_empty() {
+                        break Ok(results.drain(..).map(|r| r.unwrap()).collect())
+                    }
+
+                    let mut progress = false;
+                    let mut err = None;
+                    let mut j = 0;
+                    while j < in_flight.len() {
+                        match in_flight[j].1.poll() {
+                            Some(Ok(item)) => {
+                                results[in_flight.remove(j).0] = Some(item);
+                                progress = true;
+                            }
+                            Some(Err(e)) => {
+                                err = Some(e);
+                                break
+                            }
+                            None => j += 1,
+                        }
+                    }
+                    if let Some(e) = err {
+                        break Err(e)
+                    }
+                    if !progress {
+                        return None
+                    }
+                }
+            }
+            ConcurrentState::Scheduled(..) |
+            ConcurrentState::Done => Err(util::reused()),
+        };
+        // Dropping the futures in flight here cancels them if we hit an error
+        if let ConcurrentState::Local { .. } = self.state {
+            self.state = ConcurrentState::Done;
+        }
+        Some(res)
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<Self::Item, Self::Error>) + Send + 'static
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<Self::Item, Self::Error>>) {
+        let (in_flight, remaining, results, limit) =
+            match mem::replace(&mut self.state, ConcurrentState::Done) {
+                ConcurrentState::Local { in_flight, remaining, results, limit } => {
+                    (in_flight, remaining, results, limit)
+                }
+                ConcurrentState::Scheduled(s) => {
+                    self.state = ConcurrentState::Scheduled(s);
+                    return executor::current().execute(|| cb.call(Err(util::reused())))
+                }
+                ConcurrentState::Done => {
+                    return executor::current().execute(|| cb.call(Err(util::reused())))
+                }
+            };
+        let state = Arc::new(ScheduledConcurrent {
+            state: AtomicUsize::new(0),
+            inner: Mutex::new(Concurrent {
+                futures: results.iter().map(|_| None).collect(),
+                results: results,
+                remaining: remaining,
+                active: in_flight.len(),
+                limit: limit,
+                cb: Some(cb),
+            }),
+            executor: executor::current(),
+        });
+        let more = ScheduledConcurrent::fill(&state);
+        for (i, f) in in_flight.into_iter().chain(more) {
+            ScheduledConcurrent::spawn(&state, i, f);
+        }
+
+        self.state = ConcurrentState::Scheduled(state);
+    }
+}
+
+impl<I> ScheduledConcurrent<I>
+    where I: IntoIterator + Send + 'static,
+          I::Item: IntoFuture,
+          I::IntoIter: Send + 'static
+{
+    fn spawn(state: &Arc<ScheduledConcurrent<I>>,
+             i: usize,
+             mut future: <I::Item as IntoFuture>::Future) {
+        let state2 = state.clone();
+        future.schedule(move |res| ScheduledConcurrent::finish(&state2, i, res));
+
+        // If we were canceled in the meantime, or the future (or everything)
+        // finished immediately, then there's no need to hold on to it.
+        let future = {
+            let mut inner = state.inner.lock().unwrap();
+            if state.state.load(Ordering::SeqCst) == CANCELED ||
+               inner.cb.is_none() ||
+               inner.results[i].is_some() {
+                Some(future)
+            } else {
+                inner.futures[i] = Some(future);
+                None
+            }
+        };
+        drop(future);
+    }
+
+    // Pulls futures off the iterator until we're back at the limit, running
+    // the callback if everything's done.
+    fn fill(state: &Arc<ScheduledConcurrent<I>>)
+            -> Vec<(usize, <I::Item as IntoFuture>::Future)> {
+        let mut inner = state.inner.lock().unwrap();
+        let mut more = Vec::new();
+        let canceled = state.state.load(Ordering::SeqCst) == CANCELED;
+        while !canceled && inner.active < inner.limit {
+            let f = match inner.remaining.next() {
+                Some(f) => f.into_future(),
+                None => break,
+            };
+            more.push((inner.results.len(), f));
+            inner.results.push(None);
+            inner.futures.push(None);
+            inner.active += 1;
+        }
+        if inner.active == 0 {
+            if let Some(cb) = inner.cb.take() {
+                let res = if canceled {
+                    Err(PollError::Canceled)
+                } else {
+                    Ok(inner.results.drain(..).map(|r| r.unwrap()).collect())
+                };
+                drop(inner);
+                state.executor.execute(|| cb.call(res));
+            }
+        }
+        more
+    }
+
+    fn finish(state: &Arc<ScheduledConcurrent<I>>,
+              i: usize,
+              res: PollResult<<I::Item as IntoFuture>::Item,
+                              <I::Item as IntoFuture>::Error>) {
+        let (e, finished) = {
+            let mut inner = state.inner.lock().unwrap();
+            if inner.cb.is_none() {
+                return
+            }
+            match res {
+                Ok(item) => {
+                    inner.results[i] = Some(item);
+                    inner.active -= 1;
+                    (None, inner.futures[i].take())
+                }
+                Err(e) => (Some((e, inner.cb.take().unwrap())), None),
+            }
+        };
+
+        // Dropping the future may run callbacks which call back into us, so
+        // make sure that happens outside the lock.
+        drop(finished);
+        match e {
+            Some((e, cb)) => {
+                state.cancel();
+                state.executor.execute(|| cb.call(Err(e)))
+            }
+            None => {
+                let more = ScheduledConcurrent::fill(state);
+                state.executor.enter(|| {
+                    for (i, f) in more {
+                        ScheduledConcurrent::spawn(state, i, f);
+                    }
+                })
+            }
+        }
+    }
+
+    fn cancel(&self) {
+        // Same as `Scheduled::cancel` above, except that there may be many
+        // futures to cancel. Any future being stored concurrently will see
+        // CANCELED and cancel itself.
+        self.state.store(CANCELED, Ordering::SeqCst);
+        let futures = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.futures.iter_mut().filter_map(Option::take).collect::<Vec<_>>()
+        };
+        drop(futures);
+    }
+}
+
+impl<I> Drop for CollectConcurrent<I>
+    where I: IntoIterator + Send + 'static,
+          I::Item: IntoFuture,
+          I::IntoIter: Send + 'static,
+{
+    fn drop(&mut self) {
+        if let ConcurrentState::Scheduled(ref s) = self.state {
+            s.cancel();
+        }
+    }
+}
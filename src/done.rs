@@ -1,4 +1,4 @@
-use executor::{Executor, DEFAULT};
+use executor::{self, Executor};
 use util;
 use {PollResult, Future, PollError, Callback};
 
@@ -20,13 +20,19 @@ impl<T, E> Future for Done<T, E>
     type Item = T;
     type Error = E;
 
+    fn poll(&mut self) -> Option<PollResult<T, E>> {
+        Some(util::opt2poll(self.inner.take()).and_then(|r| {
+            r.map_err(PollError::Other)
+        }))
+    }
+
     fn schedule<F>(&mut self, f: F)
         where F: FnOnce(PollResult<T, E>) + Send + 'static
     {
         let res = util::opt2poll(self.inner.take()).and_then(|r| {
             r.map_err(PollError::Other)
         });
-        DEFAULT.execute(|| f(res))
+        executor::current().execute(|| f(res))
     }
 
     fn schedule_boxed(&mut self, cb: Box<Callback<T, E>>) {
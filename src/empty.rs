@@ -1,12 +1,12 @@
 use {Future, Callback, PollResult, PollError};
-use executor::{Executor, DEFAULT};
+use executor::{self, Executor, Current};
 use util;
 
 pub struct Empty<T, E>
     where T: Send + 'static,
           E: Send + 'static,
 {
-    callback: Option<Box<Callback<T, E>>>,
+    callback: Option<(Box<Callback<T, E>>, Current)>,
 }
 
 pub fn empty<T: Send + 'static, E: Send + 'static>() -> Empty<T, E> {
@@ -22,6 +22,14 @@ impl<T, E> Future for Empty<T, E>
     type Item = T;
     type Error = E;
 
+    fn poll(&mut self) -> Option<PollResult<T, E>> {
+        if self.callback.is_some() {
+            Some(Err(util::reused()))
+        } else {
+            None
+        }
+    }
+
     fn schedule<G>(&mut self, g: G)
         where G: FnOnce(PollResult<T, E>) + Send + 'static
     {
@@ -29,10 +37,11 @@ impl<T, E> Future for Empty<T, E>
     }
 
     fn schedule_boxed(&mut self, cb: Box<Callback<T, E>>) {
+        let executor = executor::current();
         if self.callback.is_some() {
-            DEFAULT.execute(|| cb.call(Err(util::reused())))
+            executor.execute(|| cb.call(Err(util::reused())))
         } else {
-            self.callback = Some(cb);
+            self.callback = Some((cb, executor));
         }
     }
 }
@@ -42,8 +51,8 @@ impl<T, E> Drop for Empty<T, E>
           E: Send + 'static,
 {
     fn drop(&mut self) {
-        if let Some(cb) = self.callback.take() {
-            DEFAULT.execute(|| cb.call(Err(PollError::Canceled)));
+        if let Some((cb, executor)) = self.callback.take() {
+            executor.execute(|| cb.call(Err(PollError::Canceled)));
         }
     }
 }
@@ -0,0 +1,43 @@
+use std::marker;
+
+use {PollResult, Future, Callback};
+use executor::{self, Executor};
+
+pub struct ErrInto<A, E> {
+    future: A,
+    _e: marker::PhantomData<E>,
+}
+
+pub fn new<A, E>(future: A) -> ErrInto<A, E> {
+    ErrInto {
+        future: future,
+        _e: marker::PhantomData,
+    }
+}
+
+impl<A, E> Future for ErrInto<A, E>
+    where A: Future,
+          A::Error: Into<E>,
+          E: Send + 'static,
+{
+    type Item = A::Item;
+    type Error = E;
+
+    fn poll(&mut self) -> Option<PollResult<A::Item, E>> {
+        self.future.poll().map(|r| r.map_err(|e| e.map(Into::into)))
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<A::Item, E>) + Send + 'static
+    {
+        let executor = executor::current();
+        self.future.schedule(move |result| {
+            let r = result.map_err(|e| e.map(Into::into));
+            executor.execute(|| g(r))
+        })
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<A::Item, E>>) {
+        self.schedule(|r| cb.call(r));
+    }
+}
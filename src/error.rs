@@ -1,4 +1,7 @@
 use std::any::Any;
+use std::error::Error;
+use std::fmt;
+use std::panic;
 
 pub type PollResult<T, E> = Result<T, PollError<E>>;
 
@@ -8,6 +11,17 @@ pub enum PollError<E> {
     Other(E),
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum WaitError<E> {
+    Canceled,
+    Other(E),
+}
+
+// The payload of the `PollError::Panicked` handed out when a future is polled
+// or scheduled after it's already been consumed or scheduled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reused;
+
 impl<E> PollError<E> {
     pub fn map<F: FnOnce(E) -> E2, E2>(self, f: F) -> PollError<E2> {
         match self {
@@ -16,4 +30,107 @@ impl<E> PollError<E> {
             PollError::Other(e) => PollError::Other(f(e)),
         }
     }
+
+    // Returns whether this is the panic from a future being reused.
+    pub fn is_reused(&self) -> bool {
+        match *self {
+            PollError::Panicked(ref p) => p.is::<Reused>(),
+            _ => false,
+        }
+    }
+
+    // Returns the message the future panicked with, if it panicked with a
+    // string as `panic!` does.
+    pub fn panic_message(&self) -> Option<&str> {
+        let p = match *self {
+            PollError::Panicked(ref p) => p,
+            _ => return None,
+        };
+        match p.downcast_ref::<&'static str>() {
+            Some(s) => Some(s),
+            None => p.downcast_ref::<String>().map(|s| &s[..]),
+        }
+    }
+
+    // Converts this into the error `Future::wait` would return, resuming the
+    // panic on this thread if the future panicked.
+    //
+    // This is meant to be used as `res.or_else(PollError::into_result)`.
+    pub fn into_result<T>(self) -> Result<T, WaitError<E>> {
+        match self {
+            PollError::Panicked(p) => panic::resume_unwind(p),
+            PollError::Canceled => Err(WaitError::Canceled),
+            PollError::Other(e) => Err(WaitError::Other(e)),
+        }
+    }
+}
+
+impl<E: fmt::Debug> fmt::Debug for PollError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PollError::Panicked(_) if self.is_reused() => {
+                f.debug_tuple("Panicked").field(&Reused).finish()
+            }
+            PollError::Panicked(_) => {
+                let msg = self.panic_message().unwrap_or("<unknown>");
+                f.debug_tuple("Panicked").field(&msg).finish()
+            }
+            PollError::Canceled => f.write_str("Canceled"),
+            PollError::Other(ref e) => f.debug_tuple("Other").field(e).finish(),
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for PollError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PollError::Panicked(_) if self.is_reused() => {
+                f.write_str("future polled or scheduled after being consumed")
+            }
+            PollError::Panicked(_) => {
+                match self.panic_message() {
+                    Some(msg) => write!(f, "future panicked: {}", msg),
+                    None => f.write_str("future panicked"),
+                }
+            }
+            PollError::Canceled => f.write_str("future canceled"),
+            PollError::Other(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for PollError<E> {
+    fn source(&self) -> Option<&(Error + 'static)> {
+        match *self {
+            PollError::Other(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl<E> WaitError<E> {
+    pub fn map<F: FnOnce(E) -> E2, E2>(self, f: F) -> WaitError<E2> {
+        match self {
+            WaitError::Canceled => WaitError::Canceled,
+            WaitError::Other(e) => WaitError::Other(f(e)),
+        }
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for WaitError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WaitError::Canceled => f.write_str("future canceled"),
+            WaitError::Other(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for WaitError<E> {
+    fn source(&self) -> Option<&(Error + 'static)> {
+        match *self {
+            WaitError::Other(ref e) => Some(e),
+            WaitError::Canceled => None,
+        }
+    }
 }
@@ -1,5 +1,11 @@
 use std::cell::{Cell, RefCell};
-use std::sync::Arc;
+use std::collections::VecDeque;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, Condvar};
+use std::thread::{self, JoinHandle};
+
+use task::Locals;
 
 pub trait Executor: Send + Sync + 'static {
     fn execute<F>(&self, f: F)
@@ -14,6 +20,81 @@ pub trait Executor: Send + Sync + 'static {
 
 pub static DEFAULT: Limited = Limited;
 
+thread_local!(static CURRENT: RefCell<Option<Arc<Executor>>> = RefCell::new(None));
+
+// Returns a handle to the executor that futures scheduled on this thread
+// should deliver their results through.
+//
+// This is `DEFAULT` unless it's been overridden with `Current::enter`, for
+// example by `Future::on`. The task-locals set on this thread are captured
+// too, and restored around every callback executed through the handle.
+pub fn current() -> Current {
+    Current {
+        inner: CURRENT.with(|c| c.borrow().clone()),
+        locals: Locals::current(),
+    }
+}
+
+#[derive(Clone)]
+pub struct Current {
+    inner: Option<Arc<Executor>>,
+    locals: Locals,
+}
+
+impl Current {
+    // Creates a handle to `executor` which carries along the task-locals set
+    // on this thread.
+    pub fn new(executor: Arc<Executor>) -> Current {
+        Current { inner: Some(executor), locals: Locals::current() }
+    }
+
+    // Runs `f` with this executor and its task-locals installed as the
+    // current ones, restoring the previous ones afterwards.
+    //
+    // Combinators capture the current executor when they're scheduled, but
+    // continuations may run on some other thread, so they need to re-enter
+    // the executor before scheduling any more futures.
+    pub fn enter<F, R>(&self, f: F) -> R
+        where F: FnOnce() -> R
+    {
+        self.locals.enter(|| self.enter_executor(f))
+    }
+
+    fn enter_executor<F, R>(&self, f: F) -> R
+        where F: FnOnce() -> R
+    {
+        struct Reset(Option<Arc<Executor>>);
+
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                let prev = self.0.take();
+                CURRENT.with(|c| *c.borrow_mut() = prev);
+            }
+        }
+
+        let prev = CURRENT.with(|c| {
+            mem::replace(&mut *c.borrow_mut(), self.inner.clone())
+        });
+        let _reset = Reset(prev);
+        f()
+    }
+}
+
+impl Executor for Current {
+    fn execute_boxed(&self, f: Box<ExecuteCallback>) {
+        // Whatever thread `f` ends up on, it shouldn't see that thread's
+        // task-locals, so it always gets ours even if there are none.
+        let locals = self.locals.clone();
+        let f: Box<ExecuteCallback> = Box::new(move || {
+            locals.enter(|| f.call())
+        });
+        match self.inner {
+            Some(ref e) => e.execute_boxed(f),
+            None => DEFAULT.execute_boxed(f),
+        }
+    }
+}
+
 impl<T: Executor + ?Sized + Send + Sync + 'static> Executor for Box<T> {
     fn execute_boxed(&self, f: Box<ExecuteCallback>) {
         (**self).execute_boxed(f)
@@ -100,12 +181,156 @@ impl LimitState {
     }
 }
 
+pub struct ThreadPool {
+    inner: Arc<PoolInner>,
+    threads: Mutex<Vec<JoinHandle<()>>>,
+}
+
+pub struct ThreadPoolBuilder {
+    size: usize,
+    name_prefix: Option<String>,
+    stack_size: Option<usize>,
+}
+
+struct PoolInner {
+    state: Mutex<PoolState>,
+    cvar: Condvar,
+}
+
+struct PoolState {
+    queue: VecDeque<Box<ExecuteCallback>>,
+    shutdown: bool,
+}
+
+impl ThreadPool {
+    pub fn new(size: usize) -> ThreadPool {
+        ThreadPool::builder().size(size).create()
+    }
+
+    pub fn builder() -> ThreadPoolBuilder {
+        ThreadPoolBuilder {
+            size: 1,
+            name_prefix: None,
+            stack_size: None,
+        }
+    }
+
+    // Stops accepting new work and blocks until every callback that was
+    // already queued has been run and all worker threads have exited.
+    //
+    // Callbacks executed after shutdown are run inline on the calling thread
+    // as they're guaranteed to get called.
+    pub fn shutdown(&self) {
+        self.inner.state.lock().unwrap().shutdown = true;
+        self.inner.cvar.notify_all();
+
+        let threads = self.threads.lock().unwrap().drain(..).collect::<Vec<_>>();
+        let me = thread::current().id();
+        for thread in threads {
+            // If the last reference to the pool is dropped on one of its own
+            // workers then we can't wait for ourselves to exit.
+            if thread.thread().id() != me {
+                drop(thread.join());
+            }
+        }
+    }
+}
+
+impl Executor for ThreadPool {
+    fn execute_boxed(&self, f: Box<ExecuteCallback>) {
+        let mut state = self.inner.state.lock().unwrap();
+        if state.shutdown {
+            drop(state);
+            return f.call()
+        }
+        state.queue.push_back(f);
+        drop(state);
+        self.inner.cvar.notify_one();
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl ThreadPoolBuilder {
+    pub fn size(&mut self, size: usize) -> &mut ThreadPoolBuilder {
+        assert!(size > 0, "thread pool must have at least one thread");
+        self.size = size;
+        self
+    }
+
+    pub fn name_prefix(&mut self, prefix: &str) -> &mut ThreadPoolBuilder {
+        self.name_prefix = Some(prefix.to_string());
+        self
+    }
+
+    pub fn stack_size(&mut self, size: usize) -> &mut ThreadPoolBuilder {
+        self.stack_size = Some(size);
+        self
+    }
+
+    pub fn create(&mut self) -> ThreadPool {
+        let inner = Arc::new(PoolInner {
+            state: Mutex::new(PoolState {
+                queue: VecDeque::new(),
+                shutdown: false,
+            }),
+            cvar: Condvar::new(),
+        });
+        let threads = (0..self.size).map(|i| {
+            let mut builder = thread::Builder::new();
+            if let Some(ref prefix) = self.name_prefix {
+                builder = builder.name(format!("{}{}", prefix, i));
+            }
+            if let Some(size) = self.stack_size {
+                builder = builder.stack_size(size);
+            }
+            let inner = inner.clone();
+            builder.spawn(move || inner.work()).expect("failed to spawn thread")
+        }).collect();
+        ThreadPool {
+            inner: inner,
+            threads: Mutex::new(threads),
+        }
+    }
+}
+
+impl PoolInner {
+    fn work(&self) {
+        loop {
+            let f = {
+                let mut state = self.state.lock().unwrap();
+                loop {
+                    if let Some(f) = state.queue.pop_front() {
+                        break f
+                    }
+                    // Only exit once the queue is drained so shutdown is
+                    // graceful.
+                    if state.shutdown {
+                        return
+                    }
+                    state = self.cvar.wait(state).unwrap();
+                }
+            };
+
+            // A panicking callback shouldn't take down the worker, so we just
+            // swallow the panic here and move on to the next job.
+            drop(panic::catch_unwind(AssertUnwindSafe(|| f.call())));
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
     use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc::channel;
+    use std::thread;
 
-    use super::{Executor, Limited};
+    use super::{Executor, Limited, ThreadPool, Inline, Current};
 
     #[test]
     fn limited() {
@@ -125,4 +350,74 @@ mod tests {
         doit(Arc::new(Limited), hits.clone(), n);
         assert_eq!(hits.load(Ordering::SeqCst), n);
     }
+
+    #[test]
+    fn pool_smoke() {
+        let pool = ThreadPool::new(4);
+        let (tx, rx) = channel();
+        for i in 0..100 {
+            let tx = tx.clone();
+            pool.execute(move || tx.send(i).unwrap());
+        }
+        drop(tx);
+        let mut v = rx.iter().collect::<Vec<_>>();
+        v.sort();
+        assert_eq!(v, (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn pool_names() {
+        let pool = ThreadPool::builder().size(2).name_prefix("pool-").create();
+        let (tx, rx) = channel();
+        pool.execute(move || {
+            tx.send(thread::current().name().map(|s| s.to_string())).unwrap();
+        });
+        let name = rx.recv().unwrap().unwrap();
+        assert!(name.starts_with("pool-"));
+    }
+
+    #[test]
+    fn pool_panic() {
+        let pool = ThreadPool::new(1);
+        pool.execute(|| panic!());
+        let (tx, rx) = channel();
+        pool.execute(move || tx.send(1).unwrap());
+        assert_eq!(rx.recv().unwrap(), 1);
+    }
+
+    #[test]
+    fn pool_shutdown_drains() {
+        let pool = ThreadPool::new(1);
+        let hits = Arc::new(AtomicUsize::new(0));
+        for _ in 0..100 {
+            let hits = hits.clone();
+            pool.execute(move || {
+                hits.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        pool.shutdown();
+        assert_eq!(hits.load(Ordering::SeqCst), 100);
+
+        // after shutdown work is run inline
+        let hits2 = hits.clone();
+        pool.execute(move || {
+            hits2.fetch_add(1, Ordering::SeqCst);
+        });
+        assert_eq!(hits.load(Ordering::SeqCst), 101);
+    }
+
+    #[test]
+    fn current_enter() {
+        let hits = Arc::new(AtomicUsize::new(0));
+        assert!(super::current().inner.is_none());
+        Current::new(Arc::new(Inline)).enter(|| {
+            assert!(super::current().inner.is_some());
+            let hits = hits.clone();
+            super::current().execute(move || {
+                hits.fetch_add(1, Ordering::SeqCst);
+            });
+        });
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+        assert!(super::current().inner.is_none());
+    }
 }
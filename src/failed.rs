@@ -1,7 +1,7 @@
 use std::marker;
 
 use {Future, PollResult, PollError, Callback};
-use executor::{Executor, DEFAULT};
+use executor::{self, Executor};
 use util;
 
 pub struct Failed<T, E> {
@@ -23,12 +23,17 @@ impl<T, E> Future for Failed<T, E>
     type Item = T;
     type Error = E;
 
+    fn poll(&mut self) -> Option<PollResult<T, E>> {
+        Some(util::opt2poll(self.e.take())
+                  .and_then(|e| Err(PollError::Other(e))))
+    }
+
     fn schedule<G>(&mut self, g: G)
         where G: FnOnce(PollResult<T, E>) + Send + 'static
     {
         let res = util::opt2poll(self.e.take())
                        .and_then(|e| Err(PollError::Other(e)));
-        DEFAULT.execute(|| g(res))
+        executor::current().execute(|| g(res))
     }
 
     fn schedule_boxed(&mut self, cb: Box<Callback<T, E>>) {
@@ -1,7 +1,7 @@
 use std::marker;
 
 use {Future, PollResult, Callback};
-use executor::{Executor, DEFAULT};
+use executor::{self, Executor};
 use util;
 
 pub struct Finished<T, E> {
@@ -23,11 +23,15 @@ impl<T, E> Future for Finished<T, E>
     type Item = T;
     type Error = E;
 
+    fn poll(&mut self) -> Option<PollResult<T, E>> {
+        Some(util::opt2poll(self.t.take()))
+    }
+
     fn schedule<G>(&mut self, g: G)
         where G: FnOnce(PollResult<T, E>) + Send + 'static
     {
         let res = util::opt2poll(self.t.take());
-        DEFAULT.execute(|| g(res));
+        executor::current().execute(|| g(res));
     }
 
     fn schedule_boxed(&mut self, cb: Box<Callback<T, E>>) {
@@ -22,18 +22,28 @@ impl<A> Future for Flatten<A>
     type Item = <<A as Future>::Item as IntoFuture>::Item;
     type Error = <<A as Future>::Item as IntoFuture>::Error;
 
+    fn poll(&mut self) -> Option<PollResult<Self::Item, Self::Error>> {
+        self.state.poll(flatten)
+    }
+
     fn schedule<G>(&mut self, g: G)
         where G: FnOnce(PollResult<Self::Item, Self::Error>) + Send + 'static
     {
-        self.state.schedule(g, |a, ()| {
-            match a {
-                Ok(item) => Ok(Err(item.into_future())),
-                Err(e) => Err(e.map(From::from)),
-            }
-        })
+        self.state.schedule(g, flatten)
     }
 
     fn schedule_boxed(&mut self, cb: Box<Callback<Self::Item, Self::Error>>) {
         self.schedule(|r| cb.call(r))
     }
 }
+
+fn flatten<T, E>(a: PollResult<T, E>, (): ())
+                 -> PollResult<Result<T::Item, T::Future>, T::Error>
+    where T: IntoFuture,
+          T::Error: From<E>,
+{
+    match a {
+        Ok(item) => Ok(Err(item.into_future())),
+        Err(e) => Err(e.map(From::from)),
+    }
+}
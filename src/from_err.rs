@@ -0,0 +1,42 @@
+use std::marker;
+
+use {PollResult, Future, Callback};
+use executor::{self, Executor};
+
+pub struct FromErr<A, E> {
+    future: A,
+    _e: marker::PhantomData<E>,
+}
+
+pub fn new<A, E>(future: A) -> FromErr<A, E> {
+    FromErr {
+        future: future,
+        _e: marker::PhantomData,
+    }
+}
+
+impl<A, E> Future for FromErr<A, E>
+    where A: Future,
+          E: From<A::Error> + Send + 'static,
+{
+    type Item = A::Item;
+    type Error = E;
+
+    fn poll(&mut self) -> Option<PollResult<A::Item, E>> {
+        self.future.poll().map(|r| r.map_err(|e| e.map(From::from)))
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<A::Item, E>) + Send + 'static
+    {
+        let executor = executor::current();
+        self.future.schedule(move |result| {
+            let r = result.map_err(|e| e.map(From::from));
+            executor.execute(|| g(r))
+        })
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<A::Item, E>>) {
+        self.schedule(|r| cb.call(r));
+    }
+}
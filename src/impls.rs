@@ -4,6 +4,10 @@ impl<F: ?Sized + Future> Future for Box<F> {
     type Item = F::Item;
     type Error = F::Error;
 
+    fn poll(&mut self) -> Option<PollResult<F::Item, F::Error>> {
+        (**self).poll()
+    }
+
     fn schedule<G>(&mut self, g: G)
         where G: FnOnce(PollResult<F::Item, F::Error>) + Send + 'static,
     {
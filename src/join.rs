@@ -3,7 +3,7 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 use {PollResult, Callback, Future, PollError};
-use executor::{Executor, DEFAULT};
+use executor::{self, Executor, Current};
 use lock::Lock;
 use util;
 
@@ -16,7 +16,7 @@ pub fn new<A, B>(a: A, b: B) -> Join<A, B>
           B: Future<Error=A::Error>,
 {
     Join {
-        state: State::Start(a, b),
+        state: State::Start(a, b, None, None),
     }
 }
 
@@ -27,6 +27,28 @@ impl<A, B> Future for Join<A, B>
     type Item = (A::Item, B::Item);
     type Error = A::Error;
 
+    fn poll(&mut self) -> Option<PollResult<Self::Item, Self::Error>> {
+        let res = match self.state {
+            State::Start(ref mut a, ref mut b, ref mut a_val, ref mut b_val) => {
+                if let Some(e) = poll_half(a, a_val) {
+                    Err(e)
+                } else if let Some(e) = poll_half(b, b_val) {
+                    Err(e)
+                } else if a_val.is_some() && b_val.is_some() {
+                    Ok((a_val.take().unwrap(), b_val.take().unwrap()))
+                } else {
+                    return None
+                }
+            }
+            State::Canceled => Err(PollError::Canceled),
+            State::Scheduled(..) |
+            State::Done => return Some(Err(util::reused())),
+        };
+        // Dropping the futures here cancels the other half if we hit an error
+        self.state = State::Done;
+        Some(res)
+    }
+
     fn schedule<G>(&mut self, g: G)
         where G: FnOnce(PollResult<Self::Item, Self::Error>) + Send + 'static
     {
@@ -35,14 +57,19 @@ impl<A, B> Future for Join<A, B>
     }
 
     fn schedule_boxed(&mut self, cb: Box<Callback<Self::Item, Self::Error>>) {
-        let (mut a, mut b) = match mem::replace(&mut self.state, State::Canceled) {
-            State::Start(a, b) => (a, b),
+        let (mut a, mut b, a_val, b_val) = match mem::replace(&mut self.state,
+                                                              State::Canceled) {
+            State::Start(a, b, a_val, b_val) => (a, b, a_val, b_val),
             State::Canceled => {
-                return DEFAULT.execute(|| cb.call(Err(PollError::Canceled)))
+                return executor::current().execute(|| cb.call(Err(PollError::Canceled)))
             }
             State::Scheduled(s) => {
                 self.state = State::Scheduled(s);
-                return DEFAULT.execute(|| cb.call(Err(util::reused())))
+                return executor::current().execute(|| cb.call(Err(util::reused())))
+            }
+            State::Done => {
+                self.state = State::Done;
+                return executor::current().execute(|| cb.call(Err(util::reused())))
             }
         };
 
@@ -53,12 +80,25 @@ impl<A, B> Future for Join<A, B>
             b_val: Lock::new(None),
             state: AtomicUsize::new(0),
             cb: Lock::new(Some(cb)),
+            executor: executor::current(),
         });
         let data2 = data1.clone();
         let data3 = data2.clone();
 
-        a.schedule(move |result| data1.finish(&data1.a_val, result, A_OK));
-        b.schedule(move |result| data2.finish(&data2.b_val, result, B_OK));
+        // If either half already finished from a previous `poll` then we
+        // just feed its value straight in rather than scheduling it.
+        match a_val {
+            Some(v) => data1.finish(&data1.a_val, Ok(v), A_OK),
+            None => {
+                a.schedule(move |result| data1.finish(&data1.a_val, result, A_OK))
+            }
+        }
+        match b_val {
+            Some(v) => data2.finish(&data2.b_val, Ok(v), B_OK),
+            None => {
+                b.schedule(move |result| data2.finish(&data2.b_val, result, B_OK))
+            }
+        }
         *data3.futures.try_lock().expect("[j] futures locked") = Some((a, b));
 
         // Tell the state that we've now placed the futures so they can be
@@ -74,9 +114,22 @@ impl<A, B> Future for Join<A, B>
 }
 
 enum State<A, B> where A: Future, B: Future<Error=A::Error> {
-    Start(A, B),
+    Start(A, B, Option<A::Item>, Option<B::Item>),
     Scheduled(Arc<Scheduled<A, B>>),
     Canceled,
+    Done,
+}
+
+fn poll_half<F: Future>(f: &mut F,
+                        slot: &mut Option<F::Item>) -> Option<PollError<F::Error>> {
+    if slot.is_none() {
+        match f.poll() {
+            Some(Ok(v)) => *slot = Some(v),
+            Some(Err(e)) => return Some(e),
+            None => {}
+        }
+    }
+    None
 }
 
 const A_OK: usize = 1 << 0;
@@ -94,6 +147,7 @@ struct Scheduled<A, B>
     b_val: Lock<Option<B::Item>>,
     state: AtomicUsize,
     cb: Lock<Option<Box<Callback<(A::Item, B::Item), A::Error>>>>,
+    executor: Current,
 }
 
 impl<A, B> Scheduled<A, B>
@@ -152,13 +206,13 @@ impl<A, B> Scheduled<A, B>
             if old & SET != 0 {
                 self.cancel();
             }
-            DEFAULT.execute(|| cb.call(Err(e)))
+            self.executor.execute(|| cb.call(Err(e)))
         } else {
             let a = self.a_val.try_lock().expect("[j] done, but a locked")
                               .take().expect("[j] done but a not here");
             let b = self.b_val.try_lock().expect("[j] done, but b locked")
                               .take().expect("[j] done but b not here");
-            DEFAULT.execute(|| cb.call(Ok((a, b))))
+            self.executor.execute(|| cb.call(Ok((a, b))))
         }
     }
 
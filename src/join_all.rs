@@ -0,0 +1,189 @@
+use std::mem;
+use std::sync::{Arc, Mutex};
+
+use executor::{self, Executor, Current};
+use {Future, IntoFuture, Callback, PollResult};
+use util;
+
+pub struct JoinAll<A: Future> {
+    state: State<A>,
+}
+
+enum State<A: Future> {
+    Start(Vec<A>, Vec<Option<A::Item>>),
+    Scheduled(Arc<Scheduled<A>>),
+    Done,
+}
+
+struct Scheduled<A: Future> {
+    inner: Mutex<Inner<A>>,
+    executor: Current,
+}
+
+struct Inner<A: Future> {
+    // Only filled in once every future has been scheduled, entries are taken
+    // out to cancel them.
+    futures: Vec<Option<A>>,
+    values: Vec<Option<A::Item>>,
+    remaining: usize,
+    cb: Option<Box<Callback<Vec<A::Item>, A::Error>>>,
+}
+
+// Creates a future which runs every future in `i` concurrently, resolving to
+// their values in the same order as `i`.
+//
+// If any future fails then the rest are canceled and the error is returned.
+pub fn join_all<I>(i: I) -> JoinAll<<I::Item as IntoFuture>::Future>
+    where I: IntoIterator,
+          I::Item: IntoFuture,
+{
+    let futures = i.into_iter()
+                   .map(IntoFuture::into_future)
+                   .collect::<Vec<_>>();
+    let values = futures.iter().map(|_| None).collect();
+    JoinAll {
+        state: State::Start(futures, values),
+    }
+}
+
+impl<A: Future> Future for JoinAll<A> {
+    type Item = Vec<A::Item>;
+    type Error = A::Error;
+
+    fn poll(&mut self) -> Option<PollResult<Self::Item, Self::Error>> {
+        let res = match self.state {
+            State::Start(ref mut futures, ref mut values) => {
+                let mut done = true;
+                let mut err = None;
+                for (f, slot) in futures.iter_mut().zip(values.iter_mut()) {
+                    if slot.is_some() {
+                        continue
+                    }
+                    match f.poll() {
+                        Some(Ok(v)) => *slot = Some(v),
+                        Some(Err(e)) => {
+                            err = Some(e);
+                            break
+                        }
+                        None => done = false,
+                    }
+                }
+                match err {
+                    Some(e) => Err(e),
+                    None if !done => return None,
+                    None => {
+                        Ok(values.iter_mut().map(|v| v.take().unwrap()).collect())
+                    }
+                }
+            }
+            State::Scheduled(..) | State::Done => Err(util::reused()),
+        };
+        // Dropping the futures here cancels the rest if we hit an error
+        if let State::Start(..) = self.state {
+            self.state = State::Done;
+        }
+        Some(res)
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<Self::Item, Self::Error>) + Send + 'static
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<Self::Item, Self::Error>>) {
+        let (mut futures, values) = match mem::replace(&mut self.state,
+                                                       State::Done) {
+            State::Start(futures, values) => (futures, values),
+            State::Scheduled(s) => {
+                self.state = State::Scheduled(s);
+                return executor::current().execute(|| cb.call(Err(util::reused())))
+            }
+            State::Done => {
+                return executor::current().execute(|| cb.call(Err(util::reused())))
+            }
+        };
+
+        // Values we already have from a previous `poll` are left alone, only
+        // the futures that haven't finished yet get scheduled.
+        let pending = values.iter().map(|v| v.is_none()).collect::<Vec<_>>();
+        let remaining = pending.iter().filter(|p| **p).count();
+        if remaining == 0 {
+            let values = values.into_iter().map(|v| v.unwrap()).collect();
+            return executor::current().execute(|| cb.call(Ok(values)))
+        }
+        let state = Arc::new(Scheduled {
+            inner: Mutex::new(Inner {
+                futures: Vec::new(),
+                values: values,
+                remaining: remaining,
+                cb: Some(cb),
+            }),
+            executor: executor::current(),
+        });
+
+        for (i, f) in futures.iter_mut().enumerate() {
+            if pending[i] {
+                let state = state.clone();
+                f.schedule(move |r| state.finish(i, r));
+            }
+        }
+
+        // If something already failed then the callback has been run, so we
+        // cancel everything rather than storing it.
+        let canceled = {
+            let mut inner = state.inner.lock().unwrap();
+            if inner.cb.is_some() {
+                inner.futures = futures.into_iter().map(Some).collect();
+                None
+            } else {
+                Some(futures)
+            }
+        };
+        drop(canceled);
+
+        self.state = State::Scheduled(state);
+    }
+}
+
+impl<A: Future> Scheduled<A> {
+    fn finish(&self, i: usize, res: PollResult<A::Item, A::Error>) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.cb.is_none() {
+            return
+        }
+        let res = match res {
+            Ok(v) => {
+                inner.values[i] = Some(v);
+                inner.remaining -= 1;
+                if inner.remaining > 0 {
+                    return
+                }
+                Ok(inner.values.iter_mut().map(|v| v.take().unwrap()).collect())
+            }
+            Err(e) => Err(e),
+        };
+        let cb = inner.cb.take().unwrap();
+        let futures = inner.futures.drain(..).collect::<Vec<_>>();
+        drop(inner);
+
+        // Canceling the rest may run their callbacks, so make sure that
+        // happens outside the lock.
+        drop(futures);
+        self.executor.execute(|| cb.call(res))
+    }
+}
+
+impl<A: Future> Drop for JoinAll<A> {
+    fn drop(&mut self) {
+        if let State::Scheduled(ref state) = self.state {
+            // Canceled futures report back through `finish`, which is what
+            // ends up running the callback.
+            let futures = {
+                let mut inner = state.inner.lock().unwrap();
+                inner.futures.drain(..).collect::<Vec<_>>()
+            };
+            drop(futures);
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use std::mem;
 
 use {Future, PollResult, Callback, IntoFuture};
-use executor::{Executor, DEFAULT};
+use executor::{self, Executor};
 use util;
 
 pub struct Lazy<F, R> {
@@ -28,6 +28,26 @@ impl<F, R> Future for Lazy<F, R::Future>
     type Item = R::Item;
     type Error = R::Error;
 
+    fn poll(&mut self) -> Option<PollResult<R::Item, R::Error>> {
+        match mem::replace(&mut self.inner, _Lazy::Moved) {
+            _Lazy::First(f) => {
+                let mut f = match util::recover(f) {
+                    Ok(f) => f.into_future(),
+                    Err(e) => return Some(Err(e)),
+                };
+                let ret = f.poll();
+                self.inner = _Lazy::Second(f);
+                ret
+            }
+            _Lazy::Second(mut f) => {
+                let ret = f.poll();
+                self.inner = _Lazy::Second(f);
+                ret
+            }
+            _Lazy::Moved => Some(Err(util::reused())),
+        }
+    }
+
     fn schedule<G>(&mut self, g: G)
         where G: FnOnce(PollResult<R::Item, R::Error>) + Send + 'static
     {
@@ -35,17 +55,20 @@ impl<F, R> Future for Lazy<F, R::Future>
             _Lazy::First(f) => {
                 let mut f = match util::recover(f) {
                     Ok(f) => f.into_future(),
-                    Err(e) => return DEFAULT.execute(|| g(Err(e))),
+                    Err(e) => return executor::current().execute(|| g(Err(e))),
                 };
                 f.schedule(g);
                 self.inner = _Lazy::Second(f);
             }
-            _Lazy::Second(f) => {
+            // If we were previously polled then the future has been created
+            // but not yet scheduled, so it's the one that gets to decide
+            // whether this is a reuse.
+            _Lazy::Second(mut f) => {
+                f.schedule(g);
                 self.inner = _Lazy::Second(f);
-                DEFAULT.execute(|| g(Err(util::reused())))
             }
             _Lazy::Moved => {
-                DEFAULT.execute(|| g(Err(util::reused())))
+                executor::current().execute(|| g(Err(util::reused())))
             }
         };
     }
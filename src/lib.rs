@@ -1,11 +1,14 @@
+use std::error::Error;
+use std::time::Duration;
+
 mod lock;
-mod slot;
 mod util;
 
 mod error;
-pub use error::{PollError, PollResult};
+pub use error::{PollError, PollResult, WaitError, Reused};
 
 pub mod executor;
+pub mod slot;
 
 // Primitive futures
 mod collect;
@@ -13,46 +16,85 @@ mod done;
 mod empty;
 mod failed;
 mod finished;
+mod join_all;
 mod lazy;
 mod promise;
-pub use collect::{collect, Collect};
+mod select_all;
+pub use collect::{collect, Collect, collect_concurrent, CollectConcurrent};
 pub use done::{done, Done};
 pub use empty::{empty, Empty};
 pub use failed::{failed, Failed};
 pub use finished::{finished, Finished};
+pub use join_all::{join_all, JoinAll};
 pub use lazy::{lazy, Lazy};
 pub use promise::{promise, Promise, Complete};
+pub use select_all::{select_all, SelectAll, SelectAllNext};
 
 // combinators
+mod abortable;
 mod and_then;
+mod err_into;
 mod flatten;
+mod from_err;
 mod join;
 mod map;
 mod map_err;
+mod on;
 mod or_else;
+mod retry;
 mod select;
+mod shared;
 mod then;
+mod with_local;
+pub use abortable::{Abortable, AbortHandle};
 pub use and_then::AndThen;
+pub use err_into::ErrInto;
 pub use flatten::Flatten;
+pub use from_err::FromErr;
 pub use join::Join;
 pub use map::Map;
 pub use map_err::MapErr;
+pub use on::On;
 pub use or_else::OrElse;
+pub use retry::{retry, Retry, RetryPolicy};
 pub use select::{Select, SelectNext};
+pub use shared::Shared;
 pub use then::Then;
+pub use with_local::WithLocal;
 
 // streams
 pub mod stream;
 
+// synchronization
+pub mod sync;
+
+// time
+pub mod timer;
+
+// task-local storage
+pub mod task;
+
 // impl details
 mod chain;
 mod impls;
 mod forget;
+mod wait;
 
 pub trait Future: Send + 'static {
     type Item: Send + 'static;
     type Error: Send + 'static;
 
+    // Contract: never blocks, returns `None` if the value isn't ready yet.
+    //
+    // Semantics:
+    // - If the future is ready, `Some` is returned with its value and the
+    //   future is consumed.
+    // - If the future is not ready, `None` is returned and the future may be
+    //   polled again or scheduled later.
+    // - If the future has already been consumed or scheduled, `Some` is
+    //   returned with a "panicked" value.
+    fn poll(&mut self) -> Option<PollResult<Self::Item, Self::Error>>;
+
     // Contract: the closure `f` is guaranteed to get called
     //
     // Semantics:
@@ -90,6 +132,25 @@ pub trait Future: Send + 'static {
         assert_future::<Self::Item, E, _>(map_err::new(self, f))
     }
 
+    // Converts this future's error into any type which can be created from
+    // it, as `map_err(From::from)` would.
+    #[allow(clippy::wrong_self_convention)]
+    fn from_err<E>(self) -> FromErr<Self, E>
+        where E: From<Self::Error> + Send + 'static,
+              Self: Sized,
+    {
+        assert_future::<Self::Item, E, _>(from_err::new(self))
+    }
+
+    // Like `from_err`, but for error types which only implement `Into`.
+    fn err_into<E>(self) -> ErrInto<Self, E>
+        where Self::Error: Into<E>,
+              E: Send + 'static,
+              Self: Sized,
+    {
+        assert_future::<Self::Item, E, _>(err_into::new(self))
+    }
+
     fn then<F, B>(self, f: F) -> Then<Self, B, F>
         where F: FnOnce(Result<Self::Item, Self::Error>) -> B + Send + 'static,
               B: IntoFuture,
@@ -143,11 +204,79 @@ pub trait Future: Send + 'static {
                         _>(f)
     }
 
+    // Returns a handle which can be cloned to deliver this future's result to
+    // any number of waiters. This future is only canceled once every handle
+    // has been dropped.
+    fn shared(self) -> Shared<Self>
+        where Self::Item: Clone,
+              Self::Error: Clone,
+              Self: Sized
+    {
+        assert_future::<Self::Item, Self::Error, _>(shared::new(self))
+    }
+
+    // Returns a wrapper around this future along with a handle which can be
+    // used to cancel it from any thread.
+    fn abortable(self) -> (Abortable<Self>, AbortHandle) where Self: Sized {
+        abortable::new(self)
+    }
+
+    // Converts this future into a stream which yields its result and then
+    // ends.
+    fn into_stream(self) -> stream::Once<Self> where Self: Sized {
+        stream::once(self)
+    }
+
+    // Runs this future's combinators, and then its callback, on `executor`
+    // rather than on the default executor.
+    fn on<E>(self, executor: E) -> On<Self, E>
+        where E: executor::Executor,
+              Self: Sized,
+    {
+        assert_future::<Self::Item, Self::Error, _>(on::new(self, executor))
+    }
+
+    // Sets `value` as the task-local of type `T` for this future, so that the
+    // callbacks of any combinators it's made up of can see it. The value is
+    // no longer set once this future's result is handed on.
+    fn with_local<T>(self, value: T) -> WithLocal<Self, T>
+        where T: Send + Sync + 'static,
+              Self: Sized,
+    {
+        assert_future::<Self::Item, Self::Error, _>(with_local::new(self, value))
+    }
+
     fn forget(self) where Self: Sized {
         forget::forget(self);
     }
+
+    // Resolves to `TimeoutError::TimedOut` if this future doesn't finish
+    // within `dur`, canceling it.
+    fn timeout(self, dur: Duration) -> timer::Timeout<Self>
+        where Self: Sized
+    {
+        timer::timeout(self, dur)
+    }
+
+    // Blocks the current thread until this future is resolved.
+    //
+    // Panics from the future are resumed on this thread and a canceled future
+    // is reported as `WaitError::Canceled`.
+    fn wait(self) -> Result<Self::Item, WaitError<Self::Error>>
+        where Self: Sized
+    {
+        wait::wait(self)
+    }
 }
 
+// An error of any type, which most error types can be converted into with
+// `From`.
+pub type BoxError = Box<Error + Send + Sync>;
+
+// A boxed future of any type which fails with a `BoxError`, so futures with
+// different error types can be chained together with just `from_err`.
+pub type BoxFuture<T> = Box<Future<Item=T, Error=BoxError>>;
+
 fn assert_future<A, B, F>(t: F) -> F
     where F: Future<Item=A, Error=B>,
           A: Send + 'static,
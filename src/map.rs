@@ -1,5 +1,5 @@
 use {Future, PollResult, Callback};
-use executor::{Executor, DEFAULT};
+use executor::{self, Executor};
 use util;
 
 pub struct Map<A, F> {
@@ -22,6 +22,17 @@ impl<U, A, F> Future for Map<A, F>
     type Item = U;
     type Error = A::Error;
 
+    fn poll(&mut self) -> Option<PollResult<U, A::Error>> {
+        let f = &mut self.f;
+        self.future.poll().map(|result| {
+            let f = match util::opt2poll(f.take()) {
+                Ok(f) => f,
+                Err(e) => return Err(e),
+            };
+            result.and_then(|e| util::recover(|| f(e)))
+        })
+    }
+
     fn schedule<G>(&mut self, g: G)
         where G: FnOnce(PollResult<U, A::Error>) + Send + 'static
     {
@@ -29,9 +40,10 @@ impl<U, A, F> Future for Map<A, F>
             Ok(f) => f,
             Err(e) => return g(Err(e)),
         };
-        self.future.schedule(|result| {
+        let executor = executor::current();
+        self.future.schedule(move |result| {
             let res = result.and_then(|e| util::recover(|| f(e)));
-            DEFAULT.execute(|| g(res))
+            executor.execute(|| g(res))
         })
     }
 
@@ -1,5 +1,5 @@
 use {PollResult, Future, Callback, PollError};
-use executor::{Executor, DEFAULT};
+use executor::{self, Executor};
 use util;
 
 pub struct MapErr<A, F> {
@@ -22,24 +22,28 @@ impl<U, A, F> Future for MapErr<A, F>
     type Item = A::Item;
     type Error = U;
 
+    fn poll(&mut self) -> Option<PollResult<A::Item, U>> {
+        let f = &mut self.f;
+        self.future.poll().map(|result| {
+            match util::opt2poll(f.take()) {
+                Ok(f) => map_err(result, f),
+                Err(e) => Err(e),
+            }
+        })
+    }
+
     fn schedule<G>(&mut self, g: G)
         where G: FnOnce(PollResult<A::Item, U>) + Send + 'static
     {
         let f = match util::opt2poll(self.f.take()) {
             Ok(f) => f,
-            Err(e) => return DEFAULT.execute(|| g(Err(e))),
+            Err(e) => return executor::current().execute(|| g(Err(e))),
         };
 
-        self.future.schedule(|result| {
-            let r = match (result, f) {
-                (Err(PollError::Other(e)), f) => {
-                    util::recover(|| f(e)).and_then(|e| Err(PollError::Other(e)))
-                }
-                (Err(PollError::Panicked(e)), _) => Err(PollError::Panicked(e)),
-                (Err(PollError::Canceled), _) => Err(PollError::Canceled),
-                (Ok(e), _) => Ok(e),
-            };
-            DEFAULT.execute(|| g(r))
+        let executor = executor::current();
+        self.future.schedule(move |result| {
+            let r = map_err(result, f);
+            executor.execute(|| g(r))
         })
     }
 
@@ -48,3 +52,18 @@ impl<U, A, F> Future for MapErr<A, F>
     }
 }
 
+fn map_err<T, E, F, U>(result: PollResult<T, E>, f: F) -> PollResult<T, U>
+    where F: FnOnce(E) -> U + Send + 'static,
+          E: Send + 'static,
+          U: Send + 'static,
+{
+    match result {
+        Err(PollError::Other(e)) => {
+            util::recover(|| f(e)).and_then(|e| Err(PollError::Other(e)))
+        }
+        Err(PollError::Panicked(e)) => Err(PollError::Panicked(e)),
+        Err(PollError::Canceled) => Err(PollError::Canceled),
+        Ok(e) => Ok(e),
+    }
+}
+
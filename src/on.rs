@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use {Future, PollResult, Callback};
+use executor::{Executor, Current};
+
+pub struct On<A, E> {
+    future: A,
+    executor: Arc<E>,
+}
+
+pub fn new<A, E>(future: A, executor: E) -> On<A, E> {
+    On {
+        future: future,
+        executor: Arc::new(executor),
+    }
+}
+
+impl<A, E> Future for On<A, E>
+    where A: Future,
+          E: Executor,
+{
+    type Item = A::Item;
+    type Error = A::Error;
+
+    fn poll(&mut self) -> Option<PollResult<A::Item, A::Error>> {
+        self.future.poll()
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<A::Item, A::Error>) + Send + 'static
+    {
+        let executor = Current::new(self.executor.clone());
+        let executor2 = executor.clone();
+        let future = &mut self.future;
+        executor.enter(|| {
+            future.schedule(move |result| executor2.execute(move || g(result)))
+        })
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<A::Item, A::Error>>) {
+        self.schedule(|r| cb.call(r))
+    }
+}
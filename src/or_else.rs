@@ -24,22 +24,33 @@ impl<A, B, F> Future for OrElse<A, B, F>
     type Item = B::Item;
     type Error = B::Error;
 
+    fn poll(&mut self) -> Option<PollResult<B::Item, B::Error>> {
+        self.state.poll(or_else)
+    }
+
     fn schedule<G>(&mut self, g: G)
         where G: FnOnce(PollResult<B::Item, B::Error>) + Send + 'static
     {
-        self.state.schedule(g, |a, f| {
-            match a {
-                Ok(item) => Ok(Ok(item)),
-                Err(PollError::Panicked(d)) => Err(PollError::Panicked(d)),
-                Err(PollError::Canceled) => Err(PollError::Canceled),
-                Err(PollError::Other(e)) => {
-                    util::recover(|| f(e)).map(|e| Err(e.into_future()))
-                }
-            }
-        })
+        self.state.schedule(g, or_else)
     }
 
     fn schedule_boxed(&mut self, cb: Box<Callback<B::Item, B::Error>>) {
         self.schedule(|r| cb.call(r))
     }
 }
+
+fn or_else<T, E, B, F>(a: PollResult<T, E>, f: F)
+                       -> PollResult<Result<T, B::Future>, B::Error>
+    where F: FnOnce(E) -> B + Send + 'static,
+          B: IntoFuture<Item=T>,
+          E: Send + 'static,
+{
+    match a {
+        Ok(item) => Ok(Ok(item)),
+        Err(PollError::Panicked(d)) => Err(PollError::Panicked(d)),
+        Err(PollError::Canceled) => Err(PollError::Canceled),
+        Err(PollError::Other(e)) => {
+            util::recover(|| f(e)).map(|e| Err(e.into_future()))
+        }
+    }
+}
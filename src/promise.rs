@@ -1,8 +1,9 @@
 use std::mem;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use {Future, Callback, PollResult, PollError};
 use slot::{Slot, Token};
+use task::Locals;
 use util;
 
 pub struct Promise<T, E>
@@ -32,6 +33,14 @@ pub struct Complete<T, E>
 
 struct Inner<T, E> {
     slot: Slot<Option<Result<T, E>>>,
+    cancel: Mutex<Cancel>,
+}
+
+struct Cancel {
+    canceled: bool,
+    // completed once the `Promise` goes away, or dropped (canceling them in
+    // turn) once the `Complete` does
+    waiters: Vec<Complete<(), ()>>,
 }
 
 pub fn promise<T, E>() -> (Promise<T, E>, Complete<T, E>)
@@ -40,6 +49,10 @@ pub fn promise<T, E>() -> (Promise<T, E>, Complete<T, E>)
 {
     let inner = Arc::new(Inner {
         slot: Slot::new(None),
+        cancel: Mutex::new(Cancel {
+            canceled: false,
+            waiters: Vec::new(),
+        }),
     });
     (Promise { state: _Promise::Start(inner.clone()) },
      Complete { inner: inner, completed: false })
@@ -59,12 +72,41 @@ impl<T, E> Complete<T, E>
         self.complete(Some(Err(e)))
     }
 
+    // Returns whether the `Promise` has been dropped without taking the
+    // value, in which case there's no point in producing one.
+    pub fn is_canceled(&self) -> bool {
+        self.inner.cancel.lock().unwrap().canceled
+    }
+
+    // Returns a future which resolves once the `Promise` is dropped without
+    // taking the value, so work on producing it can be abandoned.
+    //
+    // If this `Complete` finishes or is dropped first then the future is
+    // canceled instead.
+    pub fn on_cancel(&self) -> Promise<(), ()> {
+        let (p, c) = promise();
+        let mut cancel = self.inner.cancel.lock().unwrap();
+        if cancel.canceled {
+            drop(cancel);
+            c.finish(());
+        } else {
+            cancel.waiters.push(c);
+        }
+        p
+    }
+
     fn complete(&mut self, t: Option<Result<T, E>>) {
+        let waiters = {
+            let mut cancel = self.inner.cancel.lock().unwrap();
+            cancel.waiters.drain(..).collect::<Vec<_>>()
+        };
+        drop(waiters);
+
         if let Err(e) = self.inner.slot.try_produce(t) {
             self.inner.slot.on_empty(|slot| {
                 slot.try_produce(e.into_inner()).ok()
                     .expect("advertised as empty but wasn't");
-            });
+            }).unwrap();
         }
     }
 }
@@ -84,6 +126,31 @@ impl<T: Send + 'static, E: Send + 'static> Future for Promise<T, E> {
     type Item = T;
     type Error = E;
 
+    fn poll(&mut self) -> Option<PollResult<T, E>> {
+        let inner = match mem::replace(&mut self.state, _Promise::Used) {
+            _Promise::Start(inner) => inner,
+            _Promise::Canceled => return Some(Err(PollError::Canceled)),
+            _Promise::Used => return Some(Err(util::reused())),
+            _Promise::Scheduled(s, token) => {
+                self.state = _Promise::Scheduled(s, token);
+                return Some(Err(util::reused()))
+            }
+        };
+        match inner.slot.try_consume() {
+            Ok(Some(Ok(e))) => Some(Ok(e)),
+            Ok(Some(Err(e))) => Some(Err(PollError::Other(e))),
+
+            // canceled because the `Complete` handle dropped
+            Ok(None) => Some(Err(PollError::Canceled)),
+
+            // not ready yet, so put ourselves back in the start state
+            Err(..) => {
+                self.state = _Promise::Start(inner);
+                None
+            }
+        }
+    }
+
     fn schedule<F>(&mut self, f: F)
         where F: FnOnce(PollResult<T, E>) + Send + 'static
     {
@@ -96,18 +163,22 @@ impl<T: Send + 'static, E: Send + 'static> Future for Promise<T, E> {
                 return f(Err(util::reused()))
             }
         };
-        let token = inner.slot.on_full(|slot| {
-            match slot.try_consume() {
-                Ok(Some(Ok(e))) => f(Ok(e)),
-                Ok(Some(Err(e))) => f(Err(PollError::Other(e))),
+        // The callback runs on whichever thread completes us, so it takes our
+        // task-locals along rather than seeing that thread's.
+        let locals = Locals::current();
+        let token = inner.slot.on_full(move |slot| {
+            let res = match slot.try_consume() {
+                Ok(Some(Ok(e))) => Ok(e),
+                Ok(Some(Err(e))) => Err(PollError::Other(e)),
 
                 // canceled because the `Complete` handle dropped
-                Ok(None) => f(Err(PollError::Canceled)),
+                Ok(None) => Err(PollError::Canceled),
 
                 // canceled via Drop
-                Err(..) => f(Err(PollError::Canceled)),
-            }
-        });
+                Err(..) => Err(PollError::Canceled),
+            };
+            locals.enter(|| f(res))
+        }).unwrap();
         self.state = _Promise::Scheduled(inner, token);
     }
 
@@ -121,13 +192,23 @@ impl<T, E> Drop for Promise<T, E>
           E: Send + 'static,
 {
     fn drop(&mut self) {
-        match mem::replace(&mut self.state, _Promise::Canceled) {
-            _Promise::Start(..) => {}
-            _Promise::Canceled => {}
-            _Promise::Used => {}
+        let inner = match mem::replace(&mut self.state, _Promise::Canceled) {
+            _Promise::Start(inner) => inner,
+            _Promise::Canceled => return,
+            _Promise::Used => return,
             _Promise::Scheduled(s, token) => {
                 s.slot.cancel(token);
+                s
             }
+        };
+        // Let the `Complete` side know that nobody's listening any more
+        let waiters = {
+            let mut cancel = inner.cancel.lock().unwrap();
+            cancel.canceled = true;
+            cancel.waiters.drain(..).collect::<Vec<_>>()
+        };
+        for waiter in waiters {
+            waiter.finish(());
         }
     }
 }
@@ -0,0 +1,376 @@
+use std::cmp;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use executor::{self, Executor, Current};
+use timer::{self, Delay};
+use {Future, IntoFuture, Callback, PollResult, PollError};
+use util;
+
+// Describes how many times, how often, and on which errors a `Retry` future
+// should re-invoke its factory.
+pub struct RetryPolicy<E> {
+    backoff: Backoff,
+    max_attempts: Option<usize>,
+    retryable: Box<FnMut(&E) -> bool + Send>,
+}
+
+enum Backoff {
+    Fixed(Duration),
+    Exponential(Duration, Duration),
+    Jittered(Duration, Duration),
+}
+
+pub struct Retry<F, R>
+    where F: FnMut() -> R + Send + 'static,
+          R: IntoFuture,
+{
+    state: State<F, R>,
+}
+
+enum State<F, R>
+    where F: FnMut() -> R + Send + 'static,
+          R: IntoFuture,
+{
+    Local(Step<R::Future>, Context<F, R::Error>),
+    Scheduled(Arc<Scheduled<R::Future>>),
+    Done,
+}
+
+// What a retry is currently doing: about to invoke the factory, waiting on an
+// attempt, or backing off before the next attempt.
+enum Step<A> {
+    Start,
+    Attempt(A),
+    Sleep(Delay),
+}
+
+struct Context<F, E> {
+    factory: Option<F>,
+    policy: RetryPolicy<E>,
+    attempts: usize,
+    steps: usize,
+}
+
+struct Scheduled<A> {
+    // The in-flight step along with how many steps have been taken to get
+    // there (or CANCELED), so a step which finished synchronously never
+    // clobbers the one which replaced it.
+    slot: Mutex<(usize, Option<Step<A>>)>,
+    executor: Current,
+}
+
+const CANCELED: usize = !0;
+
+// Creates a future which resolves to the first successful result of a future
+// returned by `factory`, invoking `factory` again after a backoff whenever the
+// previous attempt fails with an error `policy` deems retryable.
+//
+// Panics and cancellations are never retried. Dropping the returned future
+// cancels whichever attempt or backoff is currently in flight.
+pub fn retry<F, R>(policy: RetryPolicy<R::Error>, factory: F) -> Retry<F, R>
+    where F: FnMut() -> R + Send + 'static,
+          R: IntoFuture,
+{
+    Retry {
+        state: State::Local(Step::Start, Context {
+            factory: Some(factory),
+            policy: policy,
+            attempts: 0,
+            steps: 0,
+        }),
+    }
+}
+
+impl<E> RetryPolicy<E> {
+    // Waits `delay` between each attempt.
+    pub fn fixed(delay: Duration) -> RetryPolicy<E> {
+        RetryPolicy::new(Backoff::Fixed(delay))
+    }
+
+    // Waits `initial` after the first failure, doubling the delay after each
+    // subsequent failure up to `max`.
+    pub fn exponential(initial: Duration, max: Duration) -> RetryPolicy<E> {
+        RetryPolicy::new(Backoff::Exponential(initial, max))
+    }
+
+    // Like `exponential`, but waits a uniformly random duration between zero
+    // and the exponential delay to avoid retrying in lockstep with others.
+    pub fn jittered(initial: Duration, max: Duration) -> RetryPolicy<E> {
+        RetryPolicy::new(Backoff::Jittered(initial, max))
+    }
+
+    fn new(backoff: Backoff) -> RetryPolicy<E> {
+        RetryPolicy {
+            backoff: backoff,
+            max_attempts: None,
+            retryable: Box::new(|_| true),
+        }
+    }
+
+    // Gives up after `n` attempts in total, resolving to the last error.
+    pub fn max_attempts(mut self, n: usize) -> RetryPolicy<E> {
+        self.max_attempts = Some(n);
+        self
+    }
+
+    // Only retries errors for which `f` returns `true`; any other error is
+    // returned immediately.
+    pub fn retry_if<P>(mut self, f: P) -> RetryPolicy<E>
+        where P: FnMut(&E) -> bool + Send + 'static,
+    {
+        self.retryable = Box::new(f);
+        self
+    }
+
+    // Returns how long to wait before trying again, or `None` if `err` should
+    // be returned after `attempts` attempts.
+    fn next(&mut self, attempts: usize, err: &E) -> Option<Duration> {
+        if let Some(max) = self.max_attempts {
+            if attempts >= max {
+                return None
+            }
+        }
+        if !(self.retryable)(err) {
+            return None
+        }
+        Some(match self.backoff {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential(initial, max) => {
+                exponential(initial, max, attempts)
+            }
+            Backoff::Jittered(initial, max) => {
+                jitter(exponential(initial, max, attempts))
+            }
+        })
+    }
+}
+
+fn exponential(initial: Duration, max: Duration, attempts: usize) -> Duration {
+    let mut delay = initial;
+    for _ in 1..attempts {
+        match delay.checked_mul(2) {
+            Some(d) if d < max => delay = d,
+            _ => return max,
+        }
+    }
+    cmp::min(delay, max)
+}
+
+fn jitter(max: Duration) -> Duration {
+    // `RandomState` is randomly keyed, which is plenty of randomness for
+    // spreading out retries without pulling in an rng.
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u32(max.subsec_nanos());
+    let max = max.as_secs() * 1_000_000_000 + max.subsec_nanos() as u64;
+    let nanos = hasher.finish() % (max + 1);
+    Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32)
+}
+
+impl<F, R> Future for Retry<F, R>
+    where F: FnMut() -> R + Send + 'static,
+          R: IntoFuture,
+{
+    type Item = R::Item;
+    type Error = R::Error;
+
+    fn poll(&mut self) -> Option<PollResult<R::Item, R::Error>> {
+        let res = match self.state {
+            State::Local(ref mut step, ref mut cx) => {
+                loop {
+                    let next = match *step {
+                        Step::Start => {
+                            match cx.start() {
+                                Ok(f) => Step::Attempt(f),
+                                Err(e) => break Err(e),
+                            }
+                        }
+                        Step::Attempt(ref mut f) => {
+                            match f.poll() {
+                                Some(res) => {
+                                    match cx.attempted(res) {
+                                        Ok(dur) => backoff(dur),
+                                        Err(res) => break res,
+                                    }
+                                }
+                                None => return None,
+                            }
+                        }
+                        Step::Sleep(ref mut d) => {
+                            match d.poll() {
+                                Some(_) => Step::Start,
+                                None => return None,
+                            }
+                        }
+                    };
+                    *step = next;
+                }
+            }
+            State::Scheduled(..) | State::Done => Err(util::reused()),
+        };
+        if let State::Local(..) = self.state {
+            self.state = State::Done;
+        }
+        Some(res)
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<R::Item, R::Error>) + Send + 'static
+    {
+        let (step, cx) = match mem::replace(&mut self.state, State::Done) {
+            State::Local(step, cx) => (step, cx),
+            State::Scheduled(s) => {
+                self.state = State::Scheduled(s);
+                return executor::current().execute(|| g(Err(util::reused())))
+            }
+            State::Done => {
+                return executor::current().execute(|| g(Err(util::reused())))
+            }
+        };
+        let state = Arc::new(Scheduled {
+            slot: Mutex::new((cx.steps, None)),
+            executor: executor::current(),
+        });
+        Scheduled::run(&state, step, cx, g);
+
+        self.state = State::Scheduled(state);
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<R::Item, R::Error>>) {
+        self.schedule(|r| cb.call(r))
+    }
+}
+
+impl<F, R> Context<F, R::Error>
+    where F: FnMut() -> R + Send + 'static,
+          R: IntoFuture,
+{
+    fn start(&mut self) -> PollResult<R::Future, R::Error> {
+        let factory = try!(util::opt2poll(self.factory.take()));
+        let (f, factory) = try!(util::recover(move || {
+            let mut factory = factory;
+            (factory().into_future(), factory)
+        }));
+        self.factory = Some(factory);
+        self.attempts += 1;
+        Ok(f)
+    }
+
+    // Decides what to do with the result of an attempt, returning either how
+    // long to back off for or the final result.
+    fn attempted<T>(&mut self, res: PollResult<T, R::Error>)
+                    -> Result<Duration, PollResult<T, R::Error>> {
+        let e = match res {
+            Err(PollError::Other(e)) => e,
+            res => return Err(res),
+        };
+        match self.policy.next(self.attempts, &e) {
+            Some(dur) => Ok(dur),
+            None => Err(Err(PollError::Other(e))),
+        }
+    }
+}
+
+fn backoff<A>(dur: Duration) -> Step<A> {
+    if dur == Duration::new(0, 0) {
+        Step::Start
+    } else {
+        Step::Sleep(timer::delay(dur))
+    }
+}
+
+impl<A: Future> Scheduled<A> {
+    fn run<F, R, G>(state: &Arc<Scheduled<A>>,
+                    step: Step<A>,
+                    mut cx: Context<F, A::Error>,
+                    g: G)
+        where F: FnMut() -> R + Send + 'static,
+              R: IntoFuture<Future=A, Item=A::Item, Error=A::Error>,
+              G: FnOnce(PollResult<A::Item, A::Error>) + Send + 'static
+    {
+        let mut step = match step {
+            Step::Start => {
+                match cx.start() {
+                    Ok(f) => Step::Attempt(f),
+                    Err(e) => return state.executor.execute(|| g(Err(e))),
+                }
+            }
+            step => step,
+        };
+        cx.steps += 1;
+        let nth = cx.steps;
+        let state2 = state.clone();
+        match step {
+            Step::Attempt(ref mut f) => {
+                f.schedule(move |res| Scheduled::finish(&state2, res, cx, g))
+            }
+            Step::Sleep(ref mut d) => {
+                d.schedule(move |res| {
+                    if let Err(PollError::Canceled) = res {
+                        return state2.executor.execute(|| {
+                            g(Err(PollError::Canceled))
+                        })
+                    }
+                    state2.executor.enter(|| {
+                        Scheduled::run(&state2, Step::Start, cx, g)
+                    })
+                })
+            }
+            Step::Start => unreachable!(),
+        }
+
+        // Publish our step so `cancel` can drop it. Whatever we end up not
+        // keeping is dropped outside the lock as that may run callbacks.
+        let prev = {
+            let mut slot = state.slot.lock().unwrap();
+            if slot.0 == CANCELED || slot.0 >= nth {
+                Some(step)
+            } else {
+                slot.0 = nth;
+                slot.1.replace(step)
+            }
+        };
+        drop(prev);
+    }
+
+    fn finish<F, R, G>(state: &Arc<Scheduled<A>>,
+                       res: PollResult<A::Item, A::Error>,
+                       mut cx: Context<F, A::Error>,
+                       g: G)
+        where F: FnMut() -> R + Send + 'static,
+              R: IntoFuture<Future=A, Item=A::Item, Error=A::Error>,
+              G: FnOnce(PollResult<A::Item, A::Error>) + Send + 'static
+    {
+        match cx.attempted(res) {
+            Ok(dur) => {
+                state.executor.enter(|| {
+                    Scheduled::run(state, backoff(dur), cx, g)
+                })
+            }
+            Err(res) => state.executor.execute(|| g(res)),
+        }
+    }
+
+    fn cancel(&self) {
+        let f = {
+            let mut slot = self.slot.lock().unwrap();
+            slot.0 = CANCELED;
+            slot.1.take()
+        };
+        drop(f);
+    }
+}
+
+impl<F, R> Drop for Retry<F, R>
+    where F: FnMut() -> R + Send + 'static,
+          R: IntoFuture,
+{
+    fn drop(&mut self) {
+        if let State::Scheduled(ref s) = self.state {
+            s.cancel();
+        }
+    }
+}
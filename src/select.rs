@@ -3,7 +3,7 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
 use {PollResult, Callback, Future, PollError};
-use executor::{Executor, DEFAULT};
+use executor::{self, Executor, Current};
 use lock::Lock;
 use slot::Slot;
 use util;
@@ -13,7 +13,15 @@ pub struct Select<A, B> where A: Future, B: Future<Item=A::Item, Error=A::Error>
 }
 
 pub struct SelectNext<A, B> where A: Future, B: Future<Item=A::Item, Error=A::Error> {
-    state: Arc<Scheduled<A, B>>,
+    state: Next<A, B>,
+    // whether the shared result has been taken by `poll` or `schedule`
+    used: bool,
+}
+
+enum Next<A, B> where A: Future, B: Future<Item=A::Item, Error=A::Error> {
+    Scheduled(Arc<Scheduled<A, B>>),
+    A(A),
+    B(B),
 }
 
 pub fn new<A, B>(a: A, b: B) -> Select<A, B>
@@ -32,6 +40,27 @@ impl<A, B> Future for Select<A, B>
     type Item = (A::Item, SelectNext<A, B>);
     type Error = (A::Error, SelectNext<A, B>);
 
+    fn poll(&mut self) -> Option<PollResult<Self::Item, Self::Error>> {
+        let (mut a, mut b) = match mem::replace(&mut self.state, State::Done) {
+            State::Start(a, b) => (a, b),
+            State::Canceled => return Some(Err(PollError::Canceled)),
+            State::Scheduled(s) => {
+                self.state = State::Scheduled(s);
+                return Some(Err(util::reused()))
+            }
+            State::Done => return Some(Err(util::reused())),
+        };
+        let (val, next) = if let Some(val) = a.poll() {
+            (val, Next::B(b))
+        } else if let Some(val) = b.poll() {
+            (val, Next::A(a))
+        } else {
+            self.state = State::Start(a, b);
+            return None
+        };
+        Some(wrap(val, SelectNext { state: next, used: false }))
+    }
+
     fn schedule<G>(&mut self, g: G)
         where G: FnOnce(PollResult<Self::Item, Self::Error>) + Send + 'static
     {
@@ -43,11 +72,15 @@ impl<A, B> Future for Select<A, B>
         let (mut a, mut b) = match mem::replace(&mut self.state, State::Canceled) {
             State::Start(a, b) => (a, b),
             State::Canceled => {
-                return DEFAULT.execute(|| cb.call(Err(PollError::Canceled)))
+                return executor::current().execute(|| cb.call(Err(PollError::Canceled)))
             }
             State::Scheduled(s) => {
                 self.state = State::Scheduled(s);
-                return DEFAULT.execute(|| cb.call(Err(util::reused())))
+                return executor::current().execute(|| cb.call(Err(util::reused())))
+            }
+            State::Done => {
+                self.state = State::Done;
+                return executor::current().execute(|| cb.call(Err(util::reused())))
             }
         };
 
@@ -57,6 +90,7 @@ impl<A, B> Future for Select<A, B>
             state: AtomicUsize::new(0),
             cb: Lock::new(Some(cb)),
             data: Slot::new(None),
+            executor: executor::current(),
         });
         let data2 = data1.clone();
         let data3 = data2.clone();
@@ -92,6 +126,7 @@ enum State<A, B> where A: Future, B: Future<Item=A::Item, Error=A::Error> {
     Start(A, B),
     Scheduled(Arc<Scheduled<A, B>>),
     Canceled,
+    Done,
 }
 
 const DONE: usize = 1 << 0;
@@ -107,6 +142,7 @@ struct Scheduled<A, B>
     cb: Lock<Option<Box<Callback<(A::Item, SelectNext<A, B>),
                                  (A::Error, SelectNext<A, B>)>>>>,
     data: Slot<PollResult<A::Item, A::Error>>,
+    executor: Current,
 }
 
 impl<A, B> Scheduled<A, B>
@@ -126,14 +162,9 @@ impl<A, B> Scheduled<A, B>
 
         let cb = me.cb.try_lock().expect("[s] done but cb is locked")
                       .take().expect("[s] done done but cb not here");
-        let next = SelectNext { state: me };
-        let res = match val {
-            Ok(v) => Ok((v, next)),
-            Err(PollError::Other(e)) => Err(PollError::Other((e, next))),
-            Err(PollError::Panicked(p)) => Err(PollError::Panicked(p)),
-            Err(PollError::Canceled) => Err(PollError::Canceled),
-        };
-        DEFAULT.execute(|| cb.call(res))
+        let executor = me.executor.clone();
+        let res = wrap(val, SelectNext { state: Next::Scheduled(me), used: false });
+        executor.execute(|| cb.call(res))
     }
 
     fn cancel(&self) {
@@ -143,6 +174,15 @@ impl<A, B> Scheduled<A, B>
     }
 }
 
+fn wrap<T, E, N>(val: PollResult<T, E>, next: N) -> PollResult<(T, N), (E, N)> {
+    match val {
+        Ok(v) => Ok((v, next)),
+        Err(PollError::Other(e)) => Err(PollError::Other((e, next))),
+        Err(PollError::Panicked(p)) => Err(PollError::Panicked(p)),
+        Err(PollError::Canceled) => Err(PollError::Canceled),
+    }
+}
+
 impl<A, B> Drop for Select<A, B>
     where A: Future,
           B: Future<Item=A::Item, Error=A::Error>
@@ -168,13 +208,37 @@ impl<A, B> Future for SelectNext<A, B>
     type Item = A::Item;
     type Error = A::Error;
 
+    fn poll(&mut self) -> Option<PollResult<Self::Item, Self::Error>> {
+        match self.state {
+            Next::Scheduled(_) if self.used => Some(Err(util::reused())),
+            Next::Scheduled(ref s) => {
+                let res = s.data.try_consume().ok();
+                self.used = res.is_some();
+                res
+            }
+            Next::A(ref mut a) => a.poll(),
+            Next::B(ref mut b) => b.poll(),
+        }
+    }
+
     fn schedule<G>(&mut self, g: G)
         where G: FnOnce(PollResult<Self::Item, Self::Error>) + Send + 'static
     {
-        self.state.data.on_full(|slot| {
-            let data = slot.try_consume().unwrap();
-            DEFAULT.execute(|| g(data));
-        });
+        match self.state {
+            Next::Scheduled(_) if self.used => {
+                executor::current().execute(|| g(Err(util::reused())))
+            }
+            Next::Scheduled(ref s) => {
+                self.used = true;
+                let executor = executor::current();
+                s.data.on_full(move |slot| {
+                    let data = slot.try_consume().unwrap();
+                    executor.execute(|| g(data));
+                }).unwrap();
+            }
+            Next::A(ref mut a) => a.schedule(g),
+            Next::B(ref mut b) => b.schedule(g),
+        }
     }
 
     fn schedule_boxed(&mut self, cb: Box<Callback<Self::Item, Self::Error>>) {
@@ -187,7 +251,13 @@ impl<A, B> Drop for SelectNext<A, B>
           B: Future<Item=A::Item, Error=A::Error>
 {
     fn drop(&mut self) {
-        let mut state = self.state.state.load(Ordering::SeqCst);
+        // If we were created from a `poll` then we own the other future
+        // outright and dropping it is all the cancellation we need.
+        let me = match self.state {
+            Next::Scheduled(ref s) => s,
+            Next::A(..) | Next::B(..) => return,
+        };
+        let mut state = me.state.load(Ordering::SeqCst);
         loop {
             // We should in theory only be here if one half is done and we
             // haven't canceled yet.
@@ -197,8 +267,7 @@ impl<A, B> Drop for SelectNext<A, B>
             // Our next state will indicate that we are canceled, and if the
             // futures are available to us we're gonna take them.
             let next = state | CANCEL & !SET;
-            let old = self.state.state.compare_and_swap(state, next,
-                                                        Ordering::SeqCst);
+            let old = me.state.compare_and_swap(state, next, Ordering::SeqCst);
             if old == state {
                 break
             }
@@ -208,7 +277,7 @@ impl<A, B> Drop for SelectNext<A, B>
         // If the old state indicated that we had the futures, then we just took
         // ownership of them so we cancel the futures here.
         if state & SET != 0 {
-            self.state.cancel();
+            me.cancel();
         }
     }
 }
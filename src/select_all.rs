@@ -0,0 +1,259 @@
+use std::mem;
+use std::sync::{Arc, Mutex};
+
+use executor::{self, Executor, Current};
+use slot::Slot;
+use {Future, IntoFuture, Callback, PollResult, PollError};
+use util;
+
+pub struct SelectAll<A: Future> {
+    state: State<A>,
+}
+
+// One of the futures which didn't win a `SelectAll`, which can be used to
+// wait for its result. Dropping this cancels the underlying future.
+pub struct SelectAllNext<A: Future> {
+    state: Next<A>,
+    // whether the shared result has been taken by `poll` or `schedule`
+    used: bool,
+}
+
+enum State<A: Future> {
+    Start(Vec<A>),
+    Scheduled(Arc<Scheduled<A>>),
+    Done,
+}
+
+enum Next<A: Future> {
+    Scheduled(Arc<Scheduled<A>>, usize),
+    Local(A),
+}
+
+struct Scheduled<A: Future> {
+    inner: Mutex<Inner<A>>,
+    data: Vec<Slot<PollResult<A::Item, A::Error>>>,
+    executor: Current,
+}
+
+struct Inner<A: Future> {
+    // Only filled in once every future has been scheduled, entries are taken
+    // out to cancel them.
+    futures: Vec<Option<A>>,
+    set: bool,
+    winner: Option<usize>,
+    cb: Option<Box<Callback<(A::Item, usize, Vec<SelectAllNext<A>>),
+                            (A::Error, usize, Vec<SelectAllNext<A>>)>>>,
+}
+
+// Creates a future which resolves with the first of the futures in `i` to
+// finish, along with its index in `i` and the rest of the futures.
+//
+// Panics if `i` is empty.
+pub fn select_all<I>(i: I) -> SelectAll<<I::Item as IntoFuture>::Future>
+    where I: IntoIterator,
+          I::Item: IntoFuture,
+{
+    let futures = i.into_iter()
+                   .map(IntoFuture::into_future)
+                   .collect::<Vec<_>>();
+    assert!(!futures.is_empty(), "cannot select from no futures");
+    SelectAll {
+        state: State::Start(futures),
+    }
+}
+
+impl<A: Future> Future for SelectAll<A> {
+    type Item = (A::Item, usize, Vec<SelectAllNext<A>>);
+    type Error = (A::Error, usize, Vec<SelectAllNext<A>>);
+
+    fn poll(&mut self) -> Option<PollResult<Self::Item, Self::Error>> {
+        let mut futures = match mem::replace(&mut self.state, State::Done) {
+            State::Start(futures) => futures,
+            State::Scheduled(s) => {
+                self.state = State::Scheduled(s);
+                return Some(Err(util::reused()))
+            }
+            State::Done => return Some(Err(util::reused())),
+        };
+        let found = futures.iter_mut().enumerate().filter_map(|(i, f)| {
+            f.poll().map(|val| (i, val))
+        }).next();
+        let (i, val) = match found {
+            Some(pair) => pair,
+            None => {
+                self.state = State::Start(futures);
+                return None
+            }
+        };
+        futures.remove(i);
+        let rest = futures.into_iter().map(|f| {
+            SelectAllNext { state: Next::Local(f), used: false }
+        }).collect();
+        Some(wrap(val, i, rest))
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<Self::Item, Self::Error>) + Send + 'static
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<Self::Item, Self::Error>>) {
+        let mut futures = match mem::replace(&mut self.state, State::Done) {
+            State::Start(futures) => futures,
+            State::Scheduled(s) => {
+                self.state = State::Scheduled(s);
+                return executor::current().execute(|| cb.call(Err(util::reused())))
+            }
+            State::Done => {
+                return executor::current().execute(|| cb.call(Err(util::reused())))
+            }
+        };
+
+        let state = Arc::new(Scheduled {
+            inner: Mutex::new(Inner {
+                futures: Vec::new(),
+                set: false,
+                winner: None,
+                cb: Some(cb),
+            }),
+            data: futures.iter().map(|_| Slot::new(None)).collect(),
+            executor: executor::current(),
+        });
+        for (i, f) in futures.iter_mut().enumerate() {
+            let state = state.clone();
+            f.schedule(move |r| Scheduled::finish(&state, i, r));
+        }
+
+        // Now that the futures are all scheduled they can be handed out to
+        // the callback, which may have been waiting on us to get here.
+        let winner = {
+            let mut inner = state.inner.lock().unwrap();
+            inner.futures = futures.into_iter().map(Some).collect();
+            inner.set = true;
+            inner.winner
+        };
+        if let Some(i) = winner {
+            Scheduled::deliver(&state, i);
+        }
+
+        self.state = State::Scheduled(state);
+    }
+}
+
+impl<A: Future> Scheduled<A> {
+    fn finish(me: &Arc<Scheduled<A>>,
+              i: usize,
+              val: PollResult<A::Item, A::Error>) {
+        me.data[i].try_produce(val).ok().expect("[sa] data already full");
+        let deliver = {
+            let mut inner = me.inner.lock().unwrap();
+            if inner.winner.is_some() {
+                return
+            }
+            inner.winner = Some(i);
+            inner.set
+        };
+        if deliver {
+            Scheduled::deliver(me, i);
+        }
+    }
+
+    fn deliver(me: &Arc<Scheduled<A>>, i: usize) {
+        let (cb, winner) = {
+            let mut inner = me.inner.lock().unwrap();
+            let winner = inner.futures.get_mut(i).and_then(Option::take);
+            (inner.cb.take().expect("[sa] cb not here"), winner)
+        };
+        drop(winner);
+        let val = me.data[i].try_consume().ok().expect("[sa] data not here");
+        let rest = (0..me.data.len()).filter(|j| *j != i).map(|j| {
+            SelectAllNext { state: Next::Scheduled(me.clone(), j), used: false }
+        }).collect();
+        let res = wrap(val, i, rest);
+        me.executor.execute(|| cb.call(res))
+    }
+
+    fn cancel(&self, i: usize) {
+        let f = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.futures.get_mut(i).and_then(Option::take)
+        };
+        drop(f);
+    }
+}
+
+fn wrap<T, E, N>(val: PollResult<T, E>, i: usize, rest: N)
+                 -> PollResult<(T, usize, N), (E, usize, N)> {
+    match val {
+        Ok(v) => Ok((v, i, rest)),
+        Err(PollError::Other(e)) => Err(PollError::Other((e, i, rest))),
+        Err(PollError::Panicked(p)) => Err(PollError::Panicked(p)),
+        Err(PollError::Canceled) => Err(PollError::Canceled),
+    }
+}
+
+impl<A: Future> Drop for SelectAll<A> {
+    fn drop(&mut self) {
+        if let State::Scheduled(ref state) = self.state {
+            // Once something has finished the rest of the futures are owned by
+            // the `SelectAllNext` handles given to the callback instead.
+            let futures = {
+                let mut inner = state.inner.lock().unwrap();
+                if inner.winner.is_some() {
+                    return
+                }
+                inner.futures.drain(..).collect::<Vec<_>>()
+            };
+            drop(futures);
+        }
+    }
+}
+
+impl<A: Future> Future for SelectAllNext<A> {
+    type Item = A::Item;
+    type Error = A::Error;
+
+    fn poll(&mut self) -> Option<PollResult<A::Item, A::Error>> {
+        match self.state {
+            Next::Scheduled(..) if self.used => Some(Err(util::reused())),
+            Next::Scheduled(ref s, i) => {
+                let res = s.data[i].try_consume().ok();
+                self.used = res.is_some();
+                res
+            }
+            Next::Local(ref mut f) => f.poll(),
+        }
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<A::Item, A::Error>) + Send + 'static
+    {
+        match self.state {
+            Next::Scheduled(..) if self.used => {
+                executor::current().execute(|| g(Err(util::reused())))
+            }
+            Next::Scheduled(ref s, i) => {
+                self.used = true;
+                let executor = executor::current();
+                s.data[i].on_full(move |slot| {
+                    let data = slot.try_consume().unwrap();
+                    executor.execute(|| g(data));
+                }).unwrap();
+            }
+            Next::Local(ref mut f) => f.schedule(g),
+        }
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<A::Item, A::Error>>) {
+        self.schedule(|r| cb.call(r))
+    }
+}
+
+impl<A: Future> Drop for SelectAllNext<A> {
+    fn drop(&mut self) {
+        if let Next::Scheduled(ref s, i) = self.state {
+            s.cancel(i);
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::sync::{Arc, Mutex};
+
+use executor::{self, Executor, Current};
+use {Future, Callback, PollResult, PollError};
+use util;
+
+pub struct Shared<F: Future> {
+    inner: Arc<Inner<F>>,
+    state: State,
+}
+
+enum State {
+    Start,
+    Scheduled(usize),
+    Done,
+}
+
+struct Inner<F: Future> {
+    state: Mutex<Data<F>>,
+}
+
+struct Data<F: Future> {
+    // `None` while the future is being scheduled or once it's been canceled
+    future: Option<F>,
+    started: bool,
+    result: Option<Outcome<F::Item, F::Error>>,
+    waiters: Vec<(usize, Box<Callback<F::Item, F::Error>>, Current)>,
+    next_waiter: usize,
+    handles: usize,
+}
+
+// A `PollResult` which can be handed out to any number of handles. Panic
+// payloads can't be cloned, so we only keep the message around.
+#[derive(Clone)]
+enum Outcome<T, E> {
+    Ok(T),
+    Err(E),
+    Panicked(String),
+    Reused,
+    Canceled,
+}
+
+pub fn new<F>(future: F) -> Shared<F>
+    where F: Future,
+          F::Item: Clone,
+          F::Error: Clone,
+{
+    Shared {
+        inner: Arc::new(Inner {
+            state: Mutex::new(Data {
+                future: Some(future),
+                started: false,
+                result: None,
+                waiters: Vec::new(),
+                next_waiter: 0,
+                handles: 1,
+            }),
+        }),
+        state: State::Start,
+    }
+}
+
+impl<F> Future for Shared<F>
+    where F: Future,
+          F::Item: Clone,
+          F::Error: Clone,
+{
+    type Item = F::Item;
+    type Error = F::Error;
+
+    fn poll(&mut self) -> Option<PollResult<F::Item, F::Error>> {
+        match self.state {
+            State::Start => {}
+            State::Scheduled(..) | State::Done => return Some(Err(util::reused())),
+        }
+        let mut data = self.inner.state.lock().unwrap();
+        if data.result.is_none() && !data.started {
+            let res = match data.future {
+                Some(ref mut f) => f.poll(),
+                None => None,
+            };
+            if let Some(res) = res {
+                data.future = None;
+                data.result = Some(Outcome::new(res));
+            }
+        }
+        let res = data.result.clone().map(Outcome::into_result);
+        if res.is_some() {
+            self.state = State::Done;
+        }
+        res
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<F::Item, F::Error>) + Send + 'static
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<F::Item, F::Error>>) {
+        let executor = executor::current();
+        match self.state {
+            State::Start => {}
+            State::Scheduled(..) | State::Done => {
+                return executor.execute(|| cb.call(Err(util::reused())))
+            }
+        }
+        let mut data = self.inner.state.lock().unwrap();
+        if let Some(res) = data.result.clone() {
+            drop(data);
+            self.state = State::Done;
+            return executor.execute(|| cb.call(res.into_result()))
+        }
+        let id = data.next_waiter;
+        data.next_waiter += 1;
+        data.waiters.push((id, cb, executor));
+        self.state = State::Scheduled(id);
+        if data.started {
+            return
+        }
+
+        // We're the first one here, so get the future running. It's taken out
+        // while it's scheduled as it may complete immediately, which requires
+        // the lock.
+        data.started = true;
+        let mut future = match data.future.take() {
+            Some(f) => f,
+            None => return,
+        };
+        drop(data);
+        let inner = self.inner.clone();
+        future.schedule(move |res| inner.finish(res));
+
+        let mut data = self.inner.state.lock().unwrap();
+        if data.result.is_none() && data.handles > 0 {
+            data.future = Some(future);
+        }
+    }
+}
+
+impl<F: Future> Inner<F> {
+    fn finish(&self, res: PollResult<F::Item, F::Error>)
+        where F::Item: Clone, F::Error: Clone,
+    {
+        let res = Outcome::new(res);
+        let (future, waiters) = {
+            let mut data = self.state.lock().unwrap();
+            data.result = Some(res.clone());
+            (data.future.take(), data.waiters.drain(..).collect::<Vec<_>>())
+        };
+        drop(future);
+        for (_, cb, executor) in waiters {
+            let res = res.clone().into_result();
+            executor.execute(|| cb.call(res));
+        }
+    }
+}
+
+impl<F> Clone for Shared<F>
+    where F: Future,
+          F::Item: Clone,
+          F::Error: Clone,
+{
+    fn clone(&self) -> Shared<F> {
+        self.inner.state.lock().unwrap().handles += 1;
+        Shared {
+            inner: self.inner.clone(),
+            state: State::Start,
+        }
+    }
+}
+
+impl<F: Future> Drop for Shared<F> {
+    fn drop(&mut self) {
+        let (waiter, future) = {
+            let mut data = self.inner.state.lock().unwrap();
+            data.handles -= 1;
+            let waiter = match self.state {
+                State::Scheduled(id) => {
+                    data.waiters.iter()
+                        .position(|w| w.0 == id)
+                        .map(|i| data.waiters.remove(i))
+                }
+                State::Start | State::Done => None,
+            };
+            // The last handle going away cancels the underlying future
+            let future = if data.handles == 0 {data.future.take()} else {None};
+            (waiter, future)
+        };
+        if let Some((_, cb, executor)) = waiter {
+            executor.execute(|| cb.call(Err(PollError::Canceled)));
+        }
+        drop(future);
+    }
+}
+
+impl<T, E> Outcome<T, E> {
+    fn new(res: PollResult<T, E>) -> Outcome<T, E> {
+        match res {
+            Ok(t) => Outcome::Ok(t),
+            Err(PollError::Other(e)) => Outcome::Err(e),
+            Err(ref e) if e.is_reused() => Outcome::Reused,
+            Err(e @ PollError::Panicked(_)) => {
+                let msg = e.panic_message().unwrap_or("shared future panicked");
+                Outcome::Panicked(msg.to_string())
+            }
+            Err(PollError::Canceled) => Outcome::Canceled,
+        }
+    }
+
+    fn into_result(self) -> PollResult<T, E> {
+        match self {
+            Outcome::Ok(t) => Ok(t),
+            Outcome::Err(e) => Err(PollError::Other(e)),
+            Outcome::Panicked(msg) => Err(PollError::Panicked(Box::new(msg))),
+            Outcome::Reused => Err(util::reused()),
+            Outcome::Canceled => Err(PollError::Canceled),
+        }
+    }
+}
@@ -7,6 +7,13 @@ use lock::Lock;
 ///
 /// Each slot contains space for a piece of data, `T`, and space for callbacks.
 /// The callbacks can be run when the data is either full or empty.
+///
+/// The producer fills the slot with `try_produce` and may wait for it to be
+/// emptied with `on_empty`, while the consumer empties it with `try_consume`
+/// and may wait for it to be filled with `on_full`. None of these block, and
+/// the slot itself never takes a lock. Calls which break this protocol, such
+/// as two producers racing or registering a second callback, fail with an
+/// error rather than corrupting the slot.
 pub struct Slot<T> {
     state: AtomicUsize,
     slot: Lock<Option<T>>,
@@ -14,22 +21,38 @@ pub struct Slot<T> {
     on_empty: Lock<Option<Box<FnBox<T>>>>,
 }
 
-/// Error value returned from erroneous calls to `try_produce`.
+/// Error value returned from erroneous calls to `try_produce`, handing back
+/// the value which couldn't be stored.
+///
+/// This happens when the slot is already full, or when another producer is
+/// in the middle of filling it.
 #[derive(Debug, PartialEq)]
 pub struct TryProduceError<T>(T);
 
 /// Error value returned from erroneous calls to `try_consume`.
+///
+/// This happens when the slot is empty, or when another consumer is in the
+/// middle of emptying it.
 #[derive(Debug, PartialEq)]
 pub struct TryConsumeError(());
 
 /// Error value returned from erroneous calls to `on_full`.
+///
+/// This happens when an `on_full` callback is already registered, in which
+/// case the new callback is dropped without being run.
 #[derive(Debug, PartialEq)]
 pub struct OnFullError(());
 
 /// Error value returned from erroneous calls to `on_empty`.
+///
+/// This happens when an `on_empty` callback is already registered, in which
+/// case the new callback is dropped without being run.
 #[derive(Debug, PartialEq)]
 pub struct OnEmptyError(());
 
+/// Identifies a callback registered with `on_full` or `on_empty`, so that it
+/// can later be passed to `cancel`.
+#[derive(Debug)]
 pub struct Token(usize);
 
 struct State(usize);
@@ -49,6 +72,7 @@ const STATE_BITS: usize = 3;
 const STATE_MASK: usize = (1 << STATE_BITS) - 1;
 
 impl<T: 'static> Slot<T> {
+    /// Creates a slot, which is full if `val` is `Some`.
     pub fn new(val: Option<T>) -> Slot<T> {
         Slot {
             state: AtomicUsize::new(if val.is_some() {DATA} else {0}),
@@ -58,20 +82,39 @@ impl<T: 'static> Slot<T> {
         }
     }
 
-    // PRODUCER
+    /// Fills the slot with `t`, running the `on_full` callback if there is
+    /// one.
+    ///
+    /// This is the producer's half of the slot. If the slot is already full
+    /// then `t` is handed back in the error.
     pub fn try_produce(&self, t: T) -> Result<(), TryProduceError<T>> {
         let mut state = State(self.state.load(Ordering::SeqCst));
-        assert!(!state.flag(ON_EMPTY));
+        // `ON_EMPTY` is only ever set along with `DATA`
         if state.flag(DATA) {
             return Err(TryProduceError(t))
         }
-        let mut slot = self.slot.try_lock().expect("interference with consumer?");
-        assert!(slot.is_none());
+        let mut slot = match self.slot.try_lock() {
+            Some(slot) => slot,
+            None => return Err(TryProduceError(t)),
+        };
+        if slot.is_some() {
+            return Err(TryProduceError(t))
+        }
         *slot = Some(t);
         drop(slot);
 
         loop {
-            assert!(!state.flag(ON_EMPTY));
+            if state.flag(DATA) {
+                // Another producer got in first and its value has already been
+                // taken, or ours couldn't have gone in. Pull ours back out
+                // before it's flagged, unless a consumer has beaten us to it.
+                let t = self.slot.try_lock().and_then(|mut slot| slot.take());
+                return match t {
+                    Some(t) => Err(TryProduceError(t)),
+                    None => Ok(()),
+                }
+            }
+            debug_assert!(!state.flag(ON_EMPTY));
             let new_state = state.set_flag(DATA, true).set_flag(ON_FULL, false);
             let old = self.state.compare_and_swap(state.0,
                                                   new_state.0,
@@ -82,65 +125,92 @@ impl<T: 'static> Slot<T> {
             state.0 = old;
         }
         if state.flag(ON_FULL) {
-            let cb = self.on_full.try_lock().expect("interference2")
-                                 .take().expect("ON_FULL but no callback");
-            cb.call_box(self);
+            self.run(&self.on_full);
         }
         Ok(())
     }
 
-    // PRODUCER
-    pub fn on_empty<F>(&self, f: F) -> Token
+    /// Runs `f` once the slot is empty, which may be right away.
+    ///
+    /// This is the producer's half of the slot. Only one `on_empty` callback
+    /// can be registered at a time, and registering another fails.
+    pub fn on_empty<F>(&self, f: F) -> Result<Token, OnEmptyError>
         where F: FnOnce(&Slot<T>) + Send + 'static
     {
         let mut state = State(self.state.load(Ordering::SeqCst));
-        assert!(!state.flag(ON_EMPTY));
+        if state.flag(ON_EMPTY) {
+            return Err(OnEmptyError(()))
+        }
         if !state.flag(DATA) {
             f(self);
-            return Token(0)
+            return Ok(Token(0))
+        }
+        debug_assert!(!state.flag(ON_FULL));
+        let mut slot = match self.on_empty.try_lock() {
+            Some(slot) => slot,
+            None => return Err(OnEmptyError(())),
+        };
+        if slot.is_some() {
+            return Err(OnEmptyError(()))
         }
-        assert!(!state.flag(ON_FULL));
-        let mut slot = self.on_empty.try_lock().expect("on_empty interference");
-        assert!(slot.is_none());
         *slot = Some(Box::new(f));
         drop(slot);
 
         loop {
-            assert!(state.flag(DATA));
-            assert!(!state.flag(ON_FULL));
-            assert!(!state.flag(ON_EMPTY));
+            debug_assert!(state.flag(DATA));
+            debug_assert!(!state.flag(ON_FULL));
+            debug_assert!(!state.flag(ON_EMPTY));
             let new_state = state.set_flag(ON_EMPTY, true)
                                  .set_token(state.token() + 1);
             let old = self.state.compare_and_swap(state.0,
                                                   new_state.0,
                                                   Ordering::SeqCst);
             if old == state.0 {
-                return Token(new_state.token())
+                return Ok(Token(new_state.token()))
             }
             state.0 = old;
 
             if !state.flag(DATA) {
-                let cb = self.on_empty.try_lock().expect("on_empty interference2")
-                                      .take().expect("on_empty not empty??");
-                cb.call_box(self);
-                return Token(0)
+                self.run(&self.on_empty);
+                return Ok(Token(0))
             }
         }
     }
 
-    // CONSUMER
+    /// Empties the slot, running the `on_empty` callback if there is one.
+    ///
+    /// This is the consumer's half of the slot, and fails if there's nothing
+    /// to take.
     pub fn try_consume(&self) -> Result<T, TryConsumeError> {
         let mut state = State(self.state.load(Ordering::SeqCst));
-        assert!(!state.flag(ON_FULL));
+        // `ON_FULL` is only ever set while `DATA` isn't
         if !state.flag(DATA) {
             return Err(TryConsumeError(()))
         }
-        let mut slot = self.slot.try_lock().expect("interference with producer?");
-        let val = slot.take().expect("DATA but not data");
+        let mut slot = match self.slot.try_lock() {
+            Some(slot) => slot,
+            None => return Err(TryConsumeError(())),
+        };
+        let val = match slot.take() {
+            Some(val) => val,
+            None => return Err(TryConsumeError(())),
+        };
         drop(slot);
 
         loop {
-            assert!(!state.flag(ON_FULL));
+            if !state.flag(DATA) {
+                // Another consumer got in first and the slot has already been
+                // refilled, or we wouldn't have found anything. Put the value
+                // back for the producer to flag, if there's still room.
+                if let Some(mut slot) = self.slot.try_lock() {
+                    if slot.is_none() {
+                        *slot = Some(val);
+                        return Err(TryConsumeError(()))
+                    }
+                }
+                return Ok(val)
+            }
+            debug_assert!(!state.flag(ON_FULL));
             let new_state = state.set_flag(DATA, false).set_flag(ON_EMPTY, false);
             let old = self.state.compare_and_swap(state.0,
                                                   new_state.0,
@@ -150,97 +220,123 @@ impl<T: 'static> Slot<T> {
             }
             state.0 = old;
         }
-        assert!(!state.flag(ON_FULL));
         if state.flag(ON_EMPTY) {
-            let cb = self.on_empty.try_lock().expect("interference3")
-                                  .take().expect("ON_EMPTY but no callback");
-            cb.call_box(self);
+            self.run(&self.on_empty);
         }
         Ok(val)
     }
 
-    // CONSUMER
-    pub fn on_full<F>(&self, f: F) -> Token
+    /// Runs `f` once the slot is full, which may be right away.
+    ///
+    /// This is the consumer's half of the slot. Only one `on_full` callback
+    /// can be registered at a time, and registering another fails.
+    pub fn on_full<F>(&self, f: F) -> Result<Token, OnFullError>
         where F: FnOnce(&Slot<T>) + Send + 'static
     {
         let mut state = State(self.state.load(Ordering::SeqCst));
-        assert!(!state.flag(ON_FULL));
+        if state.flag(ON_FULL) {
+            return Err(OnFullError(()))
+        }
         if state.flag(DATA) {
             f(self);
-            return Token(0)
+            return Ok(Token(0))
+        }
+        debug_assert!(!state.flag(ON_EMPTY));
+        let mut slot = match self.on_full.try_lock() {
+            Some(slot) => slot,
+            None => return Err(OnFullError(())),
+        };
+        if slot.is_some() {
+            return Err(OnFullError(()))
         }
-        assert!(!state.flag(ON_EMPTY));
-        let mut slot = self.on_full.try_lock().expect("on_full interference");
-        assert!(slot.is_none());
         *slot = Some(Box::new(f));
         drop(slot);
 
         loop {
-            assert!(!state.flag(DATA));
-            assert!(!state.flag(ON_EMPTY));
-            assert!(!state.flag(ON_FULL));
+            debug_assert!(!state.flag(DATA));
+            debug_assert!(!state.flag(ON_EMPTY));
+            debug_assert!(!state.flag(ON_FULL));
             let new_state = state.set_flag(ON_FULL, true)
                                  .set_token(state.token() + 1);
             let old = self.state.compare_and_swap(state.0,
                                                   new_state.0,
                                                   Ordering::SeqCst);
             if old == state.0 {
-                return Token(new_state.token())
+                return Ok(Token(new_state.token()))
             }
             state.0 = old;
 
             if state.flag(DATA) {
-                let cb = self.on_full.try_lock().expect("on_full interference2")
-                                      .take().expect("on_full not full??");
-                cb.call_box(self);
-                return Token(0)
+                self.run(&self.on_full);
+                return Ok(Token(0))
             }
         }
     }
 
+    /// Runs the callback registered by whichever `on_full` or `on_empty` call
+    /// returned `token`, if it hasn't run yet.
+    ///
+    /// Tokens for callbacks which have already run are ignored.
     pub fn cancel(&self, token: Token) {
         let token = token.0;
         if token == 0 {
             return
         }
         let mut state = State(self.state.load(Ordering::SeqCst));
-        loop {
+        // Whichever of us clears the flag gets to run the callback, so we
+        // have to check that it's still the one `token` refers to every time
+        // the state changes under us.
+        let flag = loop {
             if state.token() != token {
                 return
             }
-            let new_state = if state.flag(ON_FULL) {
-                assert!(!state.flag(ON_EMPTY));
-                state.set_flag(ON_FULL, false)
+            let flag = if state.flag(ON_FULL) {
+                ON_FULL
             } else if state.flag(ON_EMPTY) {
-                assert!(!state.flag(ON_FULL));
-                state.set_flag(ON_EMPTY, false)
+                ON_EMPTY
             } else {
                 return
             };
+            let new_state = state.set_flag(flag, false);
             let old = self.state.compare_and_swap(state.0,
                                                   new_state.0,
                                                   Ordering::SeqCst);
-            if old != state.0 {
-                break
+            if old == state.0 {
+                break flag
             }
             state.0 = old;
-        }
+        };
 
         // TODO: communicate that this is being called as part of a
         //       cancellation?
-        if state.flag(ON_FULL) {
-            let cb = self.on_full.try_lock().expect("on_full interference3")
-                                  .take().expect("on_full not full??");
-            cb.call_box(self);
+        if flag == ON_FULL {
+            self.run(&self.on_full);
         } else {
-            let cb = self.on_empty.try_lock().expect("on_empty interference3")
-                                  .take().expect("on_empty not empty??");
+            self.run(&self.on_empty);
+        }
+    }
+
+    // Runs the callback in `cb`, having just cleared its flag.
+    //
+    // Clearing the flag means the callback is ours, but a second `on_full`
+    // or `on_empty` call which raced with registering it may still be
+    // holding the lock to check whether it's there. It's about to give up
+    // and let go, so we just wait for it.
+    fn run(&self, cb: &Lock<Option<Box<FnBox<T>>>>) {
+        let cb = loop {
+            if let Some(mut cb) = cb.try_lock() {
+                break cb.take()
+            }
+        };
+        debug_assert!(cb.is_some());
+        if let Some(cb) = cb {
             cb.call_box(self);
         }
     }
 }
 
 impl<T> TryProduceError<T> {
+    /// Returns the value which couldn't be stored.
     pub fn into_inner(self) -> T {
         self.0
     }
@@ -287,7 +383,7 @@ mod tests {
     use std::sync::atomic::{AtomicUsize, Ordering};
     use std::thread;
 
-    use super::Slot;
+    use super::{Slot, OnFullError, OnEmptyError, TryConsumeError};
 
     #[test]
     fn sequential() {
@@ -310,7 +406,7 @@ mod tests {
         let hit2 = hit.clone();
         slot.on_full(move |_s| {
             hit2.fetch_add(1, Ordering::SeqCst);
-        });
+        }).unwrap();
         assert_eq!(hit.load(Ordering::SeqCst), 1);
 
         // on_full can be run twice, and we can consume in the callback
@@ -318,7 +414,7 @@ mod tests {
         slot.on_full(move |s| {
             hit2.fetch_add(1, Ordering::SeqCst);
             assert_eq!(s.try_consume(), Ok(3));
-        });
+        }).unwrap();
         assert_eq!(hit.load(Ordering::SeqCst), 2);
 
         // Production can't run a previous callback
@@ -331,7 +427,7 @@ mod tests {
         slot.on_full(move |s| {
             hit2.fetch_add(1, Ordering::SeqCst);
             assert_eq!(s.try_consume(), Ok(5));
-        });
+        }).unwrap();
         assert_eq!(slot.try_produce(5), Ok(()));
         assert_eq!(hit.load(Ordering::SeqCst), 3);
 
@@ -339,7 +435,7 @@ mod tests {
         let hit2 = hit.clone();
         slot.on_empty(move |_| {
             hit2.fetch_add(1, Ordering::SeqCst);
-        });
+        }).unwrap();
         assert_eq!(hit.load(Ordering::SeqCst), 4);
     }
 
@@ -368,7 +464,7 @@ mod tests {
                 self.slot.on_empty(move |_slot| {
                     hit.store(1, Ordering::SeqCst);
                     me.unpark();
-                });
+                }).unwrap();
                 while self.hit.load(Ordering::SeqCst) == 0 {
                     thread::park();
                 }
@@ -388,7 +484,7 @@ mod tests {
                 self.slot.on_full(move |_slot| {
                     hit.store(1, Ordering::SeqCst);
                     me.unpark();
-                });
+                }).unwrap();
                 while self.hit.load(Ordering::SeqCst) == 0 {
                     thread::park();
                 }
@@ -427,7 +523,7 @@ mod tests {
 
         // cancel on_full
         assert_eq!(hits.load(Ordering::SeqCst), 0);
-        let token = slot.on_full(add());
+        let token = slot.on_full(add()).unwrap();
         assert_eq!(hits.load(Ordering::SeqCst), 0);
         slot.cancel(token);
         assert_eq!(hits.load(Ordering::SeqCst), 1);
@@ -436,7 +532,7 @@ mod tests {
         // cancel on_empty
         assert_eq!(hits.load(Ordering::SeqCst), 1);
         slot.try_produce(1).unwrap();
-        let token = slot.on_empty(add());
+        let token = slot.on_empty(add()).unwrap();
         assert_eq!(hits.load(Ordering::SeqCst), 1);
         slot.cancel(token);
         assert_eq!(hits.load(Ordering::SeqCst), 2);
@@ -444,29 +540,52 @@ mod tests {
 
         // cancel with no effect
         assert_eq!(hits.load(Ordering::SeqCst), 2);
-        let token = slot.on_full(add());
+        let token = slot.on_full(add()).unwrap();
         assert_eq!(hits.load(Ordering::SeqCst), 3);
         slot.cancel(token);
         assert_eq!(hits.load(Ordering::SeqCst), 3);
         assert!(slot.try_consume().is_ok());
-        let token = slot.on_empty(add());
+        let token = slot.on_empty(add()).unwrap();
         assert_eq!(hits.load(Ordering::SeqCst), 4);
         slot.cancel(token);
         assert_eq!(hits.load(Ordering::SeqCst), 4);
 
         // cancel old ones don't count
         assert_eq!(hits.load(Ordering::SeqCst), 4);
-        let token1 = slot.on_full(add());
+        let token1 = slot.on_full(add()).unwrap();
         assert_eq!(hits.load(Ordering::SeqCst), 4);
         assert!(slot.try_produce(1).is_ok());
         assert_eq!(hits.load(Ordering::SeqCst), 5);
         assert!(slot.try_consume().is_ok());
         assert_eq!(hits.load(Ordering::SeqCst), 5);
-        let token2 = slot.on_full(add());
+        let token2 = slot.on_full(add()).unwrap();
         assert_eq!(hits.load(Ordering::SeqCst), 5);
         slot.cancel(token1);
         assert_eq!(hits.load(Ordering::SeqCst), 5);
         slot.cancel(token2);
         assert_eq!(hits.load(Ordering::SeqCst), 6);
     }
+
+    #[test]
+    fn misuse() {
+        let slot = Slot::new(None);
+
+        // Only one `on_full` callback at a time
+        let token = slot.on_full(|_| ()).unwrap();
+        assert_eq!(slot.on_full(|_| ()).err(), Some(OnFullError(())));
+        assert_eq!(slot.try_consume(), Err(TryConsumeError(())));
+        slot.cancel(token);
+
+        // Only one `on_empty` callback at a time
+        slot.try_produce(1).unwrap();
+        let token = slot.on_empty(|_| ()).unwrap();
+        assert_eq!(slot.on_empty(|_| ()).err(), Some(OnEmptyError(())));
+        assert_eq!(slot.try_produce(2).unwrap_err().into_inner(), 2);
+        slot.cancel(token);
+
+        // The slot still works afterwards
+        assert_eq!(slot.try_consume(), Ok(1));
+        assert_eq!(slot.try_produce(3), Ok(()));
+        assert_eq!(slot.try_consume(), Ok(3));
+    }
 }
@@ -0,0 +1,54 @@
+use {Callback, IntoFuture};
+use stream::{Stream, StreamResult};
+use stream::future_chain::{FutureChain, Next};
+
+pub struct AndThen<S, F, U>
+    where S: Stream,
+          F: Send + 'static,
+          U: IntoFuture,
+{
+    chain: FutureChain<S, U::Future, F>,
+}
+
+pub fn new<S, F, U>(s: S, f: F) -> AndThen<S, F, U>
+    where S: Stream,
+          F: FnMut(S::Item) -> U + Send + 'static,
+          U: IntoFuture<Error=S::Error>,
+{
+    AndThen {
+        chain: FutureChain::new(s, f, and_then::<S, F, U>),
+    }
+}
+
+impl<S, F, U> Stream for AndThen<S, F, U>
+    where S: Stream,
+          F: FnMut(S::Item) -> U + Send + 'static,
+          U: IntoFuture<Error=S::Error>,
+{
+    type Item = U::Item;
+    type Error = S::Error;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<Self::Item, Self::Error>) + Send + 'static,
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self,
+                      g: Box<Callback<Option<Self::Item>, Self::Error>>) {
+        self.chain.schedule(g)
+    }
+}
+
+fn and_then<S, F, U>(res: StreamResult<S::Item, S::Error>, f: &mut F)
+                     -> Next<U::Future>
+    where S: Stream,
+          F: FnMut(S::Item) -> U + Send + 'static,
+          U: IntoFuture<Error=S::Error>,
+{
+    match res {
+        Ok(Some(e)) => Next::Run(f(e).into_future()),
+        Ok(None) => Next::Done(Ok(None)),
+        Err(e) => Next::Done(Err(e)),
+    }
+}
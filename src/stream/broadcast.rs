@@ -0,0 +1,229 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use {Callback, PollError};
+use stream::{Stream, StreamResult};
+use stream::subscribers::Subscribers;
+
+// Creates a multi-producer, multi-consumer channel where each message is
+// cloned out to every receiver.
+//
+// Sending never waits. The channel keeps the last `capacity` messages around
+// for receivers which haven't caught up, and a receiver which falls further
+// behind than that gets a `RecvError::Lagged` before picking up again at the
+// oldest message still kept.
+//
+// More receivers are created with `Sender::subscribe`, and only see messages
+// sent after that. Each receiver is a `Stream` which ends once every `Sender`
+// has been dropped and it's received everything left.
+//
+// Panics if `capacity` is zero.
+pub fn channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>)
+    where T: Clone + Send + 'static,
+{
+    assert!(capacity > 0, "broadcast channel capacity must be positive");
+    let inner = Arc::new(Inner {
+        state: Mutex::new(State {
+            buf: VecDeque::new(),
+            head: 0,
+            capacity: capacity,
+            subs: Subscribers::new(),
+        }),
+    });
+    let rx = Receiver::new(&inner);
+    (Sender { inner: inner }, rx)
+}
+
+pub struct Sender<T>
+    where T: Clone + Send + 'static,
+{
+    inner: Arc<Inner<T>>,
+}
+
+pub struct Receiver<T>
+    where T: Clone + Send + 'static,
+{
+    inner: Arc<Inner<T>>,
+    id: usize,
+}
+
+struct Inner<T>
+    where T: Clone + Send + 'static,
+{
+    state: Mutex<State<T>>,
+}
+
+struct State<T>
+    where T: Clone + Send + 'static,
+{
+    // the last `capacity` messages, the first of which was message `head`
+    buf: VecDeque<T>,
+    head: u64,
+    capacity: usize,
+    // each receiver's position is the next message it'll see
+    subs: Subscribers<T, RecvError>,
+}
+
+// There are no receivers left, handing back the message which couldn't be
+// sent.
+pub struct SendError<T>(T);
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RecvError {
+    // The receiver fell behind and this many messages were dropped before it
+    // could see them
+    Lagged(u64),
+}
+
+impl<T> Sender<T>
+    where T: Clone + Send + 'static,
+{
+    // Sends `msg` to every receiver, dropping the oldest message if the
+    // channel is at capacity.
+    pub fn send(&self, msg: T) -> Result<(), SendError<T>> {
+        let waiting = {
+            let mut state = self.inner.state.lock().unwrap();
+            if state.subs.is_empty() {
+                return Err(SendError(msg))
+            }
+            if state.buf.len() == state.capacity {
+                state.buf.pop_front();
+                state.head += 1;
+            }
+            state.buf.push_back(msg.clone());
+            let next = state.head + state.buf.len() as u64;
+            state.subs.wake(next)
+        };
+        for h in waiting {
+            h.call(Ok(Some(msg.clone())));
+        }
+        Ok(())
+    }
+
+    // Creates a new receiver which sees every message sent from now on.
+    pub fn subscribe(&self) -> Receiver<T> {
+        Receiver::new(&self.inner)
+    }
+
+    // Returns how many receivers there are.
+    pub fn receiver_count(&self) -> usize {
+        self.inner.state.lock().unwrap().subs.len()
+    }
+}
+
+impl<T> Clone for Sender<T>
+    where T: Clone + Send + 'static,
+{
+    fn clone(&self) -> Sender<T> {
+        self.inner.state.lock().unwrap().subs.add_sender();
+        Sender { inner: self.inner.clone() }
+    }
+}
+
+impl<T> Drop for Sender<T>
+    where T: Clone + Send + 'static,
+{
+    fn drop(&mut self) {
+        // The last sender going away ends every stream which is caught up
+        let waiting = self.inner.state.lock().unwrap().subs.remove_sender();
+        for h in waiting {
+            h.call(Ok(None));
+        }
+    }
+}
+
+impl<T> Receiver<T>
+    where T: Clone + Send + 'static,
+{
+    fn new(inner: &Arc<Inner<T>>) -> Receiver<T> {
+        let mut state = inner.state.lock().unwrap();
+        let next = state.head + state.buf.len() as u64;
+        let id = state.subs.add(next);
+        Receiver { inner: inner.clone(), id: id }
+    }
+}
+
+impl<T> Stream for Receiver<T>
+    where T: Clone + Send + 'static,
+{
+    type Item = T;
+    type Error = RecvError;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<Self::Item, Self::Error>) + Send + 'static
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self,
+                      g: Box<Callback<Option<Self::Item>, Self::Error>>) {
+        let ready = {
+            let mut state = self.inner.state.lock().unwrap();
+            let State { ref buf, head, ref mut subs, .. } = *state;
+            subs.schedule(self.id, g, |next| {
+                if *next < head {
+                    let skipped = head - *next;
+                    *next = head;
+                    Some(Err(PollError::Other(RecvError::Lagged(skipped))))
+                } else if *next < head + buf.len() as u64 {
+                    let msg = buf[(*next - head) as usize].clone();
+                    *next += 1;
+                    Some(Ok(Some(msg)))
+                } else {
+                    None
+                }
+            })
+        };
+        if let Some((h, res)) = ready {
+            h.call(res)
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T>
+    where T: Clone + Send + 'static,
+{
+    fn drop(&mut self) {
+        let waiting = self.inner.state.lock().unwrap().subs.remove(self.id);
+        if let Some(h) = waiting {
+            h.cancel();
+        }
+    }
+}
+
+impl<T> SendError<T> {
+    // Returns the message which couldn't be sent
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+// The message may well not be `Debug`, so it's left out like it is for the
+// other channels.
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SendError(..)")
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("sending on a channel with no receivers")
+    }
+}
+
+impl<T> Error for SendError<T> {}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RecvError::Lagged(n) => {
+                write!(f, "receiver lagged behind, missing {} messages", n)
+            }
+        }
+    }
+}
+
+impl Error for RecvError {}
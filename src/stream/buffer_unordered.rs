@@ -0,0 +1,43 @@
+use {Callback, IntoFuture};
+use stream::{Stream, StreamResult};
+use stream::buffered::Buffer;
+
+// Runs up to `max` of the futures yielded by a stream at once, yielding their
+// results as soon as they finish.
+pub struct BufferUnordered<S>
+    where S: Stream,
+          S::Item: IntoFuture,
+          <S::Item as IntoFuture>::Error: From<S::Error>,
+{
+    buffer: Buffer<S>,
+}
+
+pub fn new<S>(s: S, max: usize) -> BufferUnordered<S>
+    where S: Stream,
+          S::Item: IntoFuture,
+          <S::Item as IntoFuture>::Error: From<S::Error>,
+{
+    BufferUnordered {
+        buffer: Buffer::new(s, max, false),
+    }
+}
+
+impl<S> Stream for BufferUnordered<S>
+    where S: Stream,
+          S::Item: IntoFuture,
+          <S::Item as IntoFuture>::Error: From<S::Error>,
+{
+    type Item = <S::Item as IntoFuture>::Item;
+    type Error = <S::Item as IntoFuture>::Error;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<Self::Item, Self::Error>) + Send + 'static,
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self,
+                      g: Box<Callback<Option<Self::Item>, Self::Error>>) {
+        self.buffer.schedule(g)
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::VecDeque;
+
+use {Callback, Future, IntoFuture, PollResult};
+use stream::{Stream, StreamResult};
+use stream::driver::{Driver, Machine, Next, Notify};
+
+type FutureOf<S> = <<S as Stream>::Item as IntoFuture>::Future;
+
+// Runs up to `max` of the futures yielded by a stream at once, yielding their
+// results in the same order as the stream yielded the futures.
+pub struct Buffered<S>
+    where S: Stream,
+          S::Item: IntoFuture,
+          <S::Item as IntoFuture>::Error: From<S::Error>,
+{
+    buffer: Buffer<S>,
+}
+
+pub fn new<S>(s: S, max: usize) -> Buffered<S>
+    where S: Stream,
+          S::Item: IntoFuture,
+          <S::Item as IntoFuture>::Error: From<S::Error>,
+{
+    Buffered {
+        buffer: Buffer::new(s, max, true),
+    }
+}
+
+impl<S> Stream for Buffered<S>
+    where S: Stream,
+          S::Item: IntoFuture,
+          <S::Item as IntoFuture>::Error: From<S::Error>,
+{
+    type Item = <S::Item as IntoFuture>::Item;
+    type Error = <S::Item as IntoFuture>::Error;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<Self::Item, Self::Error>) + Send + 'static,
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self,
+                      g: Box<Callback<Option<Self::Item>, Self::Error>>) {
+        self.buffer.schedule(g)
+    }
+}
+
+// What's behind both `Buffered` and `BufferUnordered`.
+//
+// The stream is pulled from eagerly until `max` futures are either running or
+// waiting for their result to be handed out. Dropping this cancels the stream
+// and every future still running.
+pub struct Buffer<S>
+    where S: Stream,
+          S::Item: IntoFuture,
+          <S::Item as IntoFuture>::Error: From<S::Error>,
+{
+    driver: Driver<Queue<S>>,
+}
+
+struct Queue<S>
+    where S: Stream,
+          S::Item: IntoFuture,
+{
+    stream: S,
+    entries: VecDeque<Entry<FutureOf<S>>>,
+    max: usize,
+    ordered: bool,
+    next_id: usize,
+    stream_pending: bool,
+    stream_done: bool,
+}
+
+struct Entry<F: Future> {
+    id: usize,
+    // `None` once it's finished
+    future: Option<F>,
+    started: bool,
+    result: Option<PollResult<F::Item, F::Error>>,
+}
+
+enum Event<S>
+    where S: Stream,
+          S::Item: IntoFuture,
+{
+    Stream(StreamResult<S::Item, S::Error>),
+    Future(usize, PollResult<<S::Item as IntoFuture>::Item,
+                             <S::Item as IntoFuture>::Error>),
+}
+
+impl<S> Buffer<S>
+    where S: Stream,
+          S::Item: IntoFuture,
+          <S::Item as IntoFuture>::Error: From<S::Error>,
+{
+    pub fn new(s: S, max: usize, ordered: bool) -> Buffer<S> {
+        assert!(max > 0, "cannot buffer zero futures");
+        Buffer {
+            driver: Driver::new(Queue {
+                stream: s,
+                entries: VecDeque::new(),
+                max: max,
+                ordered: ordered,
+                next_id: 0,
+                stream_pending: false,
+                stream_done: false,
+            }),
+        }
+    }
+
+    pub fn schedule(&mut self,
+                    g: Box<Callback<Option<<S::Item as IntoFuture>::Item>,
+                                    <S::Item as IntoFuture>::Error>>) {
+        self.driver.schedule(g)
+    }
+}
+
+impl<S> Machine for Queue<S>
+    where S: Stream,
+          S::Item: IntoFuture,
+          <S::Item as IntoFuture>::Error: From<S::Error>,
+{
+    type Item = Option<<S::Item as IntoFuture>::Item>;
+    type Error = <S::Item as IntoFuture>::Error;
+    type Event = Event<S>;
+
+    // Everything is started whether or not anyone's waiting for a result yet.
+    fn start(&mut self, _wanted: bool, notify: &Notify<Self>) -> bool {
+        if !self.stream_pending && !self.stream_done &&
+           self.entries.len() < self.max {
+            self.stream_pending = true;
+            let notify = notify.clone();
+            self.stream.schedule(move |r| notify.done(Event::Stream(r)));
+            return true
+        }
+
+        let entry = self.entries.iter_mut().find(|e| !e.started);
+        if let Some(e) = entry {
+            e.started = true;
+            if let Some(ref mut f) = e.future {
+                let id = e.id;
+                let notify = notify.clone();
+                f.schedule(move |r| notify.done(Event::Future(id, r)));
+            }
+            return true
+        }
+        false
+    }
+
+    fn event(&mut self, event: Event<S>) {
+        match event {
+            Event::Stream(res) => {
+                self.stream_pending = false;
+                let (future, result) = match res {
+                    Ok(Some(f)) => (Some(f.into_future()), None),
+                    Ok(None) => {
+                        self.stream_done = true;
+                        return
+                    }
+                    Err(e) => (None, Some(Err(e.map(From::from)))),
+                };
+                let id = self.next_id;
+                self.next_id += 1;
+                self.entries.push_back(Entry {
+                    id: id,
+                    started: future.is_none(),
+                    future: future,
+                    result: result,
+                });
+            }
+            Event::Future(id, res) => {
+                if let Some(e) = self.entries.iter_mut().find(|e| e.id == id) {
+                    e.future = None;
+                    e.result = Some(res);
+                }
+            }
+        }
+    }
+
+    // Picks out the next result to hand out, if there is one.
+    fn next(&mut self) -> Option<Next<Self::Item, Self::Error>> {
+        let idx = if self.ordered {
+            self.entries.front().and_then(|e| e.result.as_ref()).map(|_| 0)
+        } else {
+            self.entries.iter().position(|e| e.result.is_some())
+        };
+        let res = idx.and_then(|i| self.entries.remove(i))
+                     .and_then(|e| e.result)
+                     .map(|r| r.map(Some));
+        match res {
+            Some(res) => Some(Next::Yield(res)),
+            None if self.entries.is_empty() && self.stream_done => {
+                Some(Next::Yield(Ok(None)))
+            }
+            None => None,
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use Callback;
+use stream::{Stream, StreamResult};
+use stream::parked::Parked;
+
+// Yields all of the elements of the first stream, and then all of the
+// elements of the second.
+pub struct Chain<S1, S2>
+    where S1: Stream,
+          S2: Stream,
+{
+    first: Parked<S1>,
+    second: Parked<S2>,
+    first_done: Arc<AtomicBool>,
+}
+
+pub fn new<S1, S2>(s1: S1, s2: S2) -> Chain<S1, S2>
+    where S1: Stream,
+          S2: Stream<Item=S1::Item, Error=S1::Error>,
+{
+    Chain {
+        first: Parked::new(s1),
+        second: Parked::new(s2),
+        first_done: Arc::new(AtomicBool::new(false)),
+    }
+}
+
+impl<S1, S2> Stream for Chain<S1, S2>
+    where S1: Stream,
+          S2: Stream<Item=S1::Item, Error=S1::Error>,
+{
+    type Item = S1::Item;
+    type Error = S1::Error;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<Self::Item, Self::Error>) + Send + 'static,
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self,
+                      g: Box<Callback<Option<Self::Item>, Self::Error>>) {
+        if self.first_done.load(Ordering::SeqCst) {
+            return self.second.schedule(g)
+        }
+        let first = self.first.clone();
+        let second = self.second.clone();
+        let first_done = self.first_done.clone();
+        self.first.schedule(Box::new(move |r| {
+            match r {
+                Ok(None) => {
+                    // The first stream is exhausted, so let it go and pick up
+                    // with the second.
+                    first_done.store(true, Ordering::SeqCst);
+                    first.cancel();
+                    second.schedule(g)
+                }
+                r => g.call(r),
+            }
+        }))
+    }
+}
+
+impl<S1, S2> Drop for Chain<S1, S2>
+    where S1: Stream,
+          S2: Stream,
+{
+    fn drop(&mut self) {
+        self.first.cancel();
+        self.second.cancel();
+    }
+}
@@ -1,19 +1,36 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
 use std::mem;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
-use {Future, PollResult, PollError, Callback};
-use slot::{Slot, Token};
-use stream::{Stream, StreamResult};
+use {Future, PollResult, PollError, Callback, finished};
+use executor::{self, Executor, Current};
+use stream::{Stream, StreamResult, Sink};
 use util;
 
-pub fn channel<T, E>() -> (Sender<T, E>, Receiver<T, E>)
+// Creates a bounded multi-producer, single-consumer channel.
+//
+// Up to `capacity` messages are buffered, after which `send` futures won't
+// resolve until the receiver has made room. With a capacity of 0 every send
+// waits for the receiver to take the message directly.
+//
+// The receiving half is a `Stream` which ends once every `Sender` has been
+// dropped and all buffered messages have been received.
+pub fn channel<T, E>(capacity: usize) -> (Sender<T, E>, Receiver<T, E>)
     where T: Send + 'static,
           E: Send + 'static,
 {
     let inner = Arc::new(Inner {
-        slot: Slot::new(None),
-        receiver_gone: AtomicBool::new(false),
+        state: Mutex::new(State {
+            buf: VecDeque::new(),
+            capacity: capacity,
+            senders: 1,
+            receiver_gone: false,
+            receiver: None,
+            blocked: VecDeque::new(),
+            next_id: 0,
+        }),
     });
     (Sender { inner: inner.clone() }, Receiver { inner: inner })
 }
@@ -37,9 +54,8 @@ enum _FutureSender<T, E>
           E: Send + 'static,
 {
     Start(Sender<T, E>, Result<T, E>),
-    Canceled,
     Used,
-    Scheduled(Arc<Inner<T, E>>, Token),
+    Scheduled(Arc<Inner<T, E>>, usize),
 }
 
 pub struct Receiver<T, E>
@@ -49,19 +65,67 @@ pub struct Receiver<T, E>
     inner: Arc<Inner<T, E>>,
 }
 
-struct Inner<T, E> {
-    slot: Slot<Message<Result<T, E>>>,
-    receiver_gone: AtomicBool,
+struct Inner<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    state: Mutex<State<T, E>>,
+}
+
+struct State<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    buf: VecDeque<Result<T, E>>,
+    capacity: usize,
+    senders: usize,
+    receiver_gone: bool,
+    // only ever set while `buf` is empty
+    receiver: Option<Handoff<T, E>>,
+    // senders waiting for room in `buf`, in the order they arrived
+    blocked: VecDeque<Blocked<T, E>>,
+    next_id: usize,
 }
 
-enum Message<T> {
-    Data(T),
-    Done,
-    // Canceled,
+// A waiting receiver along with the message it's to be given
+type Delivery<T, E> = (Handoff<T, E>, Result<T, E>);
+
+// A receiver waiting on a message
+struct Handoff<T, E> {
+    cb: Box<Callback<Option<T>, E>>,
+    executor: Current,
+}
+
+// A sender waiting for room to put its message
+struct Blocked<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    id: usize,
+    msg: Result<T, E>,
+    waiter: Waiter<T, E>,
 }
 
+struct Waiter<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    tx: Sender<T, E>,
+    cb: Box<Callback<Sender<T, E>, SendError<T, E>>>,
+    executor: Current,
+}
+
+// The receiving half of a channel was dropped, handing back the message which
+// couldn't be sent.
 pub struct SendError<T, E>(Result<T, E>);
 
+pub enum TrySendError<T, E> {
+    // The channel is at capacity
+    Full(Result<T, E>),
+    // The receiving half of the channel was dropped
+    Disconnected(Result<T, E>),
+}
+
 impl<T, E> Stream for Receiver<T, E>
     where T: Send + 'static,
           E: Send + 'static,
@@ -69,33 +133,56 @@ impl<T, E> Stream for Receiver<T, E>
     type Item = T;
     type Error = E;
 
-    // fn poll(&mut self) -> Option<StreamResult<Self::Item, Self::Error>> {
-    //     match self.inner.slot.try_consume() {
-    //         Ok(Message::Data(Ok(e))) => Some(Ok(Some(e))),
-    //         Ok(Message::Data(Err(e))) => Some(Err(PollError::Other(e))),
-    //         Ok(Message::Done) => Some(Ok(None)),
-    //         // Ok(Message::Canceled) => Some(Err(PollError::Canceled)),
-    //         Err(..) => None,
-    //     }
-    // }
-
     fn schedule<G>(&mut self, g: G)
         where G: FnOnce(StreamResult<Self::Item, Self::Error>) + Send + 'static
     {
-        self.inner.slot.on_full(|slot| {
-            g(match slot.try_consume() {
-                Ok(Message::Data(Ok(e))) => Ok(Some(e)),
-                Ok(Message::Data(Err(e))) => Err(PollError::Other(e)),
-                Ok(Message::Done) => Ok(None),
-                // Ok(Message::Canceled) => Err(PollError::Canceled),
-                Err(..) => Err(PollError::Canceled),
-            })
-        });
+        self.schedule_boxed(Box::new(g))
     }
 
     fn schedule_boxed(&mut self,
                       g: Box<Callback<Option<Self::Item>, Self::Error>>) {
-        self.schedule(|f| g.call(f))
+        let executor = executor::current();
+        let mut state = self.inner.state.lock().unwrap();
+        if state.receiver.is_some() {
+            drop(state);
+            return executor.execute(|| g.call(Err(util::reused())))
+        }
+
+        // Take the next message, and if anyone is blocked then their message
+        // moves into the space we just made (or straight to us if there's no
+        // buffer to speak of).
+        let (msg, unblocked) = match state.buf.pop_front() {
+            Some(msg) => {
+                let w = state.blocked.pop_front().map(|b| {
+                    state.buf.push_back(b.msg);
+                    b.waiter
+                });
+                (Some(msg), w)
+            }
+            None => {
+                match state.blocked.pop_front() {
+                    Some(b) => (Some(b.msg), Some(b.waiter)),
+                    None => (None, None),
+                }
+            }
+        };
+        let msg = match msg {
+            Some(msg) => msg,
+            None if state.senders == 0 => {
+                drop(state);
+                return executor.execute(|| g.call(Ok(None)))
+            }
+            None => {
+                state.receiver = Some(Handoff { cb: g, executor: executor });
+                return
+            }
+        };
+        drop(state);
+
+        if let Some(w) = unblocked {
+            w.finish();
+        }
+        Handoff { cb: g, executor: executor }.finish(msg)
     }
 }
 
@@ -104,10 +191,20 @@ impl<T, E> Drop for Receiver<T, E>
           E: Send + 'static,
 {
     fn drop(&mut self) {
-        self.inner.receiver_gone.store(true, Ordering::SeqCst);
-        self.inner.slot.on_full(|slot| {
-            drop(slot.try_consume());
-        });
+        let (buf, blocked, receiver) = {
+            let mut state = self.inner.state.lock().unwrap();
+            state.receiver_gone = true;
+            (state.buf.drain(..).collect::<Vec<_>>(),
+             state.blocked.drain(..).collect::<Vec<_>>(),
+             state.receiver.take())
+        };
+        drop(buf);
+        for b in blocked {
+            b.waiter.fail(PollError::Other(SendError(b.msg)));
+        }
+        if let Some(r) = receiver {
+            r.call(Err(PollError::Canceled));
+        }
     }
 }
 
@@ -115,10 +212,82 @@ impl<T, E> Sender<T, E>
     where T: Send + 'static,
           E: Send + 'static,
 {
-    pub fn send(self, t: Result<T, E>) -> FutureSender<T, E> {
+    // Returns a future which resolves back to this sender once `msg` has been
+    // placed in the channel.
+    pub fn send(self, msg: Result<T, E>) -> FutureSender<T, E> {
         FutureSender {
-            state: _FutureSender::Start(self, t),
+            state: _FutureSender::Start(self, msg),
+        }
+    }
+
+    // Attempts to place `msg` in the channel without waiting, handing it back
+    // if the channel is full or the receiver is gone.
+    pub fn try_send(&self, msg: Result<T, E>) -> Result<(), TrySendError<T, E>> {
+        let handoff = {
+            let mut state = self.inner.state.lock().unwrap();
+            try!(state.push(msg))
+        };
+        if let Some((r, msg)) = handoff {
+            r.finish(msg);
         }
+        Ok(())
+    }
+}
+
+// Messages are handed straight to the receiver or its buffer, so there's
+// never anything to flush. The channel itself only closes once every sender
+// has been dropped.
+impl<T, E> Sink for Sender<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    type Item = Result<T, E>;
+    type Error = SendError<T, E>;
+
+    fn start_send(&mut self, msg: Result<T, E>)
+                  -> Box<Future<Item=(), Error=SendError<T, E>>> {
+        self.clone().send(msg).map(|_| ()).boxed()
+    }
+
+    fn flush(&mut self) -> Box<Future<Item=(), Error=SendError<T, E>>> {
+        finished(()).boxed()
+    }
+
+    fn close(&mut self) -> Box<Future<Item=(), Error=SendError<T, E>>> {
+        finished(()).boxed()
+    }
+}
+
+impl<T, E> State<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    // Places `msg` in the buffer if there's room. If the receiver is waiting
+    // then it's handed back so it can be given the message outside the lock.
+    fn push(&mut self, msg: Result<T, E>)
+            -> Result<Option<Delivery<T, E>>, TrySendError<T, E>> {
+        if self.receiver_gone {
+            return Err(TrySendError::Disconnected(msg))
+        }
+        if let Some(r) = self.receiver.take() {
+            return Ok(Some((r, msg)))
+        }
+        if self.buf.len() < self.capacity {
+            self.buf.push_back(msg);
+            Ok(None)
+        } else {
+            Err(TrySendError::Full(msg))
+        }
+    }
+}
+
+impl<T, E> Clone for Sender<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    fn clone(&self) -> Sender<T, E> {
+        self.inner.state.lock().unwrap().senders += 1;
+        Sender { inner: self.inner.clone() }
     }
 }
 
@@ -127,9 +296,15 @@ impl<T, E> Drop for Sender<T, E>
           E: Send + 'static,
 {
     fn drop(&mut self) {
-        self.inner.slot.on_empty(|slot| {
-            slot.try_produce(Message::Done).ok().unwrap();
-        });
+        // The last sender going away ends the stream
+        let receiver = {
+            let mut state = self.inner.state.lock().unwrap();
+            state.senders -= 1;
+            if state.senders == 0 {state.receiver.take()} else {None}
+        };
+        if let Some(r) = receiver {
+            r.call(Ok(None));
+        }
     }
 }
 
@@ -140,73 +315,183 @@ impl<T, E> Future for FutureSender<T, E>
     type Item = Sender<T, E>;
     type Error = SendError<T, E>;
 
-    // fn poll(&mut self) -> Option<PollResult<Self::Item, Self::Error>> {
-    //     match mem::replace(&mut self.state, _FutureSender::Used) {
-    //         _FutureSender::Start(tx, msg) => {
-    //             if tx.inner.receiver_gone.load(Ordering::SeqCst) {
-    //                 return Some(Err(PollError::Other(SendError(msg))))
-    //             }
-    //             match tx.inner.slot.try_produce(Message::Data(msg)) {
-    //                 Ok(()) => Some(Ok(tx)),
-    //                 Err(e) => {
-    //                     let msg = match e.into_inner() {
-    //                         Message::Data(d) => d,
-    //                         _ => panic!(),
-    //                     };
-    //                     self.state = _FutureSender::Start(tx, msg);
-    //                     None
-    //                 }
-    //             }
-    //         }
-    //         _FutureSender::Canceled => Some(Err(PollError::Canceled)),
-    //         _FutureSender::Used => Some(Err(util::reused())),
-    //         _FutureSender::Scheduled(s, token) => {
-    //             self.state = _FutureSender::Scheduled(s, token);
-    //             Some(Err(util::reused()))
-    //         }
-    //     }
-    // }
-
-    // TODO: move this to Drop
-    // fn cancel(&mut self) {
-    //     match mem::replace(&mut self.state, _FutureSender::Canceled) {
-    //         _FutureSender::Start(..) => {}
-    //         _FutureSender::Canceled => {}
-    //         _FutureSender::Scheduled(s, token) => {
-    //             s.slot.cancel(token);
-    //         }
-    //         _FutureSender::Used => self.state = _FutureSender::Used,
-    //     }
-    // }
+    fn poll(&mut self) -> Option<PollResult<Self::Item, Self::Error>> {
+        match mem::replace(&mut self.state, _FutureSender::Used) {
+            _FutureSender::Start(tx, msg) => {
+                match tx.try_send(msg) {
+                    Ok(()) => Some(Ok(tx)),
+                    Err(TrySendError::Disconnected(msg)) => {
+                        Some(Err(PollError::Other(SendError(msg))))
+                    }
+                    Err(TrySendError::Full(msg)) => {
+                        self.state = _FutureSender::Start(tx, msg);
+                        None
+                    }
+                }
+            }
+            _FutureSender::Used => Some(Err(util::reused())),
+            _FutureSender::Scheduled(s, id) => {
+                self.state = _FutureSender::Scheduled(s, id);
+                Some(Err(util::reused()))
+            }
+        }
+    }
 
     fn schedule<F>(&mut self, f: F)
         where F: FnOnce(PollResult<Self::Item, Self::Error>) + Send + 'static,
     {
+        self.schedule_boxed(Box::new(f))
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<Self::Item, Self::Error>>) {
+        let executor = executor::current();
         let (tx, msg) = match mem::replace(&mut self.state, _FutureSender::Used) {
             _FutureSender::Start(tx, msg) => (tx, msg),
-            _FutureSender::Canceled => return f(Err(PollError::Canceled)),
-            _FutureSender::Used => return f(Err(util::reused())),
-            _FutureSender::Scheduled(s, token) => {
-                self.state = _FutureSender::Scheduled(s, token);
-                return f(Err(util::reused()))
+            _FutureSender::Used => {
+                return executor.execute(|| cb.call(Err(util::reused())))
+            }
+            _FutureSender::Scheduled(s, id) => {
+                self.state = _FutureSender::Scheduled(s, id);
+                return executor.execute(|| cb.call(Err(util::reused())))
+            }
+        };
+        let inner = tx.inner.clone();
+        let mut state = inner.state.lock().unwrap();
+        let res = match state.push(msg) {
+            Ok(handoff) => Ok(handoff),
+            Err(TrySendError::Disconnected(msg)) => Err(SendError(msg)),
+            Err(TrySendError::Full(msg)) => {
+                let id = state.next_id;
+                state.next_id += 1;
+                state.blocked.push_back(Blocked {
+                    id: id,
+                    msg: msg,
+                    waiter: Waiter { tx: tx, cb: cb, executor: executor },
+                });
+                drop(state);
+                self.state = _FutureSender::Scheduled(inner.clone(), id);
+                return
             }
         };
-        let arc = tx.inner.clone();
-        let token = arc.slot.on_empty(|slot| {
-            if tx.inner.receiver_gone.load(Ordering::SeqCst) {
-                return f(Err(PollError::Other(SendError(msg))))
+        drop(state);
+        match res {
+            Ok(handoff) => {
+                if let Some((r, msg)) = handoff {
+                    r.finish(msg);
+                }
+                executor.execute(|| cb.call(Ok(tx)))
+            }
+            Err(e) => {
+                drop(tx);
+                executor.execute(|| cb.call(Err(PollError::Other(e))))
             }
-            match slot.try_produce(Message::Data(msg)) {
-                Ok(()) => f(Ok(tx)),
+        }
+    }
+}
 
-                // we were canceled so finish immediately
-                Err(..) => f(Err(PollError::Canceled)),
+impl<T, E> Drop for FutureSender<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    fn drop(&mut self) {
+        if let _FutureSender::Scheduled(ref inner, id) = self.state {
+            let blocked = {
+                let mut state = inner.state.lock().unwrap();
+                state.blocked.iter()
+                     .position(|b| b.id == id)
+                     .and_then(|i| state.blocked.remove(i))
+            };
+            if let Some(b) = blocked {
+                b.waiter.fail(PollError::Canceled);
             }
-        });
-        self.state = _FutureSender::Scheduled(arc, token);
+        }
+    }
+}
+
+impl<T, E> Handoff<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    fn finish(self, msg: Result<T, E>) {
+        self.call(match msg {
+            Ok(t) => Ok(Some(t)),
+            Err(e) => Err(PollError::Other(e)),
+        })
+    }
+
+    fn call(self, res: StreamResult<T, E>) {
+        let Handoff { cb, executor } = self;
+        executor.execute(|| cb.call(res))
+    }
+}
+
+impl<T, E> Waiter<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    fn finish(self) {
+        let Waiter { tx, cb, executor } = self;
+        executor.execute(|| cb.call(Ok(tx)))
+    }
+
+    fn fail(self, e: PollError<SendError<T, E>>) {
+        let Waiter { tx, cb, executor } = self;
+        drop(tx);
+        executor.execute(|| cb.call(Err(e)))
+    }
+}
+
+impl<T, E> SendError<T, E> {
+    // Returns the message which couldn't be sent
+    pub fn into_inner(self) -> Result<T, E> {
+        self.0
     }
+}
 
-    fn schedule_boxed(&mut self, f: Box<Callback<Self::Item, Self::Error>>) {
-        self.schedule(|r| f.call(r))
+impl<T, E> TrySendError<T, E> {
+    // Returns the message which couldn't be sent
+    pub fn into_inner(self) -> Result<T, E> {
+        match self {
+            TrySendError::Full(msg) => msg,
+            TrySendError::Disconnected(msg) => msg,
+        }
     }
 }
+
+// The message may well not be `Debug`, so it's left out like it is for the
+// channels in `std::sync::mpsc`.
+impl<T, E> fmt::Debug for SendError<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SendError(..)")
+    }
+}
+
+impl<T, E> fmt::Display for SendError<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("sending on a closed channel")
+    }
+}
+
+impl<T, E> Error for SendError<T, E> {}
+
+impl<T, E> fmt::Debug for TrySendError<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TrySendError::Full(..) => f.write_str("Full(..)"),
+            TrySendError::Disconnected(..) => f.write_str("Disconnected(..)"),
+        }
+    }
+}
+
+impl<T, E> fmt::Display for TrySendError<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TrySendError::Full(..) => f.write_str("sending on a full channel"),
+            TrySendError::Disconnected(..) => {
+                f.write_str("sending on a closed channel")
+            }
+        }
+    }
+}
+
+impl<T, E> Error for TrySendError<T, E> {}
@@ -0,0 +1,96 @@
+use std::mem;
+use std::sync::Arc;
+
+use Callback;
+use slot::Slot;
+use stream::{Stream, StreamResult};
+use util;
+
+pub struct Chunks<S> where S: Stream {
+    inner: Arc<_Chunks<S>>,
+}
+
+struct _Chunks<S> where S: Stream {
+    stream: Slot<S>,
+    items: Slot<Vec<S::Item>>,
+    cap: usize,
+}
+
+pub fn new<S>(s: S, capacity: usize) -> Chunks<S> where S: Stream {
+    assert!(capacity > 0, "chunks must have a non-zero capacity");
+    Chunks {
+        inner: Arc::new(_Chunks {
+            stream: Slot::new(Some(s)),
+            items: Slot::new(Some(Vec::with_capacity(capacity))),
+            cap: capacity,
+        }),
+    }
+}
+
+impl<S> Chunks<S>
+    where S: Stream,
+{
+    fn doit<G>(&self,
+               mut stream: S,
+               mut items: Vec<S::Item>,
+               g: G)
+        where G: FnOnce(StreamResult<Vec<S::Item>, S::Error>) + Send + 'static,
+    {
+        let slot = self.inner.clone();
+        stream.schedule(move |res| {
+            let res = match res {
+                Ok(Some(e)) => {
+                    items.push(e);
+                    if items.len() < slot.cap {
+                        let slot2 = slot.clone();
+                        slot.stream.on_full(|slot| {
+                            let stream = slot.try_consume().ok().unwrap();
+                            Chunks {
+                                inner: slot2,
+                            }.doit(stream, items, g)
+                        }).unwrap();
+                        return
+                    }
+                    Ok(Some(mem::replace(&mut items,
+                                         Vec::with_capacity(slot.cap))))
+                }
+                // Flush whatever we've got left before ending the stream
+                Ok(None) if !items.is_empty() => {
+                    Ok(Some(mem::replace(&mut items,
+                                         Vec::with_capacity(slot.cap))))
+                }
+                Ok(None) => Ok(None),
+                Err(e) => Err(e),
+            };
+            slot.items.try_produce(items).ok()
+                .expect("chunks stream failed to produce");
+            g(res)
+        });
+        self.inner.stream.try_produce(stream).ok()
+            .expect("chunks failed to produce stream");
+    }
+}
+
+impl<S> Stream for Chunks<S>
+    where S: Stream,
+{
+    type Item = Vec<S::Item>;
+    type Error = S::Error;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<Self::Item, Self::Error>) + Send + 'static,
+    {
+        let items = match util::opt2poll(self.inner.items.try_consume().ok()) {
+            Ok(items) => items,
+            Err(e) => return g(Err(e)),
+        };
+        let stream = self.inner.stream.try_consume().ok()
+                             .expect("chunks got items, failed to get stream");
+        self.doit(stream, items, g)
+    }
+
+    fn schedule_boxed(&mut self,
+                      g: Box<Callback<Option<Self::Item>, Self::Error>>) {
+        self.schedule(|r| g.call(r))
+    }
+}
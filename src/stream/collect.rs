@@ -0,0 +1,39 @@
+use {Future, Callback, PollResult};
+use stream::Stream;
+use stream::fold::{self, Fold};
+
+pub struct Collect<S> where S: Stream {
+    fold: Fold<S, Push<S::Item>, Vec<S::Item>>,
+}
+
+type Push<T> = fn(Vec<T>, T) -> Vec<T>;
+
+pub fn new<S>(s: S) -> Collect<S> where S: Stream {
+    Collect {
+        fold: fold::new(s, push as Push<S::Item>, Vec::new()),
+    }
+}
+
+fn push<T>(mut v: Vec<T>, t: T) -> Vec<T> {
+    v.push(t);
+    v
+}
+
+impl<S: Stream> Future for Collect<S> {
+    type Item = Vec<S::Item>;
+    type Error = S::Error;
+
+    fn poll(&mut self) -> Option<PollResult<Self::Item, Self::Error>> {
+        self.fold.poll()
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<Self::Item, Self::Error>) + Send + 'static
+    {
+        self.fold.schedule(g)
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<Self::Item, Self::Error>>) {
+        self.fold.schedule_boxed(cb)
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use {Callback, PollResult, PollError};
+use executor::{self, Executor, Current};
+use util;
+
+// Runs the state machine behind a combinator which reacts to the results of
+// the streams and futures it's made of, like `fold`, `zip` or `buffered`.
+//
+// The machine only ever runs on one thread at a time, and never with the lock
+// held. Results which come in while it's busy are queued up for whoever is
+// running it, so it never has to worry about being called into while it's
+// scheduling something. Results for the callback are always handed off
+// through the executor it was scheduled on.
+//
+// Dropping this drops the machine, canceling whatever it's running.
+pub struct Driver<M: Machine> {
+    inner: Arc<Inner<M>>,
+}
+
+// What a combinator supplies to a `Driver`.
+pub trait Machine: Send + Sized + 'static {
+    type Item: Send + 'static;
+    type Error: Send + 'static;
+    type Event: Send + 'static;
+
+    // Starts up something new if there's anything to start, with its result
+    // coming back through `notify`. `wanted` says whether there's a callback
+    // waiting for a result.
+    //
+    // Returns whether anything changed, in which case the driver comes back
+    // to look for a result and to call this again.
+    fn start(&mut self, wanted: bool, notify: &Notify<Self>) -> bool;
+
+    // Takes in a result which came back through a `Notify`.
+    fn event(&mut self, event: Self::Event);
+
+    // Hands out the next result for the callback, if there is one yet.
+    fn next(&mut self) -> Option<Next<Self::Item, Self::Error>>;
+}
+
+pub enum Next<T, E> {
+    // There might be more to come for the next callback
+    Yield(PollResult<T, E>),
+    // This is the last result, and the machine is dropped after it
+    Done(PollResult<T, E>),
+}
+
+// A handle for passing results back to a driver.
+pub struct Notify<M: Machine> {
+    inner: Arc<Inner<M>>,
+}
+
+struct Inner<M: Machine> {
+    state: Mutex<State<M>>,
+}
+
+struct State<M: Machine> {
+    // `None` while it's running, or once we're done
+    machine: Option<M>,
+    events: VecDeque<M::Event>,
+    cb: Option<Waiting<M::Item, M::Error>>,
+    // the executor of the most recent callback, which is where everything
+    // gets started from
+    executor: Current,
+    done: bool,
+}
+
+struct Waiting<T, E> {
+    cb: Box<Callback<T, E>>,
+    executor: Current,
+}
+
+impl<M: Machine> Driver<M> {
+    pub fn new(machine: M) -> Driver<M> {
+        Driver {
+            inner: Arc::new(Inner {
+                state: Mutex::new(State {
+                    machine: Some(machine),
+                    events: VecDeque::new(),
+                    cb: None,
+                    executor: executor::current(),
+                    done: false,
+                }),
+            }),
+        }
+    }
+
+    // Waits for the next result, failing if there's already a callback
+    // waiting or the machine is done.
+    pub fn schedule(&self, cb: Box<Callback<M::Item, M::Error>>) {
+        let executor = executor::current();
+        {
+            let mut state = self.inner.state.lock().unwrap();
+            if state.cb.is_none() && !state.done {
+                state.executor = executor.clone();
+                state.cb = Some(Waiting { cb: cb, executor: executor });
+                drop(state);
+                return Inner::drive(&self.inner)
+            }
+        }
+        executor.execute(|| cb.call(Err(util::reused())))
+    }
+
+    // Passes `event` to the machine, just like it had come through a
+    // `Notify`.
+    pub fn event(&self, event: M::Event) {
+        Notify { inner: self.inner.clone() }.done(event)
+    }
+}
+
+impl<M: Machine> Inner<M> {
+    fn drive(me: &Arc<Inner<M>>) {
+        let machine = me.state.lock().unwrap().machine.take();
+        let mut m = match machine {
+            Some(m) => m,
+            None => return,
+        };
+        let notify = Notify { inner: me.clone() };
+        loop {
+            let mut state = me.state.lock().unwrap();
+            if state.done {
+                drop(state);
+                drop(m);
+                return
+            }
+            if let Some(event) = state.events.pop_front() {
+                drop(state);
+                m.event(event);
+                continue
+            }
+            let wanted = state.cb.is_some();
+            let executor = state.executor.clone();
+            drop(state);
+
+            let next = if wanted {m.next()} else {None};
+            if let Some(next) = next {
+                let (res, done) = match next {
+                    Next::Yield(res) => (res, false),
+                    Next::Done(res) => (res, true),
+                };
+                let cb = {
+                    let mut state = me.state.lock().unwrap();
+                    state.done = state.done || done;
+                    state.cb.take()
+                };
+                if done {
+                    drop(m);
+                    if let Some(w) = cb {
+                        w.call(res);
+                    }
+                    return
+                }
+                if let Some(w) = cb {
+                    w.call(res);
+                }
+                continue
+            }
+
+            if executor.enter(|| m.start(wanted, &notify)) {
+                continue
+            }
+
+            // There's nothing to do until something happens, but something
+            // may well have happened while we weren't looking.
+            let mut state = me.state.lock().unwrap();
+            if !state.done && state.events.is_empty() &&
+               state.cb.is_some() == wanted {
+                state.machine = Some(m);
+                return
+            }
+        }
+    }
+}
+
+impl<T: Send + 'static, E: Send + 'static> Waiting<T, E> {
+    fn call(self, res: PollResult<T, E>) {
+        let Waiting { cb, executor } = self;
+        executor.execute(|| cb.call(res))
+    }
+}
+
+impl<M: Machine> Notify<M> {
+    pub fn done(&self, event: M::Event) {
+        {
+            let mut state = self.inner.state.lock().unwrap();
+            if !state.done {
+                state.events.push_back(event);
+                drop(state);
+                return Inner::drive(&self.inner)
+            }
+        }
+        // Drop whatever it is now that we're no longer holding the lock
+        drop(event);
+    }
+}
+
+impl<M: Machine> Clone for Notify<M> {
+    fn clone(&self) -> Notify<M> {
+        Notify { inner: self.inner.clone() }
+    }
+}
+
+impl<M: Machine> Drop for Driver<M> {
+    fn drop(&mut self) {
+        let (machine, events, cb) = {
+            let mut state = self.inner.state.lock().unwrap();
+            state.done = true;
+            (state.machine.take(),
+             state.events.drain(..).collect::<Vec<_>>(),
+             state.cb.take())
+        };
+        drop(machine);
+        drop(events);
+        if let Some(w) = cb {
+            w.call(Err(PollError::Canceled));
+        }
+    }
+}
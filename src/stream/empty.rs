@@ -0,0 +1,37 @@
+use std::marker;
+
+use executor::{self, Executor};
+use Callback;
+use stream::{Stream, StreamResult};
+
+pub struct Empty<T, E> {
+    _marker: marker::PhantomData<(T, E)>,
+}
+
+// Creates a stream which ends immediately without yielding anything.
+pub fn empty<T, E>() -> Empty<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    Empty {
+        _marker: marker::PhantomData,
+    }
+}
+
+impl<T, E> Stream for Empty<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    type Item = T;
+    type Error = E;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<T, E>) + Send + 'static,
+    {
+        executor::current().execute(|| g(Ok(None)))
+    }
+
+    fn schedule_boxed(&mut self, g: Box<Callback<Option<T>, E>>) {
+        self.schedule(|r| g.call(r))
+    }
+}
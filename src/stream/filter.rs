@@ -49,7 +49,7 @@ impl<S, F> Filter<S, F>
                                 Filter {
                                     inner: slot2,
                                 }.doit(stream, f, g)
-                            });
+                            }).unwrap();
                             return
                         }
                         Err(e) => (None, Err(e)),
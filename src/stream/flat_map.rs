@@ -0,0 +1,116 @@
+use Callback;
+use stream::{Stream, StreamResult};
+use stream::driver::{Driver, Machine, Next, Notify};
+
+// Like `FutureChain`, except the second half is itself a stream which is
+// drained before moving on to the next element of the outer stream.
+pub struct FlatMap<S>
+    where S: Stream,
+          S::Item: Stream,
+          <S::Item as Stream>::Error: From<S::Error>,
+{
+    driver: Driver<Flattener<S>>,
+}
+
+struct Flattener<S>
+    where S: Stream,
+          S::Item: Stream,
+{
+    outer: S,
+    inner: Option<S::Item>,
+    running: bool,
+    res: Option<StreamResult<<S::Item as Stream>::Item,
+                             <S::Item as Stream>::Error>>,
+}
+
+enum Event<S>
+    where S: Stream,
+          S::Item: Stream,
+{
+    Outer(StreamResult<S::Item, S::Error>),
+    Inner(StreamResult<<S::Item as Stream>::Item, <S::Item as Stream>::Error>),
+}
+
+pub fn new<S>(s: S) -> FlatMap<S>
+    where S: Stream,
+          S::Item: Stream,
+          <S::Item as Stream>::Error: From<S::Error>,
+{
+    FlatMap {
+        driver: Driver::new(Flattener {
+            outer: s,
+            inner: None,
+            running: false,
+            res: None,
+        }),
+    }
+}
+
+impl<S> Stream for FlatMap<S>
+    where S: Stream,
+          S::Item: Stream,
+          <S::Item as Stream>::Error: From<S::Error>,
+{
+    type Item = <S::Item as Stream>::Item;
+    type Error = <S::Item as Stream>::Error;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<Self::Item, Self::Error>) + Send + 'static,
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self,
+                      g: Box<Callback<Option<Self::Item>, Self::Error>>) {
+        self.driver.schedule(g)
+    }
+}
+
+impl<S> Machine for Flattener<S>
+    where S: Stream,
+          S::Item: Stream,
+          <S::Item as Stream>::Error: From<S::Error>,
+{
+    type Item = Option<<S::Item as Stream>::Item>;
+    type Error = <S::Item as Stream>::Error;
+    type Event = Event<S>;
+
+    fn start(&mut self, wanted: bool, notify: &Notify<Self>) -> bool {
+        if !wanted || self.running || self.res.is_some() {
+            return false
+        }
+        self.running = true;
+        let notify = notify.clone();
+        match self.inner {
+            Some(ref mut s) => {
+                s.schedule(move |r| notify.done(Event::Inner(r)))
+            }
+            None => {
+                self.outer.schedule(move |r| notify.done(Event::Outer(r)))
+            }
+        }
+        true
+    }
+
+    fn event(&mut self, event: Event<S>) {
+        self.running = false;
+        self.res = match event {
+            Event::Outer(Ok(Some(s))) => {
+                self.inner = Some(s);
+                None
+            }
+            Event::Outer(Ok(None)) => Some(Ok(None)),
+            Event::Outer(Err(e)) => Some(Err(e.map(From::from))),
+            Event::Inner(Ok(None)) => {
+                // This stream is done, so move on to the next one
+                self.inner = None;
+                None
+            }
+            Event::Inner(res) => Some(res),
+        };
+    }
+
+    fn next(&mut self) -> Option<Next<Self::Item, Self::Error>> {
+        self.res.take().map(Next::Yield)
+    }
+}
@@ -0,0 +1,53 @@
+use {Callback, IntoFuture};
+use stream::{Stream, StreamResult};
+use stream::future_chain::{FutureChain, Next};
+
+pub struct Flatten<S>
+    where S: Stream,
+          S::Item: IntoFuture,
+{
+    chain: FutureChain<S, <S::Item as IntoFuture>::Future, ()>,
+}
+
+pub fn new<S>(s: S) -> Flatten<S>
+    where S: Stream,
+          S::Item: IntoFuture,
+          <S::Item as IntoFuture>::Error: From<S::Error>,
+{
+    Flatten {
+        chain: FutureChain::new(s, (), flatten::<S>),
+    }
+}
+
+impl<S> Stream for Flatten<S>
+    where S: Stream,
+          S::Item: IntoFuture,
+          <S::Item as IntoFuture>::Error: From<S::Error>,
+{
+    type Item = <S::Item as IntoFuture>::Item;
+    type Error = <S::Item as IntoFuture>::Error;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<Self::Item, Self::Error>) + Send + 'static,
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self,
+                      g: Box<Callback<Option<Self::Item>, Self::Error>>) {
+        self.chain.schedule(g)
+    }
+}
+
+fn flatten<S>(res: StreamResult<S::Item, S::Error>, _: &mut ())
+              -> Next<<S::Item as IntoFuture>::Future>
+    where S: Stream,
+          S::Item: IntoFuture,
+          <S::Item as IntoFuture>::Error: From<S::Error>,
+{
+    match res {
+        Ok(Some(f)) => Next::Run(f.into_future()),
+        Ok(None) => Next::Done(Ok(None)),
+        Err(e) => Next::Done(Err(e.map(From::from))),
+    }
+}
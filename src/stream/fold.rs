@@ -0,0 +1,141 @@
+use std::mem;
+
+use executor::{self, Executor};
+use {Future, Callback, PollResult};
+use stream::{Stream, StreamResult};
+use stream::driver::{Driver, Machine, Next, Notify};
+use util;
+
+pub struct Fold<S, F, T>
+    where S: Stream,
+          F: FnMut(T, S::Item) -> T + Send + 'static,
+          T: Send + 'static,
+{
+    state: State<S, F, T>,
+}
+
+enum State<S, F, T>
+    where S: Stream,
+          F: FnMut(T, S::Item) -> T + Send + 'static,
+          T: Send + 'static,
+{
+    Start(S, F, T),
+    Scheduled(Driver<Folder<S, F, T>>),
+    Done,
+}
+
+struct Folder<S, F, T>
+    where S: Stream,
+          T: Send + 'static,
+{
+    stream: S,
+    // `None` while `f` is running, or if it panicked
+    f: Option<(F, T)>,
+    running: bool,
+    res: Option<PollResult<T, S::Error>>,
+}
+
+pub fn new<S, F, T>(s: S, f: F, init: T) -> Fold<S, F, T>
+    where S: Stream,
+          F: FnMut(T, S::Item) -> T + Send + 'static,
+          T: Send + 'static,
+{
+    Fold {
+        state: State::Start(s, f, init),
+    }
+}
+
+impl<S, F, T> Future for Fold<S, F, T>
+    where S: Stream,
+          F: FnMut(T, S::Item) -> T + Send + 'static,
+          T: Send + 'static,
+{
+    type Item = T;
+    type Error = S::Error;
+
+    fn poll(&mut self) -> Option<PollResult<T, S::Error>> {
+        match self.state {
+            // Streams can't be polled, so the only way to make progress is to
+            // get scheduled.
+            State::Start(..) => None,
+            State::Scheduled(..) | State::Done => Some(Err(util::reused())),
+        }
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<T, S::Error>) + Send + 'static
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<T, S::Error>>) {
+        let (stream, f, val) = match mem::replace(&mut self.state, State::Done) {
+            State::Start(stream, f, val) => (stream, f, val),
+            State::Scheduled(s) => {
+                self.state = State::Scheduled(s);
+                return executor::current().execute(|| cb.call(Err(util::reused())))
+            }
+            State::Done => {
+                return executor::current().execute(|| cb.call(Err(util::reused())))
+            }
+        };
+        let driver = Driver::new(Folder {
+            stream: stream,
+            f: Some((f, val)),
+            running: false,
+            res: None,
+        });
+        driver.schedule(cb);
+        self.state = State::Scheduled(driver);
+    }
+}
+
+impl<S, F, T> Machine for Folder<S, F, T>
+    where S: Stream,
+          F: FnMut(T, S::Item) -> T + Send + 'static,
+          T: Send + 'static,
+{
+    type Item = T;
+    type Error = S::Error;
+    type Event = StreamResult<S::Item, S::Error>;
+
+    fn start(&mut self, _wanted: bool, notify: &Notify<Self>) -> bool {
+        if self.running || self.res.is_some() {
+            return false
+        }
+        self.running = true;
+        let notify = notify.clone();
+        self.stream.schedule(move |r| notify.done(r));
+        true
+    }
+
+    fn event(&mut self, res: StreamResult<S::Item, S::Error>) {
+        self.running = false;
+        self.res = match res {
+            Ok(Some(e)) => {
+                let (f, val) = self.f.take().expect("[fold] f not here");
+                let res = util::recover(move || {
+                    let mut f = f;
+                    let val = f(val, e);
+                    (f, val)
+                });
+                match res {
+                    Ok(pair) => {
+                        self.f = Some(pair);
+                        return
+                    }
+                    Err(e) => Some(Err(e)),
+                }
+            }
+            Ok(None) => {
+                let (_, val) = self.f.take().expect("[fold] val not here");
+                Some(Ok(val))
+            }
+            Err(e) => Some(Err(e)),
+        };
+    }
+
+    fn next(&mut self) -> Option<Next<T, S::Error>> {
+        self.res.take().map(Next::Done)
+    }
+}
@@ -0,0 +1,191 @@
+use std::mem;
+
+use executor::{self, Executor};
+use {Future, Callback, PollResult};
+use stream::{Stream, StreamResult, Sink};
+use stream::driver::{Driver, Machine, Next, Notify};
+use util;
+
+// Sends every element of a stream into a sink, then flushes the sink and
+// resolves back to it.
+pub struct Forward<S, K>
+    where S: Stream,
+          K: Sink<Item=S::Item>,
+          K::Error: From<S::Error>,
+{
+    state: State<S, K>,
+}
+
+enum State<S, K>
+    where S: Stream,
+          K: Sink<Item=S::Item>,
+          K::Error: From<S::Error>,
+{
+    Start(S, K),
+    Scheduled(Driver<Forwarder<S, K>>),
+    Done,
+}
+
+struct Forwarder<S, K>
+    where S: Stream,
+          K: Sink,
+{
+    stream: S,
+    // `None` while it's being called, or once we're done
+    sink: Option<K>,
+    // what to do with the sink next, if anything
+    todo: Option<Todo<S::Item>>,
+    // the send or flush currently running, kept here so dropping us cancels it
+    op: Option<Box<Future<Item=(), Error=K::Error>>>,
+    running: bool,
+    res: Option<PollResult<K, K::Error>>,
+}
+
+enum Todo<T> {
+    Send(T),
+    Flush,
+}
+
+enum Event<S: Stream, K: Sink> {
+    Stream(StreamResult<S::Item, S::Error>),
+    Sent(PollResult<(), K::Error>),
+    Flushed(PollResult<(), K::Error>),
+}
+
+pub fn new<S, K>(s: S, sink: K) -> Forward<S, K>
+    where S: Stream,
+          K: Sink<Item=S::Item>,
+          K::Error: From<S::Error>,
+{
+    Forward {
+        state: State::Start(s, sink),
+    }
+}
+
+impl<S, K> Future for Forward<S, K>
+    where S: Stream,
+          K: Sink<Item=S::Item>,
+          K::Error: From<S::Error>,
+{
+    type Item = K;
+    type Error = K::Error;
+
+    fn poll(&mut self) -> Option<PollResult<K, K::Error>> {
+        match self.state {
+            State::Start(..) => None,
+            State::Scheduled(..) | State::Done => Some(Err(util::reused())),
+        }
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<K, K::Error>) + Send + 'static
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<K, K::Error>>) {
+        let (stream, sink) = match mem::replace(&mut self.state, State::Done) {
+            State::Start(stream, sink) => (stream, sink),
+            State::Scheduled(s) => {
+                self.state = State::Scheduled(s);
+                return executor::current().execute(|| cb.call(Err(util::reused())))
+            }
+            State::Done => {
+                return executor::current().execute(|| cb.call(Err(util::reused())))
+            }
+        };
+        let driver = Driver::new(Forwarder {
+            stream: stream,
+            sink: Some(sink),
+            todo: None,
+            op: None,
+            running: false,
+            res: None,
+        });
+        driver.schedule(cb);
+        self.state = State::Scheduled(driver);
+    }
+}
+
+impl<S, K> Machine for Forwarder<S, K>
+    where S: Stream,
+          K: Sink<Item=S::Item>,
+          K::Error: From<S::Error>,
+{
+    type Item = K;
+    type Error = K::Error;
+    type Event = Event<S, K>;
+
+    fn start(&mut self, _wanted: bool, notify: &Notify<Self>) -> bool {
+        if self.running || self.res.is_some() {
+            return false
+        }
+        self.running = true;
+        let notify = notify.clone();
+        match self.todo.take() {
+            Some(Todo::Send(item)) => {
+                self.run(move |sink| sink.start_send(item), Event::Sent, notify)
+            }
+            Some(Todo::Flush) => {
+                self.run(|sink| sink.flush(), Event::Flushed, notify)
+            }
+            None => {
+                self.stream.schedule(move |r| notify.done(Event::Stream(r)))
+            }
+        }
+        true
+    }
+
+    fn event(&mut self, event: Event<S, K>) {
+        self.running = false;
+        match event {
+            Event::Stream(Ok(Some(item))) => self.todo = Some(Todo::Send(item)),
+            Event::Stream(Ok(None)) => self.todo = Some(Todo::Flush),
+            Event::Stream(Err(e)) => self.res = Some(Err(e.map(From::from))),
+            Event::Sent(Ok(())) => self.op = None,
+            Event::Flushed(Ok(())) => {
+                self.op = None;
+                self.res = self.sink.take().map(Ok);
+            }
+            Event::Sent(Err(e)) |
+            Event::Flushed(Err(e)) => self.res = Some(Err(e)),
+        }
+    }
+
+    fn next(&mut self) -> Option<Next<K, K::Error>> {
+        self.res.take().map(Next::Done)
+    }
+}
+
+impl<S, K> Forwarder<S, K>
+    where S: Stream,
+          K: Sink<Item=S::Item>,
+          K::Error: From<S::Error>,
+{
+    // Runs `f` against the sink and waits for the future it returns, with
+    // its result coming back as an event made by `event`. If `f` panics then
+    // the sink goes with it, and the panic is what we resolve to.
+    fn run<F>(&mut self,
+              f: F,
+              event: fn(PollResult<(), K::Error>) -> Event<S, K>,
+              notify: Notify<Self>)
+        where F: FnOnce(&mut K) -> Box<Future<Item=(), Error=K::Error>>
+                     + Send + 'static
+    {
+        let sink = self.sink.take().expect("[forward] sink not here");
+        let res = util::recover(move || {
+            let mut sink = sink;
+            (f(&mut sink), sink)
+        });
+        let (mut op, sink) = match res {
+            Ok(pair) => pair,
+            Err(e) => {
+                self.res = Some(Err(e));
+                return
+            }
+        };
+        self.sink = Some(sink);
+        op.schedule(move |r| notify.done(event(r)));
+        self.op = Some(op);
+    }
+}
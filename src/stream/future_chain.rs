@@ -0,0 +1,137 @@
+use {Future, Callback, PollResult};
+use stream::{Stream, StreamResult};
+use stream::driver::{self, Driver, Machine, Notify};
+use util;
+
+// Shared implementation of the stream combinators which run a future for
+// elements of a stream, like `and_then`, `or_else` and `flatten`.
+pub struct FutureChain<S, B, C>
+    where S: Stream,
+          B: Future,
+          C: Send + 'static,
+{
+    driver: Driver<Chain<S, B, C>>,
+}
+
+// What to do with a result from the stream: hand back a result right away or
+// run a future to get it.
+pub enum Next<B: Future> {
+    Done(StreamResult<B::Item, B::Error>),
+    Run(B),
+}
+
+// Decides what to do with each result of the stream, given the combinator's
+// closure.
+type Step<S, B, C> = fn(StreamResult<<S as Stream>::Item, <S as Stream>::Error>,
+                        &mut C) -> Next<B>;
+
+struct Chain<S, B, C>
+    where S: Stream,
+          B: Future,
+          C: Send + 'static,
+{
+    stream: S,
+    // the future for the last element, while it's running
+    future: Option<B>,
+    // `None` while `step` is running, or if it panicked
+    data: Option<C>,
+    step: Step<S, B, C>,
+    running: bool,
+    res: Option<StreamResult<B::Item, B::Error>>,
+}
+
+enum Event<S: Stream, B: Future> {
+    Stream(StreamResult<S::Item, S::Error>),
+    Future(PollResult<B::Item, B::Error>),
+}
+
+impl<S, B, C> FutureChain<S, B, C>
+    where S: Stream,
+          B: Future,
+          C: Send + 'static,
+{
+    pub fn new(stream: S,
+               data: C,
+               step: Step<S, B, C>)
+               -> FutureChain<S, B, C> {
+        FutureChain {
+            driver: Driver::new(Chain {
+                stream: stream,
+                future: None,
+                data: Some(data),
+                step: step,
+                running: false,
+                res: None,
+            }),
+        }
+    }
+
+    pub fn schedule(&mut self, g: Box<Callback<Option<B::Item>, B::Error>>) {
+        self.driver.schedule(g)
+    }
+}
+
+impl<S, B, C> Machine for Chain<S, B, C>
+    where S: Stream,
+          B: Future,
+          C: Send + 'static,
+{
+    type Item = Option<B::Item>;
+    type Error = B::Error;
+    type Event = Event<S, B>;
+
+    // Kicks off the future if we've got one, otherwise asks the stream for
+    // its next element.
+    fn start(&mut self, wanted: bool, notify: &Notify<Self>) -> bool {
+        if !wanted || self.running || self.res.is_some() {
+            return false
+        }
+        self.running = true;
+        let notify = notify.clone();
+        match self.future {
+            Some(ref mut f) => {
+                f.schedule(move |r| notify.done(Event::Future(r)))
+            }
+            None => {
+                self.stream.schedule(move |r| notify.done(Event::Stream(r)))
+            }
+        }
+        true
+    }
+
+    fn event(&mut self, event: Event<S, B>) {
+        self.running = false;
+        let res = match event {
+            Event::Future(res) => {
+                self.future = None;
+                res.map(Some)
+            }
+            Event::Stream(res) => {
+                let step = self.step;
+                let next = util::opt2poll(self.data.take()).and_then(|data| {
+                    util::recover(move || {
+                        let mut data = data;
+                        (step(res, &mut data), data)
+                    })
+                });
+                match next {
+                    Ok((Next::Run(f), data)) => {
+                        self.data = Some(data);
+                        self.future = Some(f);
+                        return
+                    }
+                    Ok((Next::Done(res), data)) => {
+                        self.data = Some(data);
+                        res
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+        };
+        self.res = Some(res);
+    }
+
+    fn next(&mut self) -> Option<driver::Next<Option<B::Item>, B::Error>> {
+        self.res.take().map(driver::Next::Yield)
+    }
+}
@@ -0,0 +1,44 @@
+use executor::{self, Executor};
+use {Callback, PollError};
+use stream::{Stream, StreamResult};
+
+pub struct Iter<I> {
+    iter: I,
+}
+
+// Creates a stream which yields each of the results in `i`, in order, and
+// then ends.
+pub fn iter<I, T, E>(i: I) -> Iter<I::IntoIter>
+    where I: IntoIterator<Item=Result<T, E>>,
+          I::IntoIter: Send + 'static,
+          T: Send + 'static,
+          E: Send + 'static,
+{
+    Iter {
+        iter: i.into_iter(),
+    }
+}
+
+impl<I, T, E> Stream for Iter<I>
+    where I: Iterator<Item=Result<T, E>> + Send + 'static,
+          T: Send + 'static,
+          E: Send + 'static,
+{
+    type Item = T;
+    type Error = E;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<T, E>) + Send + 'static,
+    {
+        let res = match self.iter.next() {
+            Some(Ok(t)) => Ok(Some(t)),
+            Some(Err(e)) => Err(PollError::Other(e)),
+            None => Ok(None),
+        };
+        executor::current().execute(|| g(res))
+    }
+
+    fn schedule_boxed(&mut self, g: Box<Callback<Option<T>, E>>) {
+        self.schedule(|r| g.call(r))
+    }
+}
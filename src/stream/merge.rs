@@ -0,0 +1,43 @@
+use std::marker;
+
+use Callback;
+use stream::{Stream, StreamResult, SelectAll};
+use stream::select_all;
+
+// Interleaves the elements of two streams, ending once both have ended.
+pub struct Merge<S1, S2>
+    where S1: Stream,
+{
+    inner: SelectAll<S1::Item, S1::Error>,
+    _marker: marker::PhantomData<(S1, S2)>,
+}
+
+pub fn new<S1, S2>(s1: S1, s2: S2) -> Merge<S1, S2>
+    where S1: Stream,
+          S2: Stream<Item=S1::Item, Error=S1::Error>,
+{
+    Merge {
+        inner: select_all::select_all(vec![Box::new(s1) as Box<_>,
+                                           Box::new(s2) as Box<_>]),
+        _marker: marker::PhantomData,
+    }
+}
+
+impl<S1, S2> Stream for Merge<S1, S2>
+    where S1: Stream,
+          S2: Stream<Item=S1::Item, Error=S1::Error>,
+{
+    type Item = S1::Item;
+    type Error = S1::Error;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<Self::Item, Self::Error>) + Send + 'static,
+    {
+        self.inner.schedule(g)
+    }
+
+    fn schedule_boxed(&mut self,
+                      g: Box<Callback<Option<Self::Item>, Self::Error>>) {
+        self.inner.schedule_boxed(g)
+    }
+}
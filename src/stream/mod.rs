@@ -1,14 +1,71 @@
-use {PollResult, Callback};
+use std::vec;
 
-// mod channel;
-// pub use self::channel::{channel, Sender, Receiver};
+use {PollResult, Callback, IntoFuture};
 
-mod map;
+mod channel;
+pub use self::channel::{channel, Sender, Receiver, FutureSender};
+pub use self::channel::{SendError, TrySendError};
+pub mod broadcast;
+pub mod watch;
+
+mod sink;
+pub use self::sink::Sink;
+
+mod empty;
+mod iter;
+mod once;
+mod repeat;
+mod select_all;
+pub use self::empty::{empty, Empty};
+pub use self::iter::{iter, Iter};
+pub use self::once::{once, Once};
+pub use self::repeat::{repeat, Repeat};
+pub use self::select_all::{select_all, SelectAll, BoxStream};
+
+mod and_then;
+mod buffer_unordered;
+mod buffered;
+mod chain;
+mod chunks;
+mod collect;
+mod driver;
 mod filter;
+mod flat_map;
+mod flatten;
+mod fold;
+mod forward;
+mod future_chain;
+mod map;
 mod map_err;
+mod merge;
+mod or_else;
+mod parked;
+mod skip;
+mod skip_while;
+mod subscribers;
+mod take;
+mod take_while;
+mod zip;
+pub use self::and_then::AndThen;
+pub use self::buffer_unordered::BufferUnordered;
+pub use self::buffered::Buffered;
+pub use self::chain::Chain;
+pub use self::chunks::Chunks;
+pub use self::collect::Collect;
+pub use self::filter::Filter;
+pub use self::flat_map::FlatMap;
+pub use self::flatten::Flatten;
+pub use self::fold::Fold;
+pub use self::forward::Forward;
 pub use self::map::Map;
 pub use self::map_err::MapErr;
-pub use self::filter::Filter;
+pub use self::merge::Merge;
+pub use self::or_else::OrElse;
+pub use self::skip::Skip;
+pub use self::skip_while::SkipWhile;
+pub use self::take::Take;
+pub use self::take_while::TakeWhile;
+pub use self::zip::Zip;
 
 pub type StreamResult<T, E> = PollResult<Option<T>, E>;
 
@@ -25,6 +82,10 @@ pub trait Stream: Send + 'static {
     fn schedule_boxed(&mut self,
                       g: Box<Callback<Option<Self::Item>, Self::Error>>);
 
+    fn boxed(self) -> BoxStream<Self::Item, Self::Error> where Self: Sized {
+        Box::new(self)
+    }
+
     fn map<U, F>(self, f: F) -> Map<Self, F>
         where F: FnMut(Self::Item) -> U + Send + 'static,
               U: Send + 'static,
@@ -48,75 +109,149 @@ pub trait Stream: Send + 'static {
         filter::new(self, f)
     }
 
-    // // TODO: is this the same as map + flatten?
-    // fn and_then<F, U>(self, f: F) -> AndThen<Self, F, U>
-    //     where F: FnMut(Self::Item) -> U + Send + 'static,
-    //           U: IntoFuture<Error=Self::Error>,
-    //           Self: Sized
-    // {
-    //     AndThen {
-    //         stream: self,
-    //         f: f,
-    //         future: None,
-    //     }
-    // }
-    //
-    // // TODO: is this the same as map_err + flatten?
-    // fn or_else<F, U>(self, f: F) -> OrElse<Self, F, U>
-    //     where F: FnMut(Self::Error) -> U + Send + 'static,
-    //           U: IntoFuture<Item=Self::Item>,
-    //           Self: Sized
-    // {
-    //     OrElse {
-    //         stream: self,
-    //         f: f,
-    //         future: None,
-    //     }
-    // }
-    //
-    // // TODO: compare with fold + push
-    // fn collect(self) -> Collect<Self> where Self: Sized {
-    //     Collect {
-    //         stream: self,
-    //         elems: Vec::new(),
-    //     }
-    // }
+    // Yields at most the first `amt` elements of this stream.
+    fn take(self, amt: u64) -> Take<Self> where Self: Sized {
+        take::new(self, amt)
+    }
+
+    // Drops the first `amt` elements of this stream, yielding the rest.
+    fn skip(self, amt: u64) -> Skip<Self> where Self: Sized {
+        skip::new(self, amt)
+    }
+
+    // Yields elements until `f` first returns `false`, at which point the
+    // stream ends.
+    fn take_while<F>(self, f: F) -> TakeWhile<Self, F>
+        where F: FnMut(&Self::Item) -> bool + Send + 'static,
+              Self: Sized
+    {
+        take_while::new(self, f)
+    }
+
+    // Drops elements until `f` first returns `false`, yielding everything
+    // from then on.
+    fn skip_while<F>(self, f: F) -> SkipWhile<Self, F>
+        where F: FnMut(&Self::Item) -> bool + Send + 'static,
+              Self: Sized
+    {
+        skip_while::new(self, f)
+    }
+
+    // Batches up elements into vectors of `capacity` elements, with whatever
+    // is left over yielded before the stream ends.
     //
-    // fn fold<F, T>(self, init: T, f: F) -> Fold<Self, F, T>
-    //     where F: FnMut(T, Self::Item) -> T + Send + 'static,
-    //           T: Send + 'static,
-    //           Self: Sized
-    // {
-    //     Fold {
-    //         stream: self,
-    //         f: f,
-    //         val: Some(init),
-    //     }
-    // }
+    // Panics if `capacity` is zero.
+    fn chunks(self, capacity: usize) -> Chunks<Self> where Self: Sized {
+        chunks::new(self, capacity)
+    }
+
+    // Interleaves the elements of this stream and `other` as they arrive,
+    // ending once both have ended.
+    fn merge<S>(self, other: S) -> Merge<Self, S>
+        where S: Stream<Item=Self::Item, Error=Self::Error>,
+              Self: Sized
+    {
+        merge::new(self, other)
+    }
+
+    // Pairs up elements of this stream with those of `other`, ending as soon
+    // as either one ends.
+    fn zip<S>(self, other: S) -> Zip<Self, S>
+        where S: Stream<Error=Self::Error>,
+              Self: Sized
+    {
+        zip::new(self, other)
+    }
+
+    // Yields all of the elements of this stream followed by all of those of
+    // `other`.
+    fn chain<S>(self, other: S) -> Chain<Self, S>
+        where S: Stream<Item=Self::Item, Error=Self::Error>,
+              Self: Sized
+    {
+        chain::new(self, other)
+    }
+
+    fn and_then<F, U>(self, f: F) -> AndThen<Self, F, U>
+        where F: FnMut(Self::Item) -> U + Send + 'static,
+              U: IntoFuture<Error=Self::Error>,
+              Self: Sized
+    {
+        and_then::new(self, f)
+    }
+
+    fn or_else<F, U>(self, f: F) -> OrElse<Self, F, U>
+        where F: FnMut(Self::Error) -> U + Send + 'static,
+              U: IntoFuture<Item=Self::Item>,
+              Self: Sized
+    {
+        or_else::new(self, f)
+    }
+
+    // Runs up to `amt` of the futures yielded by this stream at once,
+    // yielding their results in the order the futures were yielded.
     //
-    // fn flatten(self) -> Flatten<Self>
-    //     where Self::Item: IntoFuture,
-    //           <<Self as Stream>::Item as IntoFuture>::Error:
-    //                 From<<Self as Stream>::Error>,
-    //           Self: Sized
-    // {
-    //     Flatten {
-    //         stream: self,
-    //         future: None,
-    //     }
-    // }
+    // Panics if `amt` is zero.
+    fn buffered(self, amt: usize) -> Buffered<Self>
+        where Self::Item: IntoFuture,
+              <<Self as Stream>::Item as IntoFuture>::Error:
+                    From<<Self as Stream>::Error>,
+              Self: Sized
+    {
+        buffered::new(self, amt)
+    }
+
+    // Like `buffered`, except results are yielded as soon as they're ready.
     //
-    // fn flat_map(self) -> FlatMap<Self>
-    //     where Self::Item: Stream,
-    //           <<Self as Stream>::Item as Stream>::Error:
-    //                 From<<Self as Stream>::Error>,
-    //           Self: Sized
-    // {
-    //     FlatMap {
-    //         outer: self,
-    //         inner: None,
-    //     }
-    // }
+    // Panics if `amt` is zero.
+    fn buffer_unordered(self, amt: usize) -> BufferUnordered<Self>
+        where Self::Item: IntoFuture,
+              <<Self as Stream>::Item as IntoFuture>::Error:
+                    From<<Self as Stream>::Error>,
+              Self: Sized
+    {
+        buffer_unordered::new(self, amt)
+    }
+
+    fn collect(self) -> Collect<Self> where Self: Sized {
+        collect::new(self)
+    }
+
+    fn fold<F, T>(self, init: T, f: F) -> Fold<Self, F, T>
+        where F: FnMut(T, Self::Item) -> T + Send + 'static,
+              T: Send + 'static,
+              Self: Sized
+    {
+        fold::new(self, f, init)
+    }
+
+    // Sends every element of this stream into `sink`, resolving back to the
+    // sink once the stream has ended and the sink has been flushed.
+    fn forward<K>(self, sink: K) -> Forward<Self, K>
+        where K: Sink<Item=Self::Item>,
+              K::Error: From<Self::Error>,
+              Self: Sized
+    {
+        forward::new(self, sink)
+    }
+
+    fn flatten(self) -> Flatten<Self>
+        where Self::Item: IntoFuture,
+              <<Self as Stream>::Item as IntoFuture>::Error:
+                    From<<Self as Stream>::Error>,
+              Self: Sized
+    {
+        flatten::new(self)
+    }
+
+    fn flat_map(self) -> FlatMap<Self>
+        where Self::Item: Stream,
+              <<Self as Stream>::Item as Stream>::Error:
+                    From<<Self as Stream>::Error>,
+              Self: Sized
+    {
+        flat_map::new(self)
+    }
 }
 
 impl<S: ?Sized + Stream> Stream for Box<S> {
@@ -204,349 +339,34 @@ impl<S: ?Sized + Stream> Stream for Box<S> {
 //         }
 //     }
 // }
-//
-// pub struct AndThen<S, F, U> where U: IntoFuture {
-//     stream: S,
-//     f: F,
-//     future: Option<U::Future>,
-// }
-//
-// impl<S, F, U> Stream for AndThen<S, F, U>
-//     where S: Stream,
-//           F: FnMut(S::Item) -> U + Send + 'static,
-//           U: IntoFuture<Error=S::Error>,
-// {
-//     type Item = U::Item;
-//     type Error = S::Error;
-//
-//     fn poll(&mut self) -> Result<Self::Item, PollError<Self::Error>> {
-//         let future = match self.future.take() {
-//             Some(f) => f,
-//             None => (self.f)(try!(self.stream.poll())).into_future(),
-//         };
-//
-//         match future.poll() {
-//             Ok(Ok(item)) => Ok(item),
-//             Ok(Err(e)) => Err(PollError::Other(e)),
-//             Err(f) => {
-//                 self.future = Some(f);
-//                 Err(PollError::NotReady)
-//             }
-//         }
-//     }
-//
-//     fn schedule<G>(mut self, g: G)
-//         where G: FnOnce(Option<Result<Self::Item, Self::Error>>, Self) +
-//                     Send + 'static,
-//     {
-//         match self.future.take() {
-//             Some(future) => future.schedule(|result| g(Some(result), self)),
-//             None => {
-//                 let AndThen { stream, f, future: _ } = self;
-//                 stream.schedule(move |result, s| {
-//                     let mut me = AndThen { stream: s, f: f, future: None };
-//                     match result {
-//                         Some(Ok(item)) => {
-//                             me.future = Some((me.f)(item).into_future());
-//                             me.schedule(g)
-//                         }
-//                         Some(Err(e)) => g(Some(Err(e)), me),
-//                         None => g(None, me),
-//                     }
-//                 })
-//             }
-//         }
-//     }
-// }
-//
-// pub struct OrElse<S, F, U> where U: IntoFuture {
-//     stream: S,
-//     f: F,
-//     future: Option<U::Future>,
-// }
-//
-// impl<S, F, U> Stream for OrElse<S, F, U>
-//     where S: Stream,
-//           F: FnMut(S::Error) -> U + Send + 'static,
-//           U: IntoFuture<Item=S::Item>,
-// {
-//     type Item = S::Item;
-//     type Error = U::Error;
-//
-//     fn poll(&mut self) -> Result<Self::Item, PollError<Self::Error>> {
-//         let future = match self.future.take() {
-//             Some(f) => f,
-//             None => {
-//                 match self.stream.poll() {
-//                     Ok(item) => return Ok(item),
-//                     Err(PollError::Empty) => return Err(PollError::Empty),
-//                     Err(PollError::NotReady) => return Err(PollError::NotReady),
-//                     Err(PollError::Other(e)) => (self.f)(e).into_future(),
-//                 }
-//             }
-//         };
-//
-//         match future.poll() {
-//             Ok(Ok(item)) => Ok(item),
-//             Ok(Err(e)) => Err(PollError::Other(e)),
-//             Err(f) => {
-//                 self.future = Some(f);
-//                 Err(PollError::NotReady)
-//             }
-//         }
-//     }
-//
-//     fn schedule<G>(mut self, g: G)
-//         where G: FnOnce(Option<Result<Self::Item, Self::Error>>, Self) +
-//                     Send + 'static,
-//     {
-//         match self.future.take() {
-//             Some(future) => future.schedule(|result| g(Some(result), self)),
-//             None => {
-//                 let OrElse { stream, f, future: _ } = self;
-//                 stream.schedule(move |result, s| {
-//                     let mut me = OrElse { stream: s, f: f, future: None };
-//                     match result {
-//                         Some(Ok(item)) => g(Some(Ok(item)), me),
-//                         Some(Err(e)) => {
-//                             me.future = Some((me.f)(e).into_future());
-//                             me.schedule(g)
-//                         }
-//                         None => g(None, me),
-//                     }
-//                 })
-//             }
-//         }
-//     }
-// }
-//
-// pub struct Collect<S> where S: Stream {
-//     stream: S,
-//     elems: Vec<S::Item>,
-// }
-//
-// impl<S: Stream> Future for Collect<S> {
-//     type Item = Vec<S::Item>;
-//     type Error = S::Error;
-//
-//     fn poll(mut self) -> Result<Result<Self::Item, Self::Error>, Self> {
-//         loop {
-//             match self.stream.poll() {
-//                 Ok(t) => self.elems.push(t),
-//                 Err(PollError::Empty) => return Ok(Ok(self.elems)),
-//                 Err(PollError::NotReady) => return Err(self),
-//                 Err(PollError::Other(e)) => return Ok(Err(e)),
-//             }
-//         }
-//     }
-//
-//     fn schedule<F>(self, f: F)
-//         where F: FnOnce(Result<Self::Item, Self::Error>) + Send + 'static
-//     {
-//         let Collect { stream, mut elems } = self;
-//         stream.schedule(move |result, stream| {
-//             match result {
-//                 Some(Ok(item)) => {
-//                     elems.push(item);
-//                     Collect { stream: stream, elems: elems }.schedule(f)
-//                 }
-//                 Some(Err(e)) => f(Err(e)),
-//                 None => f(Ok(elems)),
-//             }
-//         })
-//     }
-// }
-//
-// pub struct Fold<S, F, T> {
-//     stream: S,
-//     f: F,
-//     val: Option<T>,
-// }
-//
-// impl<S, F, T> Fold<S, F, T>
-//     where S: Stream,
-//           F: FnMut(T, S::Item) -> T + Send + 'static,
-//           T: Send + 'static,
-// {
-//     fn process(&mut self, item: S::Item) {
-//         self.val = Some((self.f)(self.val.take().unwrap(), item));
-//     }
-// }
-//
-// impl<S, F, T> Future for Fold<S, F, T>
-//     where S: Stream,
-//           F: FnMut(T, S::Item) -> T + Send + 'static,
-//           T: Send + 'static,
-// {
-//     type Item = T;
-//     type Error = S::Error;
-//
-//     fn poll(mut self) -> Result<Result<Self::Item, Self::Error>, Self> {
-//         loop {
-//             match self.stream.poll() {
-//                 Ok(item) => self.process(item),
-//                 Err(PollError::Empty) => return Ok(Ok(self.val.take().unwrap())),
-//                 Err(PollError::NotReady) => return Err(self),
-//                 Err(PollError::Other(e)) => return Ok(Err(e)),
-//             }
-//         }
-//     }
-//
-//     fn schedule<G>(self, g: G)
-//         where G: FnOnce(Result<Self::Item, Self::Error>) + Send + 'static
-//     {
-//         let Fold { stream, val, f } = self;
-//         stream.schedule(|result, stream| {
-//             let mut me = Fold { stream: stream, val: val, f: f };
-//             match result {
-//                 Some(Ok(item)) => {
-//                     me.process(item);
-//                     me.schedule(g);
-//                 }
-//                 Some(Err(e)) => g(Err(e)),
-//                 None => g(Ok(me.val.take().unwrap())),
-//             }
-//         })
-//     }
-// }
-//
-// pub struct Flatten<S> where S: Stream, S::Item: IntoFuture {
-//     stream: S,
-//     future: Option<<S::Item as IntoFuture>::Future>,
-// }
-//
-// impl<S> Stream for Flatten<S>
-//     where S: Stream,
-//           S::Item: IntoFuture,
-//           <<S as Stream>::Item as IntoFuture>::Error: From<<S as Stream>::Error>
-// {
-//     type Item = <S::Item as IntoFuture>::Item;
-//     type Error = <S::Item as IntoFuture>::Error;
-//
-//     fn poll(&mut self) -> Result<Self::Item, PollError<Self::Error>> {
-//         let future = match self.future.take() {
-//             Some(f) => f,
-//             None => {
-//                 try!(self.stream.poll().map_err(|e| {
-//                     e.map(From::from)
-//                 })).into_future()
-//             }
-//         };
-//         match future.poll() {
-//             Ok(Ok(item)) => Ok(item),
-//             Ok(Err(e)) => Err(PollError::Other(e)),
-//             Err(f) => {
-//                 self.future = Some(f);
-//                 Err(PollError::NotReady)
-//             }
-//         }
-//     }
-//
-//     fn schedule<G>(mut self, g: G)
-//         where G: FnOnce(Option<Result<Self::Item, Self::Error>>, Self) +
-//                     Send + 'static,
-//     {
-//         match self.future.take() {
-//             Some(f) => f.schedule(|result| g(Some(result), self)),
-//             None => {
-//                 self.stream.schedule(|result, stream| {
-//                     let mut me = Flatten { stream: stream, future: None };
-//                     match result {
-//                         Some(Ok(f)) => {
-//                             me.future = Some(f.into_future());
-//                             me.schedule(g)
-//                         }
-//                         Some(Err(e)) => g(Some(Err(From::from(e))), me),
-//                         None => g(None, me),
-//                     }
-//                 })
-//             }
-//         }
-//     }
-// }
-//
-// pub struct FlatMap<S> where S: Stream, S::Item: Stream {
-//     outer: S,
-//     inner: Option<S::Item>,
-// }
-//
-// impl<S> Stream for FlatMap<S>
-//     where S: Stream,
-//           S::Item: Stream,
-//           <<S as Stream>::Item as Stream>::Error: From<<S as Stream>::Error>
-// {
-//     type Item = <S::Item as Stream>::Item;
-//     type Error = <S::Item as Stream>::Error;
-//
-//     fn poll(&mut self) -> Result<Self::Item, PollError<Self::Error>> {
-//         loop {
-//             match self.inner.as_mut().map(|s| s.poll()) {
-//                 Some(Err(PollError::Empty)) => self.inner = None,
-//                 Some(other) => return other,
-//                 None => {}
-//             }
-//
-//             match self.outer.poll() {
-//                 Ok(inner) => self.inner = Some(inner),
-//                 Err(e) => return Err(e.map(From::from)),
-//             }
-//         }
-//     }
-//
-//     fn schedule<G>(mut self, g: G)
-//         where G: FnOnce(Option<Result<Self::Item, Self::Error>>, Self) +
-//                     Send + 'static,
-//     {
-//         match self.inner.take() {
-//             Some(inner) => {
-//                 inner.schedule(|result, inner| {
-//                     match result {
-//                         Some(item) => {
-//                             self.inner = Some(inner);
-//                             g(Some(item), self);
-//                         }
-//                         None => self.schedule(g),
-//                     }
-//                 })
-//             }
-//             None => {
-//                 self.outer.schedule(|result, outer| {
-//                     let mut me = FlatMap { inner: None, outer: outer };
-//                     match result {
-//                         Some(Ok(inner)) => {
-//                             me.inner = Some(inner);
-//                             me.schedule(g);
-//                         }
-//                         Some(Err(e)) => g(Some(Err(From::from(e))), me),
-//                         None => g(None, me),
-//                     }
-//                 })
-//             }
-//         }
-//     }
-// }
 
-// TODO: IntoStream?
-//
-// impl<I> Stream for I
-//     where I: Iterator,
-//           I: Send + 'static,
-//           I::Item: Send + 'static,
-// {
-//     type Item = I::Item;
-//     type Error = ();
-//
-//     fn poll(&mut self) -> Result<Self::Item, PollError<Self::Error>> {
-//         match self.next() {
-//             Some(item) => Ok(item),
-//             None => Err(PollError::Empty),
-//         }
-//     }
-//
-//     fn schedule<G>(mut self, g: G)
-//         where G: FnOnce(Option<Result<Self::Item, Self::Error>>, Self) +
-//                     Send + 'static,
-//     {
-//         g(self.next().map(Ok), self)
-//     }
-// }
+pub trait IntoStream: Send + 'static {
+    type Stream: Stream<Item=Self::Item, Error=Self::Error>;
+    type Item: Send + 'static;
+    type Error: Send + 'static;
+
+    fn into_stream(self) -> Self::Stream;
+}
+
+impl<S: Stream> IntoStream for S {
+    type Stream = S;
+    type Item = S::Item;
+    type Error = S::Error;
+
+    fn into_stream(self) -> S {
+        self
+    }
+}
+
+impl<T, E> IntoStream for Vec<Result<T, E>>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    type Stream = Iter<vec::IntoIter<Result<T, E>>>;
+    type Item = T;
+    type Error = E;
+
+    fn into_stream(self) -> Self::Stream {
+        iter(self)
+    }
+}
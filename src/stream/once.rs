@@ -0,0 +1,63 @@
+use std::mem;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use executor::{self, Executor};
+use util;
+use {Future, IntoFuture, Callback};
+use stream::{Stream, StreamResult};
+
+pub struct Once<F> {
+    state: State<F>,
+}
+
+enum State<F> {
+    Start(F),
+    // The future along with whether it's finished yet
+    Scheduled(F, Arc<AtomicBool>),
+    Done,
+}
+
+// Creates a stream which yields the result of `f` and then ends.
+pub fn once<F: IntoFuture>(f: F) -> Once<F::Future> {
+    Once {
+        state: State::Start(f.into_future()),
+    }
+}
+
+impl<F: Future> Stream for Once<F> {
+    type Item = F::Item;
+    type Error = F::Error;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<F::Item, F::Error>) + Send + 'static,
+    {
+        match mem::replace(&mut self.state, State::Done) {
+            State::Start(mut f) => {
+                let done = Arc::new(AtomicBool::new(false));
+                let done2 = done.clone();
+                f.schedule(move |r| {
+                    done2.store(true, Ordering::SeqCst);
+                    g(r.map(Some))
+                });
+                self.state = State::Scheduled(f, done);
+            }
+            State::Scheduled(f, done) => {
+                // Hang on to the future until it's finished so it can still
+                // be canceled.
+                let res = if done.load(Ordering::SeqCst) {
+                    Ok(None)
+                } else {
+                    self.state = State::Scheduled(f, done);
+                    Err(util::reused())
+                };
+                executor::current().execute(|| g(res))
+            }
+            State::Done => executor::current().execute(|| g(Ok(None))),
+        }
+    }
+
+    fn schedule_boxed(&mut self, g: Box<Callback<Option<F::Item>, F::Error>>) {
+        self.schedule(|r| g.call(r))
+    }
+}
@@ -0,0 +1,55 @@
+use {Callback, IntoFuture, PollError};
+use stream::{Stream, StreamResult};
+use stream::future_chain::{FutureChain, Next};
+
+pub struct OrElse<S, F, U>
+    where S: Stream,
+          F: Send + 'static,
+          U: IntoFuture,
+{
+    chain: FutureChain<S, U::Future, F>,
+}
+
+pub fn new<S, F, U>(s: S, f: F) -> OrElse<S, F, U>
+    where S: Stream,
+          F: FnMut(S::Error) -> U + Send + 'static,
+          U: IntoFuture<Item=S::Item>,
+{
+    OrElse {
+        chain: FutureChain::new(s, f, or_else::<S, F, U>),
+    }
+}
+
+impl<S, F, U> Stream for OrElse<S, F, U>
+    where S: Stream,
+          F: FnMut(S::Error) -> U + Send + 'static,
+          U: IntoFuture<Item=S::Item>,
+{
+    type Item = S::Item;
+    type Error = U::Error;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<Self::Item, Self::Error>) + Send + 'static,
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self,
+                      g: Box<Callback<Option<Self::Item>, Self::Error>>) {
+        self.chain.schedule(g)
+    }
+}
+
+fn or_else<S, F, U>(res: StreamResult<S::Item, S::Error>, f: &mut F)
+                    -> Next<U::Future>
+    where S: Stream,
+          F: FnMut(S::Error) -> U + Send + 'static,
+          U: IntoFuture<Item=S::Item>,
+{
+    match res {
+        Ok(e) => Next::Done(Ok(e)),
+        Err(PollError::Other(e)) => Next::Run(f(e).into_future()),
+        Err(PollError::Panicked(p)) => Next::Done(Err(PollError::Panicked(p))),
+        Err(PollError::Canceled) => Next::Done(Err(PollError::Canceled)),
+    }
+}
@@ -0,0 +1,92 @@
+use std::sync::{Arc, Mutex};
+
+use {Callback, PollError};
+use stream::Stream;
+use util;
+
+// A stream which can be scheduled from any of the callbacks of the combinator
+// owning it.
+//
+// If a callback runs before `schedule` has returned, then scheduling the
+// stream again is deferred until it's back in place rather than racing with
+// the `schedule` call still in progress.
+pub struct Parked<S: Stream> {
+    inner: Arc<Mutex<Inner<S>>>,
+}
+
+struct Inner<S: Stream> {
+    // `None` while being scheduled, or once canceled
+    stream: Option<S>,
+    out: bool,
+    queued: Option<Box<Callback<Option<S::Item>, S::Error>>>,
+    canceled: bool,
+}
+
+impl<S: Stream> Parked<S> {
+    pub fn new(s: S) -> Parked<S> {
+        Parked {
+            inner: Arc::new(Mutex::new(Inner {
+                stream: Some(s),
+                out: false,
+                queued: None,
+                canceled: false,
+            })),
+        }
+    }
+
+    pub fn schedule(&self, g: Box<Callback<Option<S::Item>, S::Error>>) {
+        let mut g = g;
+        loop {
+            let mut s = {
+                let mut inner = self.inner.lock().unwrap();
+                if inner.canceled {
+                    drop(inner);
+                    return g.call(Err(PollError::Canceled))
+                }
+                if inner.out {
+                    if inner.queued.is_some() {
+                        drop(inner);
+                        return g.call(Err(util::reused()))
+                    }
+                    inner.queued = Some(g);
+                    return
+                }
+                inner.out = true;
+                inner.stream.take().expect("[parked] stream not here")
+            };
+            s.schedule_boxed(g);
+
+            let mut inner = self.inner.lock().unwrap();
+            inner.out = false;
+            if inner.canceled {
+                drop(inner);
+                return drop(s)
+            }
+            inner.stream = Some(s);
+            match inner.queued.take() {
+                Some(next) => g = next,
+                None => return,
+            }
+        }
+    }
+
+    // Drops the stream (now or once it's done being scheduled), canceling
+    // anything it was scheduled with.
+    pub fn cancel(&self) {
+        let (stream, queued) = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.canceled = true;
+            (inner.stream.take(), inner.queued.take())
+        };
+        drop(stream);
+        if let Some(g) = queued {
+            g.call(Err(PollError::Canceled))
+        }
+    }
+}
+
+impl<S: Stream> Clone for Parked<S> {
+    fn clone(&self) -> Parked<S> {
+        Parked { inner: self.inner.clone() }
+    }
+}
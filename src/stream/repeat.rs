@@ -0,0 +1,40 @@
+use std::marker;
+
+use executor::{self, Executor};
+use Callback;
+use stream::{Stream, StreamResult};
+
+pub struct Repeat<T, E> {
+    item: T,
+    _marker: marker::PhantomData<E>,
+}
+
+// Creates a stream which yields clones of `item` forever.
+pub fn repeat<T, E>(item: T) -> Repeat<T, E>
+    where T: Clone + Send + 'static,
+          E: Send + 'static,
+{
+    Repeat {
+        item: item,
+        _marker: marker::PhantomData,
+    }
+}
+
+impl<T, E> Stream for Repeat<T, E>
+    where T: Clone + Send + 'static,
+          E: Send + 'static,
+{
+    type Item = T;
+    type Error = E;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<T, E>) + Send + 'static,
+    {
+        let item = self.item.clone();
+        executor::current().execute(|| g(Ok(Some(item))))
+    }
+
+    fn schedule_boxed(&mut self, g: Box<Callback<Option<T>, E>>) {
+        self.schedule(|r| g.call(r))
+    }
+}
@@ -0,0 +1,174 @@
+use Callback;
+use stream::{Stream, StreamResult};
+use stream::driver::{Driver, Machine, Next, Notify};
+
+pub type BoxStream<T, E> = Box<Stream<Item=T, Error=E>>;
+
+// A stream which yields elements from a set of streams as they become
+// available. Streams are dropped from the set as they end, and the set as a
+// whole ends once it's empty.
+pub struct SelectAll<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    driver: Driver<Set<T, E>>,
+}
+
+struct Set<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    sides: Vec<Side<T, E>>,
+    // where to start looking for an element, so no stream can starve the
+    // others
+    next: usize,
+    next_id: usize,
+}
+
+struct Side<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    id: usize,
+    stream: BoxStream<T, E>,
+    pending: bool,
+    ready: Option<StreamResult<T, E>>,
+}
+
+enum Event<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    Push(BoxStream<T, E>),
+    Ready(usize, StreamResult<T, E>),
+}
+
+// Creates a stream which merges all of the streams in `i`, yielding their
+// elements in whatever order they arrive.
+pub fn select_all<I, T, E>(i: I) -> SelectAll<T, E>
+    where I: IntoIterator<Item=BoxStream<T, E>>,
+          T: Send + 'static,
+          E: Send + 'static,
+{
+    let mut set = Set {
+        sides: Vec::new(),
+        next: 0,
+        next_id: 0,
+    };
+    for s in i {
+        set.push(s);
+    }
+    SelectAll { driver: Driver::new(set) }
+}
+
+impl<T, E> SelectAll<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    // Adds another stream to the set, scheduling it right away if there's
+    // someone waiting for an element.
+    pub fn push(&mut self, s: BoxStream<T, E>) {
+        self.driver.event(Event::Push(s))
+    }
+}
+
+impl<T, E> Set<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    fn push(&mut self, s: BoxStream<T, E>) {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.sides.push(Side {
+            id: id,
+            stream: s,
+            pending: false,
+            ready: None,
+        });
+    }
+}
+
+impl<T, E> Machine for Set<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    type Item = Option<T>;
+    type Error = E;
+    type Event = Event<T, E>;
+
+    fn start(&mut self, wanted: bool, notify: &Notify<Self>) -> bool {
+        if !wanted {
+            return false
+        }
+        let side = self.sides.iter_mut().find(|s| {
+            !s.pending && s.ready.is_none()
+        });
+        match side {
+            Some(side) => {
+                side.pending = true;
+                let id = side.id;
+                let notify = notify.clone();
+                side.stream.schedule(move |r| notify.done(Event::Ready(id, r)));
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn event(&mut self, event: Event<T, E>) {
+        match event {
+            Event::Push(s) => self.push(s),
+            Event::Ready(id, res) => {
+                if let Some(side) = self.sides.iter_mut().find(|s| s.id == id) {
+                    side.pending = false;
+                    side.ready = Some(res);
+                }
+            }
+        }
+    }
+
+    // Picks out the next result to hand out, if any, dropping the streams
+    // which have ended along the way.
+    fn next(&mut self) -> Option<Next<Option<T>, E>> {
+        let mut i = 0;
+        while i < self.sides.len() {
+            let idx = (self.next + i) % self.sides.len();
+            match self.sides[idx].ready.take() {
+                Some(Ok(None)) => {
+                    self.sides.remove(idx);
+                    if idx < self.next {
+                        self.next -= 1;
+                    }
+                }
+                Some(res) => {
+                    self.next = idx + 1;
+                    return Some(Next::Yield(res))
+                }
+                None => i += 1,
+            }
+        }
+        if self.sides.is_empty() {
+            Some(Next::Yield(Ok(None)))
+        } else {
+            None
+        }
+    }
+}
+
+impl<T, E> Stream for SelectAll<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    type Item = T;
+    type Error = E;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<T, E>) + Send + 'static,
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self, g: Box<Callback<Option<T>, E>>) {
+        self.driver.schedule(g)
+    }
+}
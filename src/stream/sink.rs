@@ -0,0 +1,24 @@
+use Future;
+
+// The producing counterpart to `Stream`: something which values can be sent
+// into, one at a time.
+//
+// Each operation returns a future which resolves once it's done, and a sink
+// only expects one of those to be running at a time.
+pub trait Sink: Send + 'static {
+    type Item: Send + 'static;
+    type Error: Send + 'static;
+
+    // Begins sending `item`, resolving once the sink has accepted it. The item
+    // may still be buffered up inside the sink at that point.
+    fn start_send(&mut self, item: Self::Item)
+                  -> Box<Future<Item=(), Error=Self::Error>>;
+
+    // Resolves once everything sent so far has made it all the way through
+    // the sink.
+    fn flush(&mut self) -> Box<Future<Item=(), Error=Self::Error>>;
+
+    // Flushes the sink and then shuts it down, after which nothing more
+    // should be sent.
+    fn close(&mut self) -> Box<Future<Item=(), Error=Self::Error>>;
+}
@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use Callback;
+use slot::Slot;
+use stream::{Stream, StreamResult};
+use util;
+
+pub struct Skip<S> {
+    inner: Arc<_Skip<S>>,
+}
+
+struct _Skip<S> {
+    stream: Slot<S>,
+    remaining: Slot<u64>,
+}
+
+pub fn new<S>(s: S, amt: u64) -> Skip<S> where S: Stream {
+    Skip {
+        inner: Arc::new(_Skip {
+            stream: Slot::new(Some(s)),
+            remaining: Slot::new(Some(amt)),
+        }),
+    }
+}
+
+impl<S> Skip<S>
+    where S: Stream,
+{
+    fn doit<G>(&self,
+               mut stream: S,
+               remaining: u64,
+               g: G)
+        where G: FnOnce(StreamResult<S::Item, S::Error>) + Send + 'static,
+    {
+        let slot = self.inner.clone();
+        stream.schedule(move |res| {
+            if remaining > 0 {
+                if let Ok(Some(_)) = res {
+                    let slot2 = slot.clone();
+                    slot.stream.on_full(move |slot| {
+                        let stream = slot.try_consume().ok().unwrap();
+                        Skip {
+                            inner: slot2,
+                        }.doit(stream, remaining - 1, g)
+                    }).unwrap();
+                    return
+                }
+            }
+            slot.remaining.try_produce(remaining).ok()
+                .expect("skip stream failed to produce");
+            g(res)
+        });
+        self.inner.stream.try_produce(stream).ok()
+            .expect("skip failed to produce stream");
+    }
+}
+
+impl<S> Stream for Skip<S>
+    where S: Stream,
+{
+    type Item = S::Item;
+    type Error = S::Error;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<Self::Item, Self::Error>) + Send + 'static,
+    {
+        let remaining = match util::opt2poll(self.inner.remaining.try_consume().ok()) {
+            Ok(n) => n,
+            Err(e) => return g(Err(e)),
+        };
+        let stream = self.inner.stream.try_consume().ok()
+                             .expect("skip got count, failed to get stream");
+        self.doit(stream, remaining, g)
+    }
+
+    fn schedule_boxed(&mut self,
+                      g: Box<Callback<Option<Self::Item>, Self::Error>>) {
+        self.schedule(|r| g.call(r))
+    }
+}
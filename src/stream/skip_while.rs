@@ -0,0 +1,95 @@
+use std::sync::Arc;
+
+use Callback;
+use slot::Slot;
+use stream::{Stream, StreamResult};
+use util;
+
+pub struct SkipWhile<S, F> {
+    inner: Arc<_SkipWhile<S, F>>,
+}
+
+struct _SkipWhile<S, F> {
+    stream: Slot<S>,
+    // `None` once the predicate has returned `false`
+    f: Slot<Option<F>>,
+}
+
+pub fn new<S, F>(s: S, f: F) -> SkipWhile<S, F>
+    where F: Send + 'static,
+          S: Stream,
+{
+    SkipWhile {
+        inner: Arc::new(_SkipWhile {
+            stream: Slot::new(Some(s)),
+            f: Slot::new(Some(Some(f))),
+        }),
+    }
+}
+
+impl<S, F> SkipWhile<S, F>
+    where S: Stream,
+          F: FnMut(&S::Item) -> bool + Send + 'static,
+{
+    fn doit<G>(&self,
+               mut stream: S,
+               skip: Option<F>,
+               g: G)
+        where G: FnOnce(StreamResult<S::Item, S::Error>) + Send + 'static,
+    {
+        let slot = self.inner.clone();
+        stream.schedule(move |res| {
+            let (f, res) = match (res, skip) {
+                (Ok(Some(e)), Some(mut f)) => {
+                    match util::recover(|| (f(&e), e, f)) {
+                        Ok((true, _, f)) => {
+                            let slot2 = slot.clone();
+                            slot.stream.on_full(|slot| {
+                                let stream = slot.try_consume().ok().unwrap();
+                                SkipWhile {
+                                    inner: slot2,
+                                }.doit(stream, Some(f), g)
+                            }).unwrap();
+                            return
+                        }
+                        Ok((false, e, _)) => (Some(None), Ok(Some(e))),
+                        Err(e) => (None, Err(e)),
+                    }
+                }
+                (res, f) => (Some(f), res),
+            };
+            if let Some(f) = f {
+                slot.f.try_produce(f).ok()
+                    .expect("skip_while stream failed to produce");
+            }
+            g(res)
+        });
+        self.inner.stream.try_produce(stream).ok()
+            .expect("skip_while failed to produce stream");
+    }
+}
+
+impl<S, F> Stream for SkipWhile<S, F>
+    where S: Stream,
+          F: FnMut(&S::Item) -> bool + Send + 'static,
+{
+    type Item = S::Item;
+    type Error = S::Error;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<Self::Item, Self::Error>) + Send + 'static,
+    {
+        let f = match util::opt2poll(self.inner.f.try_consume().ok()) {
+            Ok(f) => f,
+            Err(e) => return g(Err(e)),
+        };
+        let stream = self.inner.stream.try_consume().ok()
+                             .expect("skip_while got closure, failed to get stream");
+        self.doit(stream, f, g)
+    }
+
+    fn schedule_boxed(&mut self,
+                      g: Box<Callback<Option<Self::Item>, Self::Error>>) {
+        self.schedule(|r| g.call(r))
+    }
+}
@@ -0,0 +1,141 @@
+use {Callback, PollError};
+use executor::{self, Executor, Current};
+use stream::StreamResult;
+use util;
+
+// The bookkeeping shared by the broadcast and watch channels: how many
+// senders are left, and where each receiver is up to.
+//
+// This lives behind the channel's lock. Callbacks are never run from in here,
+// instead they're handed back as `Handoff`s to be called once the lock has
+// been released.
+pub struct Subscribers<T, E> {
+    senders: usize,
+    cursors: Vec<Cursor<T, E>>,
+    next_id: usize,
+}
+
+struct Cursor<T, E> {
+    id: usize,
+    // what this means is up to the channel, but it only ever moves forward
+    pos: u64,
+    // only ever set while the receiver has nothing left to see
+    waiting: Option<Handoff<T, E>>,
+}
+
+// A receiver's callback, along with the executor it was scheduled on.
+pub struct Handoff<T, E> {
+    cb: Box<Callback<Option<T>, E>>,
+    executor: Current,
+}
+
+impl<T, E> Subscribers<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    // Starts out with a single sender and no receivers.
+    pub fn new() -> Subscribers<T, E> {
+        Subscribers {
+            senders: 1,
+            cursors: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn add_sender(&mut self) {
+        self.senders += 1;
+    }
+
+    // Once the last sender goes away every receiver which is waiting has
+    // reached the end, so their callbacks are handed back to be told so.
+    pub fn remove_sender(&mut self) -> Vec<Handoff<T, E>> {
+        self.senders -= 1;
+        if self.senders == 0 {
+            self.cursors.iter_mut().filter_map(|c| c.waiting.take()).collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    // Registers a new receiver starting out at `pos`, returning its id.
+    pub fn add(&mut self, pos: u64) -> usize {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.cursors.push(Cursor { id: id, pos: pos, waiting: None });
+        id
+    }
+
+    // Unregisters a receiver, handing back its callback if it was waiting.
+    pub fn remove(&mut self, id: usize) -> Option<Handoff<T, E>> {
+        let i = self.cursors.iter().position(|c| c.id == id);
+        i.map(|i| self.cursors.remove(i)).and_then(|c| c.waiting)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cursors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cursors.is_empty()
+    }
+
+    pub fn pos(&mut self, id: usize) -> &mut u64 {
+        &mut self.cursor(id).pos
+    }
+
+    // Takes the callback of every waiting receiver, moving it to `pos`.
+    pub fn wake(&mut self, pos: u64) -> Vec<Handoff<T, E>> {
+        self.cursors.iter_mut().filter_map(|c| {
+            c.waiting.take().inspect(|_| c.pos = pos)
+        }).collect()
+    }
+
+    // Schedules `cb` for the receiver `id`.
+    //
+    // `next` looks for something for the receiver to see given its position,
+    // moving it along if so. If there's nothing then the stream either ends
+    // or `cb` is kept until there is, otherwise the callback and its result
+    // are handed back.
+    pub fn schedule<F>(&mut self,
+                       id: usize,
+                       cb: Box<Callback<Option<T>, E>>,
+                       next: F)
+                       -> Option<(Handoff<T, E>, StreamResult<T, E>)>
+        where F: FnOnce(&mut u64) -> Option<StreamResult<T, E>>
+    {
+        let h = Handoff { cb: cb, executor: executor::current() };
+        let senders = self.senders;
+        let cursor = self.cursor(id);
+        let res = if cursor.waiting.is_some() {
+            Err(util::reused())
+        } else if let Some(res) = next(&mut cursor.pos) {
+            res
+        } else if senders == 0 {
+            Ok(None)
+        } else {
+            cursor.waiting = Some(h);
+            return None
+        };
+        Some((h, res))
+    }
+
+    fn cursor(&mut self, id: usize) -> &mut Cursor<T, E> {
+        self.cursors.iter_mut()
+            .find(|c| c.id == id)
+            .expect("receiver not registered")
+    }
+}
+
+impl<T, E> Handoff<T, E>
+    where T: Send + 'static,
+          E: Send + 'static,
+{
+    pub fn call(self, res: StreamResult<T, E>) {
+        let Handoff { cb, executor } = self;
+        executor.execute(|| cb.call(res))
+    }
+
+    pub fn cancel(self) {
+        self.call(Err(PollError::Canceled))
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use Callback;
+use slot::Slot;
+use stream::{Stream, StreamResult};
+use util;
+
+pub struct Take<S> {
+    stream: S,
+    remaining: Arc<Slot<u64>>,
+}
+
+pub fn new<S>(s: S, amt: u64) -> Take<S> where S: Stream {
+    Take {
+        stream: s,
+        remaining: Arc::new(Slot::new(Some(amt))),
+    }
+}
+
+impl<S> Stream for Take<S>
+    where S: Stream,
+{
+    type Item = S::Item;
+    type Error = S::Error;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<Self::Item, Self::Error>) + Send + 'static,
+    {
+        let remaining = match util::opt2poll(self.remaining.try_consume().ok()) {
+            Ok(n) => n,
+            Err(e) => return g(Err(e)),
+        };
+        if remaining == 0 {
+            self.remaining.try_produce(0).ok().expect("take failed to produce");
+            return g(Ok(None))
+        }
+        let slot = self.remaining.clone();
+        self.stream.schedule(move |res| {
+            // Only elements count towards the limit, errors are passed through
+            let remaining = match res {
+                Ok(Some(_)) => remaining - 1,
+                _ => remaining,
+            };
+            slot.try_produce(remaining).ok().expect("take failed to produce");
+            g(res)
+        })
+    }
+
+    fn schedule_boxed(&mut self,
+                      g: Box<Callback<Option<Self::Item>, Self::Error>>) {
+        self.schedule(|r| g.call(r))
+    }
+}
@@ -0,0 +1,65 @@
+use std::sync::Arc;
+
+use Callback;
+use slot::Slot;
+use stream::{Stream, StreamResult};
+use util;
+
+pub struct TakeWhile<S, F> {
+    stream: S,
+    // `None` once the predicate has returned `false`
+    f: Arc<Slot<Option<F>>>,
+}
+
+pub fn new<S, F>(s: S, f: F) -> TakeWhile<S, F> where F: Send + 'static {
+    TakeWhile {
+        stream: s,
+        f: Arc::new(Slot::new(Some(Some(f)))),
+    }
+}
+
+impl<S, F> Stream for TakeWhile<S, F>
+    where S: Stream,
+          F: FnMut(&S::Item) -> bool + Send + 'static,
+{
+    type Item = S::Item;
+    type Error = S::Error;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<Self::Item, Self::Error>) + Send + 'static,
+    {
+        let mut f = match util::opt2poll(self.f.try_consume().ok()) {
+            Ok(Some(f)) => f,
+            Ok(None) => {
+                self.f.try_produce(None).ok()
+                    .expect("take_while stream failed to produce");
+                return g(Ok(None))
+            }
+            Err(e) => return g(Err(e)),
+        };
+        let slot = self.f.clone();
+        self.stream.schedule(move |res| {
+            let (f, res) = match res {
+                Ok(Some(e)) => {
+                    match util::recover(|| (f(&e), e, f)) {
+                        Ok((true, e, f)) => (Some(Some(f)), Ok(Some(e))),
+                        Ok((false, _, _)) => (Some(None), Ok(None)),
+                        Err(e) => (None, Err(e)),
+                    }
+                }
+                Ok(None) => (Some(Some(f)), Ok(None)),
+                Err(e) => (Some(Some(f)), Err(e)),
+            };
+            if let Some(f) = f {
+                slot.try_produce(f).ok()
+                    .expect("take_while stream failed to produce");
+            }
+            g(res)
+        })
+    }
+
+    fn schedule_boxed(&mut self,
+                      g: Box<Callback<Option<Self::Item>, Self::Error>>) {
+        self.schedule(|r| g.call(r))
+    }
+}
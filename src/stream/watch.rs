@@ -0,0 +1,183 @@
+use std::sync::{Arc, Mutex};
+
+use Callback;
+use stream::{Stream, StreamResult};
+use stream::subscribers::Subscribers;
+
+// Creates a channel which holds a single value, starting out as `init`, that
+// receivers are told about whenever it changes.
+//
+// Each receiver is a `Stream` yielding the latest value every time it's been
+// replaced since the receiver last looked. Updates which land in between are
+// skipped, so this is suited to things like configuration and shutdown
+// signals where only the current state matters. The stream ends once every
+// `Sender` has been dropped and the receiver has seen the final value.
+pub fn channel<T>(init: T) -> (Sender<T>, Receiver<T>)
+    where T: Clone + Send + 'static,
+{
+    let inner = Arc::new(Inner {
+        state: Mutex::new(State {
+            value: init,
+            version: 0,
+            subs: Subscribers::new(),
+        }),
+    });
+    let rx = Receiver::new(&inner);
+    (Sender { inner: inner }, rx)
+}
+
+pub struct Sender<T>
+    where T: Clone + Send + 'static,
+{
+    inner: Arc<Inner<T>>,
+}
+
+pub struct Receiver<T>
+    where T: Clone + Send + 'static,
+{
+    inner: Arc<Inner<T>>,
+    id: usize,
+}
+
+struct Inner<T>
+    where T: Clone + Send + 'static,
+{
+    state: Mutex<State<T>>,
+}
+
+struct State<T>
+    where T: Clone + Send + 'static,
+{
+    value: T,
+    // bumped every time `value` is replaced
+    version: u64,
+    // each receiver's position is the version it last saw
+    subs: Subscribers<T, ()>,
+}
+
+impl<T> Sender<T>
+    where T: Clone + Send + 'static,
+{
+    // Replaces the value and wakes up any receivers waiting on a change.
+    //
+    // The value is kept even if there are no receivers right now, so that
+    // later calls to `subscribe` see it.
+    pub fn send(&self, value: T) {
+        let waiting = {
+            let mut state = self.inner.state.lock().unwrap();
+            state.value = value.clone();
+            state.version += 1;
+            let version = state.version;
+            state.subs.wake(version)
+        };
+        for h in waiting {
+            h.call(Ok(Some(value.clone())));
+        }
+    }
+
+    // Creates a new receiver which sees the value as it is now, and is told
+    // about every change from then on.
+    pub fn subscribe(&self) -> Receiver<T> {
+        Receiver::new(&self.inner)
+    }
+
+    // Returns how many receivers there are.
+    pub fn receiver_count(&self) -> usize {
+        self.inner.state.lock().unwrap().subs.len()
+    }
+}
+
+impl<T> Clone for Sender<T>
+    where T: Clone + Send + 'static,
+{
+    fn clone(&self) -> Sender<T> {
+        self.inner.state.lock().unwrap().subs.add_sender();
+        Sender { inner: self.inner.clone() }
+    }
+}
+
+impl<T> Drop for Sender<T>
+    where T: Clone + Send + 'static,
+{
+    fn drop(&mut self) {
+        // The last sender going away ends every stream which is caught up
+        let waiting = self.inner.state.lock().unwrap().subs.remove_sender();
+        for h in waiting {
+            h.call(Ok(None));
+        }
+    }
+}
+
+impl<T> Receiver<T>
+    where T: Clone + Send + 'static,
+{
+    fn new(inner: &Arc<Inner<T>>) -> Receiver<T> {
+        let mut state = inner.state.lock().unwrap();
+        let version = state.version;
+        let id = state.subs.add(version);
+        Receiver { inner: inner.clone(), id: id }
+    }
+
+    // Returns the current value, marking it as seen.
+    pub fn get(&self) -> T {
+        let mut state = self.inner.state.lock().unwrap();
+        let version = state.version;
+        *state.subs.pos(self.id) = version;
+        state.value.clone()
+    }
+}
+
+impl<T> Clone for Receiver<T>
+    where T: Clone + Send + 'static,
+{
+    // The new receiver has seen the same version as this one.
+    fn clone(&self) -> Receiver<T> {
+        let mut state = self.inner.state.lock().unwrap();
+        let seen = *state.subs.pos(self.id);
+        let id = state.subs.add(seen);
+        Receiver { inner: self.inner.clone(), id: id }
+    }
+}
+
+impl<T> Stream for Receiver<T>
+    where T: Clone + Send + 'static,
+{
+    type Item = T;
+    type Error = ();
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<Self::Item, Self::Error>) + Send + 'static
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self,
+                      g: Box<Callback<Option<Self::Item>, Self::Error>>) {
+        let ready = {
+            let mut state = self.inner.state.lock().unwrap();
+            let State { ref value, version, ref mut subs } = *state;
+            subs.schedule(self.id, g, |seen| {
+                if *seen < version {
+                    *seen = version;
+                    Some(Ok(Some(value.clone())))
+                } else {
+                    None
+                }
+            })
+        };
+        if let Some((h, res)) = ready {
+            h.call(res)
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T>
+    where T: Clone + Send + 'static,
+{
+    fn drop(&mut self) {
+        let waiting = self.inner.state.lock().unwrap().subs.remove(self.id);
+        if let Some(h) = waiting {
+            h.cancel();
+        }
+    }
+}
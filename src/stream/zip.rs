@@ -0,0 +1,162 @@
+use Callback;
+use stream::{Stream, StreamResult};
+use stream::driver::{Driver, Machine, Next, Notify};
+
+// Pairs up the elements of two streams, ending as soon as either one ends.
+pub struct Zip<S1, S2>
+    where S1: Stream,
+          S2: Stream<Error=S1::Error>,
+{
+    driver: Driver<Zipper<S1, S2>>,
+}
+
+struct Zipper<S1, S2>
+    where S1: Stream,
+          S2: Stream,
+{
+    first: Side<S1>,
+    second: Side<S2>,
+    done: bool,
+}
+
+struct Side<S: Stream> {
+    // `None` once we've ended
+    stream: Option<S>,
+    pending: bool,
+    ready: Option<StreamResult<S::Item, S::Error>>,
+}
+
+enum Which<A, B> {
+    First(A),
+    Second(B),
+}
+
+pub fn new<S1, S2>(s1: S1, s2: S2) -> Zip<S1, S2>
+    where S1: Stream,
+          S2: Stream<Error=S1::Error>,
+{
+    Zip {
+        driver: Driver::new(Zipper {
+            first: Side::new(s1),
+            second: Side::new(s2),
+            done: false,
+        }),
+    }
+}
+
+impl<S1, S2> Stream for Zip<S1, S2>
+    where S1: Stream,
+          S2: Stream<Error=S1::Error>,
+{
+    type Item = (S1::Item, S2::Item);
+    type Error = S1::Error;
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<Self::Item, Self::Error>) + Send + 'static,
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self,
+                      g: Box<Callback<Option<Self::Item>, Self::Error>>) {
+        self.driver.schedule(g)
+    }
+}
+
+impl<S1, S2> Machine for Zipper<S1, S2>
+    where S1: Stream,
+          S2: Stream<Error=S1::Error>,
+{
+    type Item = Option<(S1::Item, S2::Item)>;
+    type Error = S1::Error;
+    type Event = Which<StreamResult<S1::Item, S1::Error>,
+                       StreamResult<S2::Item, S2::Error>>;
+
+    fn start(&mut self, wanted: bool, notify: &Notify<Self>) -> bool {
+        if !wanted {
+            return false
+        }
+        if let Some(s) = self.first.start() {
+            let notify = notify.clone();
+            s.schedule(move |r| notify.done(Which::First(r)));
+            return true
+        }
+        if let Some(s) = self.second.start() {
+            let notify = notify.clone();
+            s.schedule(move |r| notify.done(Which::Second(r)));
+            return true
+        }
+        false
+    }
+
+    fn event(&mut self, event: Self::Event) {
+        match event {
+            Which::First(r) => {
+                self.first.pending = false;
+                self.first.ready = Some(r);
+            }
+            Which::Second(r) => {
+                self.second.pending = false;
+                self.second.ready = Some(r);
+            }
+        }
+    }
+
+    fn next(&mut self) -> Option<Next<Self::Item, S1::Error>> {
+        self.take_ready().map(Next::Yield)
+    }
+}
+
+impl<S1, S2> Zipper<S1, S2>
+    where S1: Stream,
+          S2: Stream<Error=S1::Error>,
+{
+    // Errors from either side are passed along right away, otherwise we wait
+    // for an element from both sides or for either one to end.
+    fn take_ready(&mut self)
+                  -> Option<StreamResult<(S1::Item, S2::Item), S1::Error>> {
+        if self.done {
+            return Some(Ok(None))
+        }
+        if let Some(Err(_)) = self.first.ready {
+            return self.first.ready.take().map(|r| r.map(|_| None))
+        }
+        if let Some(Err(_)) = self.second.ready {
+            return self.second.ready.take().map(|r| r.map(|_| None))
+        }
+        match (&self.first.ready, &self.second.ready) {
+            (&Some(Ok(None)), _) |
+            (_, &Some(Ok(None))) => {
+                // Once we've ended there's no need to hang on to either
+                // stream.
+                self.done = true;
+                self.first.stream = None;
+                self.second.stream = None;
+                return Some(Ok(None))
+            }
+            (&Some(Ok(Some(_))), &Some(Ok(Some(_)))) => {}
+            _ => return None,
+        }
+        match (self.first.ready.take(), self.second.ready.take()) {
+            (Some(Ok(Some(a))), Some(Ok(Some(b)))) => Some(Ok(Some((a, b)))),
+            _ => panic!("[zip] elements disappeared"),
+        }
+    }
+}
+
+impl<S: Stream> Side<S> {
+    fn new(s: S) -> Side<S> {
+        Side { stream: Some(s), pending: false, ready: None }
+    }
+
+    fn start(&mut self) -> Option<&mut S> {
+        if self.pending || self.ready.is_some() {
+            return None
+        }
+        let stream = self.stream.as_mut();
+        if stream.is_some() {
+            self.pending = true;
+        }
+        stream
+    }
+}
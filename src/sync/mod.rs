@@ -0,0 +1,15 @@
+// Synchronization primitives whose blocking operations are futures.
+//
+// Each of these is a handle to some shared state, so clones all refer to the
+// same underlying lock or pool. Waiters queue up in order, and dropping the
+// future for one before it resolves gives up its place in line.
+
+mod mutex;
+mod rwlock;
+mod semaphore;
+mod rate_limiter;
+pub use self::mutex::{Mutex, MutexLock, MutexGuard};
+pub use self::rwlock::{RwLock, RwLockRead, RwLockWrite};
+pub use self::rwlock::{RwLockReadGuard, RwLockWriteGuard};
+pub use self::semaphore::{Semaphore, Acquire, Permit};
+pub use self::rate_limiter::{RateLimiter, Ready};
@@ -0,0 +1,217 @@
+use std::cell::UnsafeCell;
+use std::collections::VecDeque;
+use std::marker;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::sync::{self, Arc};
+
+use {Future, Callback, PollResult, PollError};
+use executor::{self, Executor, Current};
+use util;
+
+// A mutual exclusion lock whose `lock` returns a future rather than blocking.
+//
+// Waiters are granted the lock in the order they were scheduled.
+pub struct Mutex<T: Send + 'static> {
+    inner: Arc<Inner<T>>,
+}
+
+// A future resolving to a guard once the lock has been acquired.
+pub struct MutexLock<T: Send + 'static> {
+    inner: Arc<Inner<T>>,
+    state: LockState,
+}
+
+// Gives access to the data behind a `Mutex`, releasing the lock when dropped.
+pub struct MutexGuard<T: Send + 'static> {
+    inner: Arc<Inner<T>>,
+    // `Inner` is `Sync` for any `Send` data, but a shared guard hands out
+    // `&T`, so the guard itself can only be `Sync` if `T` is.
+    _marker: marker::PhantomData<*mut T>,
+}
+
+enum LockState {
+    Start,
+    Waiting(usize),
+    Done,
+}
+
+struct Inner<T: Send + 'static> {
+    state: sync::Mutex<State<T>>,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send + 'static> Send for Inner<T> {}
+unsafe impl<T: Send + 'static> Sync for Inner<T> {}
+
+unsafe impl<T: Send + 'static> Send for MutexGuard<T> {}
+unsafe impl<T: Send + Sync + 'static> Sync for MutexGuard<T> {}
+
+struct State<T: Send + 'static> {
+    locked: bool,
+    waiters: VecDeque<Waiter<T>>,
+    next_id: usize,
+}
+
+struct Waiter<T: Send + 'static> {
+    id: usize,
+    cb: Box<Callback<MutexGuard<T>, ()>>,
+    executor: Current,
+}
+
+impl<T: Send + 'static> Mutex<T> {
+    pub fn new(t: T) -> Mutex<T> {
+        Mutex {
+            inner: Arc::new(Inner {
+                state: sync::Mutex::new(State {
+                    locked: false,
+                    waiters: VecDeque::new(),
+                    next_id: 0,
+                }),
+                data: UnsafeCell::new(t),
+            }),
+        }
+    }
+
+    // Returns a future which resolves to a guard once the lock is ours.
+    pub fn lock(&self) -> MutexLock<T> {
+        MutexLock {
+            inner: self.inner.clone(),
+            state: LockState::Start,
+        }
+    }
+
+    // Acquires the lock if it's free and nobody is waiting for it.
+    pub fn try_lock(&self) -> Option<MutexGuard<T>> {
+        if self.inner.try_acquire() {
+            Some(MutexGuard::new(&self.inner))
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: Send + 'static> Clone for Mutex<T> {
+    fn clone(&self) -> Mutex<T> {
+        Mutex { inner: self.inner.clone() }
+    }
+}
+
+impl<T: Send + 'static> Inner<T> {
+    fn try_acquire(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.locked || !state.waiters.is_empty() {
+            false
+        } else {
+            state.locked = true;
+            true
+        }
+    }
+}
+
+impl<T: Send + 'static> Future for MutexLock<T> {
+    type Item = MutexGuard<T>;
+    type Error = ();
+
+    fn poll(&mut self) -> Option<PollResult<MutexGuard<T>, ()>> {
+        match self.state {
+            LockState::Start => {}
+            LockState::Waiting(..) |
+            LockState::Done => return Some(Err(util::reused())),
+        }
+        if self.inner.try_acquire() {
+            self.state = LockState::Done;
+            Some(Ok(MutexGuard::new(&self.inner)))
+        } else {
+            None
+        }
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<MutexGuard<T>, ()>) + Send + 'static
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<MutexGuard<T>, ()>>) {
+        match mem::replace(&mut self.state, LockState::Done) {
+            LockState::Start => {}
+            other => {
+                self.state = other;
+                return executor::current().execute(|| cb.call(Err(util::reused())))
+            }
+        }
+        let mut state = self.inner.state.lock().unwrap();
+        if !state.locked && state.waiters.is_empty() {
+            state.locked = true;
+            drop(state);
+            let guard = MutexGuard::new(&self.inner);
+            return executor::current().execute(|| cb.call(Ok(guard)))
+        }
+        let id = state.next_id;
+        state.next_id += 1;
+        state.waiters.push_back(Waiter {
+            id: id,
+            cb: cb,
+            executor: executor::current(),
+        });
+        self.state = LockState::Waiting(id);
+    }
+}
+
+impl<T: Send + 'static> Drop for MutexLock<T> {
+    fn drop(&mut self) {
+        let id = match self.state {
+            LockState::Waiting(id) => id,
+            _ => return,
+        };
+        let waiter = {
+            let mut state = self.inner.state.lock().unwrap();
+            let pos = state.waiters.iter().position(|w| w.id == id);
+            pos.and_then(|i| state.waiters.remove(i))
+        };
+        if let Some(w) = waiter {
+            let cb = w.cb;
+            w.executor.execute(|| cb.call(Err(PollError::Canceled)))
+        }
+    }
+}
+
+impl<T: Send + 'static> MutexGuard<T> {
+    fn new(inner: &Arc<Inner<T>>) -> MutexGuard<T> {
+        MutexGuard { inner: inner.clone(), _marker: marker::PhantomData }
+    }
+}
+
+impl<T: Send + 'static> Deref for MutexGuard<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.inner.data.get() }
+    }
+}
+
+impl<T: Send + 'static> DerefMut for MutexGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.inner.data.get() }
+    }
+}
+
+impl<T: Send + 'static> Drop for MutexGuard<T> {
+    fn drop(&mut self) {
+        // Hand the lock straight to the next waiter, if there is one, so
+        // nobody can sneak in ahead of it.
+        let next = {
+            let mut state = self.inner.state.lock().unwrap();
+            let next = state.waiters.pop_front();
+            if next.is_none() {
+                state.locked = false;
+            }
+            next
+        };
+        if let Some(w) = next {
+            let cb = w.cb;
+            let guard = MutexGuard::new(&self.inner);
+            w.executor.execute(|| cb.call(Ok(guard)))
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::VecDeque;
+use std::sync::{self, Arc};
+use std::time::{Duration, Instant};
+
+use {Future, Callback, PollResult, Promise, Complete, promise};
+use timer;
+
+// A token bucket which lets through up to `capacity` operations at once, and
+// one more every `period` after that.
+//
+// Waiters are let through in the order they asked.
+pub struct RateLimiter {
+    inner: Arc<Inner>,
+}
+
+// A future which resolves once a token has been taken from a `RateLimiter`.
+pub struct Ready {
+    inner: Arc<Inner>,
+    promise: Option<Promise<Token, ()>>,
+}
+
+// A token on its way to a waiter, which goes back into the bucket if it's
+// dropped before the waiter gets it.
+struct Token {
+    inner: Option<Arc<Inner>>,
+}
+
+struct Inner {
+    state: sync::Mutex<State>,
+    capacity: usize,
+    period: Duration,
+}
+
+struct State {
+    tokens: usize,
+    // the point in time up to which tokens have been added
+    refilled: Instant,
+    waiters: VecDeque<Complete<Token, ()>>,
+    // whether there's a timer running to wake us up for the next token
+    timer: bool,
+}
+
+impl RateLimiter {
+    // Creates a bucket which starts out full.
+    //
+    // Panics if `capacity` is zero or `period` is empty.
+    pub fn new(capacity: usize, period: Duration) -> RateLimiter {
+        assert!(capacity > 0, "rate limiter must hold at least one token");
+        assert!(period > Duration::new(0, 0), "rate limiter period is empty");
+        RateLimiter {
+            inner: Arc::new(Inner {
+                state: sync::Mutex::new(State {
+                    tokens: capacity,
+                    refilled: Instant::now(),
+                    waiters: VecDeque::new(),
+                    timer: false,
+                }),
+                capacity: capacity,
+                period: period,
+            }),
+        }
+    }
+
+    // Returns a future which resolves once a token is available, taking it.
+    pub fn ready(&self) -> Ready {
+        let (p, c) = promise();
+        self.inner.state.lock().unwrap().waiters.push_back(c);
+        Inner::wake(&self.inner);
+        Ready {
+            inner: self.inner.clone(),
+            promise: Some(p),
+        }
+    }
+
+    // Takes a token if one is available and nobody is waiting ahead of us.
+    pub fn try_ready(&self) -> bool {
+        let mut state = self.inner.state.lock().unwrap();
+        self.inner.refill(&mut state);
+        if state.waiters.is_empty() && state.tokens > 0 {
+            state.tokens -= 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl Clone for RateLimiter {
+    fn clone(&self) -> RateLimiter {
+        RateLimiter { inner: self.inner.clone() }
+    }
+}
+
+impl Inner {
+    fn refill(&self, state: &mut State) {
+        let now = Instant::now();
+        let periods = (now - state.refilled).as_nanos() / self.period.as_nanos();
+        let periods = if periods > self.capacity as u128 {
+            self.capacity
+        } else {
+            periods as usize
+        };
+        state.tokens += periods;
+        if state.tokens >= self.capacity {
+            // A full bucket doesn't keep filling up
+            state.tokens = self.capacity;
+            state.refilled = now;
+        } else {
+            // Only whole periods count, so the rest carries over to the next
+            // token. `periods` is below `capacity` here, but that could still
+            // be too many to add up.
+            let elapsed = if periods > u32::MAX as usize {
+                None
+            } else {
+                self.period.checked_mul(periods as u32)
+            };
+            match elapsed {
+                Some(elapsed) => state.refilled += elapsed,
+                None => state.refilled = now,
+            }
+        }
+    }
+
+    // Hands out tokens to as many waiters at the front of the queue as
+    // possible, skipping those who've stopped waiting, and sets a timer for
+    // the next token if anyone is left.
+    fn wake(me: &Arc<Inner>) {
+        loop {
+            let (c, granted) = {
+                let mut state = me.state.lock().unwrap();
+                me.refill(&mut state);
+                let granted = match state.waiters.front() {
+                    Some(c) if c.is_canceled() => false,
+                    Some(_) if state.tokens > 0 => {
+                        state.tokens -= 1;
+                        true
+                    }
+                    Some(_) if !state.timer => {
+                        state.timer = true;
+                        let next = state.refilled + me.period;
+                        drop(state);
+                        return Inner::arm(me, next)
+                    }
+                    _ => return,
+                };
+                (state.waiters.pop_front().unwrap(), granted)
+            };
+            // If the waiter goes away in the meantime then the token is
+            // dropped along with its promise, handing it straight back.
+            if granted {
+                c.finish(Token { inner: Some(me.clone()) });
+            }
+        }
+    }
+
+    fn arm(me: &Arc<Inner>, at: Instant) {
+        let me = me.clone();
+        let now = Instant::now();
+        let dur = if at > now {at - now} else {Duration::new(0, 0)};
+        // The delay failing still has to clear the flag, or nobody would ever
+        // set another timer.
+        timer::delay(dur).then(move |_| {
+            me.state.lock().unwrap().timer = false;
+            Inner::wake(&me);
+            Ok::<(), ()>(())
+        }).forget()
+    }
+}
+
+impl Future for Ready {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Option<PollResult<(), ()>> {
+        self.promise.as_mut().unwrap().poll().map(|r| r.map(Token::take))
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<(), ()>) + Send + 'static
+    {
+        self.promise.as_mut().unwrap().schedule(|r| g(r.map(Token::take)))
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<(), ()>>) {
+        self.schedule(|r| cb.call(r))
+    }
+}
+
+impl Drop for Ready {
+    fn drop(&mut self) {
+        drop(self.promise.take());
+        Inner::wake(&self.inner);
+    }
+}
+
+impl Token {
+    // The waiter has its token, so there's nothing to hand back.
+    fn take(mut self) {
+        self.inner = None;
+    }
+}
+
+impl Drop for Token {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            {
+                let mut state = inner.state.lock().unwrap();
+                inner.refill(&mut state);
+                if state.tokens < inner.capacity {
+                    state.tokens += 1;
+                }
+            }
+            Inner::wake(&inner);
+        }
+    }
+}
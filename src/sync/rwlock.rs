@@ -0,0 +1,384 @@
+use std::cell::UnsafeCell;
+use std::collections::VecDeque;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::sync::{self, Arc};
+
+use {Future, Callback, PollResult, PollError};
+use executor::{self, Executor, Current};
+use util;
+
+// A reader-writer lock whose `read` and `write` return futures rather than
+// blocking.
+//
+// Waiters are granted the lock in the order they were scheduled, with
+// consecutive readers let in together. Readers never skip ahead of a waiting
+// writer.
+pub struct RwLock<T: Send + Sync + 'static> {
+    inner: Arc<Inner<T>>,
+}
+
+// A future resolving to a shared guard once the lock has been acquired for
+// reading.
+pub struct RwLockRead<T: Send + Sync + 'static> {
+    inner: Arc<Inner<T>>,
+    state: LockState,
+}
+
+// A future resolving to an exclusive guard once the lock has been acquired
+// for writing.
+pub struct RwLockWrite<T: Send + Sync + 'static> {
+    inner: Arc<Inner<T>>,
+    state: LockState,
+}
+
+pub struct RwLockReadGuard<T: Send + Sync + 'static> {
+    inner: Arc<Inner<T>>,
+}
+
+pub struct RwLockWriteGuard<T: Send + Sync + 'static> {
+    inner: Arc<Inner<T>>,
+}
+
+enum LockState {
+    Start,
+    Waiting(usize),
+    Done,
+}
+
+struct Inner<T: Send + Sync + 'static> {
+    state: sync::Mutex<State<T>>,
+    data: UnsafeCell<T>,
+}
+
+unsafe impl<T: Send + Sync + 'static> Send for Inner<T> {}
+unsafe impl<T: Send + Sync + 'static> Sync for Inner<T> {}
+
+struct State<T: Send + Sync + 'static> {
+    readers: usize,
+    writer: bool,
+    waiters: VecDeque<Waiter<T>>,
+    next_id: usize,
+}
+
+struct Waiter<T: Send + Sync + 'static> {
+    id: usize,
+    waker: Waker<T>,
+    executor: Current,
+}
+
+enum Waker<T: Send + Sync + 'static> {
+    Read(Box<Callback<RwLockReadGuard<T>, ()>>),
+    Write(Box<Callback<RwLockWriteGuard<T>, ()>>),
+}
+
+impl<T: Send + Sync + 'static> RwLock<T> {
+    pub fn new(t: T) -> RwLock<T> {
+        RwLock {
+            inner: Arc::new(Inner {
+                state: sync::Mutex::new(State {
+                    readers: 0,
+                    writer: false,
+                    waiters: VecDeque::new(),
+                    next_id: 0,
+                }),
+                data: UnsafeCell::new(t),
+            }),
+        }
+    }
+
+    // Returns a future which resolves to a shared guard once there's no
+    // writer holding or waiting for the lock ahead of us.
+    pub fn read(&self) -> RwLockRead<T> {
+        RwLockRead {
+            inner: self.inner.clone(),
+            state: LockState::Start,
+        }
+    }
+
+    // Returns a future which resolves to an exclusive guard once everyone
+    // ahead of us has released the lock.
+    pub fn write(&self) -> RwLockWrite<T> {
+        RwLockWrite {
+            inner: self.inner.clone(),
+            state: LockState::Start,
+        }
+    }
+
+    // Acquires the lock for reading if that's possible without waiting.
+    pub fn try_read(&self) -> Option<RwLockReadGuard<T>> {
+        if self.inner.try_acquire(false) {
+            Some(RwLockReadGuard { inner: self.inner.clone() })
+        } else {
+            None
+        }
+    }
+
+    // Acquires the lock for writing if that's possible without waiting.
+    pub fn try_write(&self) -> Option<RwLockWriteGuard<T>> {
+        if self.inner.try_acquire(true) {
+            Some(RwLockWriteGuard { inner: self.inner.clone() })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> Clone for RwLock<T> {
+    fn clone(&self) -> RwLock<T> {
+        RwLock { inner: self.inner.clone() }
+    }
+}
+
+impl<T: Send + Sync + 'static> State<T> {
+    fn try_acquire(&mut self, write: bool) -> bool {
+        if self.writer || !self.waiters.is_empty() {
+            return false
+        }
+        if write {
+            if self.readers > 0 {
+                return false
+            }
+            self.writer = true;
+        } else {
+            self.readers += 1;
+        }
+        true
+    }
+
+    // Takes as many waiters off the front of the queue as can now hold the
+    // lock, marking them as holding it.
+    fn wake(&mut self) -> Vec<Waiter<T>> {
+        let mut woken = Vec::new();
+        loop {
+            match self.waiters.front().map(|w| &w.waker) {
+                Some(&Waker::Read(..)) if !self.writer => self.readers += 1,
+                Some(&Waker::Write(..)) if !self.writer && self.readers == 0 => {
+                    self.writer = true;
+                }
+                _ => return woken,
+            }
+            woken.push(self.waiters.pop_front().unwrap());
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> Inner<T> {
+    fn try_acquire(&self, write: bool) -> bool {
+        self.state.lock().unwrap().try_acquire(write)
+    }
+
+    // Joins the queue and returns our place in it, unless the lock can be
+    // acquired right away in which case `waker` is handed its guard.
+    fn acquire_or_wait(me: &Arc<Inner<T>>, waker: Waker<T>) -> Option<usize> {
+        let executor = executor::current();
+        let mut state = me.state.lock().unwrap();
+        let write = match waker {
+            Waker::Read(..) => false,
+            Waker::Write(..) => true,
+        };
+        if state.try_acquire(write) {
+            drop(state);
+            waker.grant(me, executor);
+            return None
+        }
+        let id = state.next_id;
+        state.next_id += 1;
+        state.waiters.push_back(Waiter {
+            id: id,
+            waker: waker,
+            executor: executor,
+        });
+        Some(id)
+    }
+
+    fn cancel(me: &Arc<Inner<T>>, id: usize) {
+        let (waiter, woken) = {
+            let mut state = me.state.lock().unwrap();
+            let pos = state.waiters.iter().position(|w| w.id == id);
+            let waiter = pos.and_then(|i| state.waiters.remove(i));
+            // A writer leaving the front of the queue may let readers in
+            (waiter, state.wake())
+        };
+        if let Some(w) = waiter {
+            w.waker.cancel(w.executor);
+        }
+        Inner::grant(me, woken);
+    }
+
+    fn release(me: &Arc<Inner<T>>, write: bool) {
+        let woken = {
+            let mut state = me.state.lock().unwrap();
+            if write {
+                state.writer = false;
+            } else {
+                state.readers -= 1;
+            }
+            state.wake()
+        };
+        Inner::grant(me, woken);
+    }
+
+    fn grant(me: &Arc<Inner<T>>, woken: Vec<Waiter<T>>) {
+        for w in woken {
+            w.waker.grant(me, w.executor);
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> Waker<T> {
+    // Hands the callback a guard for the lock, which it's already holding.
+    fn grant(self, inner: &Arc<Inner<T>>, executor: Current) {
+        match self {
+            Waker::Read(cb) => {
+                let guard = RwLockReadGuard { inner: inner.clone() };
+                executor.execute(|| cb.call(Ok(guard)))
+            }
+            Waker::Write(cb) => {
+                let guard = RwLockWriteGuard { inner: inner.clone() };
+                executor.execute(|| cb.call(Ok(guard)))
+            }
+        }
+    }
+
+    fn cancel(self, executor: Current) {
+        match self {
+            Waker::Read(cb) => {
+                executor.execute(|| cb.call(Err(PollError::Canceled)))
+            }
+            Waker::Write(cb) => {
+                executor.execute(|| cb.call(Err(PollError::Canceled)))
+            }
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> Future for RwLockRead<T> {
+    type Item = RwLockReadGuard<T>;
+    type Error = ();
+
+    fn poll(&mut self) -> Option<PollResult<RwLockReadGuard<T>, ()>> {
+        match self.state {
+            LockState::Start => {}
+            LockState::Waiting(..) |
+            LockState::Done => return Some(Err(util::reused())),
+        }
+        if self.inner.try_acquire(false) {
+            self.state = LockState::Done;
+            Some(Ok(RwLockReadGuard { inner: self.inner.clone() }))
+        } else {
+            None
+        }
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<RwLockReadGuard<T>, ()>) + Send + 'static
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<RwLockReadGuard<T>, ()>>) {
+        match mem::replace(&mut self.state, LockState::Done) {
+            LockState::Start => {}
+            other => {
+                self.state = other;
+                return executor::current().execute(|| {
+                    cb.call(Err(util::reused()))
+                })
+            }
+        }
+        let waker = Waker::Read(cb);
+        if let Some(id) = Inner::acquire_or_wait(&self.inner, waker) {
+            self.state = LockState::Waiting(id);
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> Drop for RwLockRead<T> {
+    fn drop(&mut self) {
+        if let LockState::Waiting(id) = self.state {
+            Inner::cancel(&self.inner, id);
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> Deref for RwLockReadGuard<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.inner.data.get() }
+    }
+}
+
+impl<T: Send + Sync + 'static> Drop for RwLockReadGuard<T> {
+    fn drop(&mut self) {
+        Inner::release(&self.inner, false);
+    }
+}
+
+impl<T: Send + Sync + 'static> Future for RwLockWrite<T> {
+    type Item = RwLockWriteGuard<T>;
+    type Error = ();
+
+    fn poll(&mut self) -> Option<PollResult<RwLockWriteGuard<T>, ()>> {
+        match self.state {
+            LockState::Start => {}
+            LockState::Waiting(..) |
+            LockState::Done => return Some(Err(util::reused())),
+        }
+        if self.inner.try_acquire(true) {
+            self.state = LockState::Done;
+            Some(Ok(RwLockWriteGuard { inner: self.inner.clone() }))
+        } else {
+            None
+        }
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<RwLockWriteGuard<T>, ()>) + Send + 'static
+    {
+        self.schedule_boxed(Box::new(g))
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<RwLockWriteGuard<T>, ()>>) {
+        match mem::replace(&mut self.state, LockState::Done) {
+            LockState::Start => {}
+            other => {
+                self.state = other;
+                return executor::current().execute(|| {
+                    cb.call(Err(util::reused()))
+                })
+            }
+        }
+        let waker = Waker::Write(cb);
+        if let Some(id) = Inner::acquire_or_wait(&self.inner, waker) {
+            self.state = LockState::Waiting(id);
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> Drop for RwLockWrite<T> {
+    fn drop(&mut self) {
+        if let LockState::Waiting(id) = self.state {
+            Inner::cancel(&self.inner, id);
+        }
+    }
+}
+
+impl<T: Send + Sync + 'static> Deref for RwLockWriteGuard<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { &*self.inner.data.get() }
+    }
+}
+
+impl<T: Send + Sync + 'static> DerefMut for RwLockWriteGuard<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.inner.data.get() }
+    }
+}
+
+impl<T: Send + Sync + 'static> Drop for RwLockWriteGuard<T> {
+    fn drop(&mut self) {
+        Inner::release(&self.inner, true);
+    }
+}
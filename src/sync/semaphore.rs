@@ -0,0 +1,154 @@
+use std::collections::VecDeque;
+use std::sync::{self, Arc};
+
+use {Future, Callback, PollResult, Promise, Complete, promise};
+
+// A counting semaphore whose `acquire` returns a future rather than blocking.
+//
+// Waiters are granted permits in the order they asked for them.
+pub struct Semaphore {
+    inner: Arc<Inner>,
+}
+
+// A future resolving to a `Permit` once enough permits are available.
+pub struct Acquire {
+    inner: Arc<Inner>,
+    promise: Option<Promise<Permit, ()>>,
+}
+
+// A number of permits taken from a `Semaphore`, which are handed back when
+// this is dropped.
+pub struct Permit {
+    inner: Arc<Inner>,
+    amt: usize,
+}
+
+struct Inner {
+    state: sync::Mutex<State>,
+    capacity: usize,
+}
+
+struct State {
+    available: usize,
+    waiters: VecDeque<(usize, Complete<Permit, ()>)>,
+}
+
+impl Semaphore {
+    pub fn new(permits: usize) -> Semaphore {
+        Semaphore {
+            inner: Arc::new(Inner {
+                state: sync::Mutex::new(State {
+                    available: permits,
+                    waiters: VecDeque::new(),
+                }),
+                capacity: permits,
+            }),
+        }
+    }
+
+    // Returns a future which resolves once `amt` permits have been taken.
+    //
+    // Panics if `amt` is more than the semaphore was created with.
+    pub fn acquire(&self, amt: usize) -> Acquire {
+        assert!(amt <= self.inner.capacity,
+                "cannot acquire more permits than the semaphore has");
+        let (p, c) = promise();
+        self.inner.state.lock().unwrap().waiters.push_back((amt, c));
+        Inner::wake(&self.inner);
+        Acquire {
+            inner: self.inner.clone(),
+            promise: Some(p),
+        }
+    }
+
+    // Takes `amt` permits if they're available and nobody is waiting ahead
+    // of us.
+    pub fn try_acquire(&self, amt: usize) -> Option<Permit> {
+        let mut state = self.inner.state.lock().unwrap();
+        if state.waiters.is_empty() && state.available >= amt {
+            state.available -= amt;
+            Some(Permit { inner: self.inner.clone(), amt: amt })
+        } else {
+            None
+        }
+    }
+
+    pub fn available_permits(&self) -> usize {
+        self.inner.state.lock().unwrap().available
+    }
+}
+
+impl Clone for Semaphore {
+    fn clone(&self) -> Semaphore {
+        Semaphore { inner: self.inner.clone() }
+    }
+}
+
+impl Inner {
+    // Hands out permits to as many waiters at the front of the queue as
+    // possible, skipping those who've stopped waiting.
+    fn wake(me: &Arc<Inner>) {
+        loop {
+            let (amt, c, granted) = {
+                let mut state = me.state.lock().unwrap();
+                let granted = match state.waiters.front() {
+                    Some((_, c)) if c.is_canceled() => false,
+                    Some(&(amt, _)) if amt <= state.available => {
+                        state.available -= amt;
+                        true
+                    }
+                    _ => return,
+                };
+                let (amt, c) = state.waiters.pop_front().unwrap();
+                (amt, c, granted)
+            };
+            // If the waiter goes away in the meantime then the permit is
+            // dropped along with its promise, handing it straight back.
+            if granted {
+                c.finish(Permit { inner: me.clone(), amt: amt });
+            }
+        }
+    }
+}
+
+impl Future for Acquire {
+    type Item = Permit;
+    type Error = ();
+
+    fn poll(&mut self) -> Option<PollResult<Permit, ()>> {
+        self.promise.as_mut().unwrap().poll()
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<Permit, ()>) + Send + 'static
+    {
+        self.promise.as_mut().unwrap().schedule(g)
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<Permit, ()>>) {
+        self.promise.as_mut().unwrap().schedule_boxed(cb)
+    }
+}
+
+impl Drop for Acquire {
+    fn drop(&mut self) {
+        // Dropping the promise marks us as canceled, after which anyone stuck
+        // behind us may be able to go.
+        drop(self.promise.take());
+        Inner::wake(&self.inner);
+    }
+}
+
+impl Permit {
+    // Returns how many permits this holds.
+    pub fn amount(&self) -> usize {
+        self.amt
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.inner.state.lock().unwrap().available += self.amt;
+        Inner::wake(&self.inner);
+    }
+}
@@ -0,0 +1,118 @@
+// Task-local storage.
+//
+// Callbacks run on whichever thread completes the future they're waiting on,
+// so thread-locals can't be used to carry context like request IDs through a
+// chain of futures. Task-locals are instead captured along with the current
+// executor whenever a future is scheduled, and restored around every callback
+// delivered through that executor, even if it runs on another thread.
+//
+// Values are looked up by type, so each piece of context generally wants a
+// type of its own.
+
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::mem;
+use std::sync::Arc;
+
+thread_local!(static LOCALS: RefCell<Locals> = RefCell::new(Locals::empty()));
+
+// A snapshot of the task-local values in place at some point, which can be
+// restored later on, possibly on another thread.
+//
+// Executors and futures in this crate take care of this already, but custom
+// futures which run callbacks themselves can use this to do the same.
+#[derive(Clone)]
+pub struct Locals {
+    head: Option<Arc<Node>>,
+}
+
+// The values form a list with the most recently set first, so that taking a
+// snapshot is just a matter of cloning the head.
+struct Node {
+    id: TypeId,
+    value: Arc<Any + Send + Sync>,
+    next: Option<Arc<Node>>,
+}
+
+// Runs `f` with `value` set as the task-local of type `T`, hiding any value
+// of that type which was already set.
+//
+// Futures scheduled by `f` carry the value along to their callbacks.
+pub fn scope<T, F, R>(value: T, f: F) -> R
+    where T: Send + Sync + 'static,
+          F: FnOnce() -> R,
+{
+    Locals::current().push(Arc::new(value)).enter(f)
+}
+
+// Calls `f` with the task-local of type `T`, if one is set.
+pub fn with<T, F, R>(f: F) -> R
+    where T: 'static,
+          F: FnOnce(Option<&T>) -> R,
+{
+    // `f` may well set task-locals of its own, so it can't be called while
+    // they're borrowed.
+    let locals = Locals::current();
+    f(locals.find())
+}
+
+// Returns a copy of the task-local of type `T`, if one is set.
+pub fn get<T: Clone + 'static>() -> Option<T> {
+    with(|t: Option<&T>| t.cloned())
+}
+
+impl Locals {
+    fn empty() -> Locals {
+        Locals { head: None }
+    }
+
+    // Takes a snapshot of the task-locals set on this thread.
+    pub fn current() -> Locals {
+        LOCALS.with(|l| l.borrow().clone())
+    }
+
+    // Runs `f` with these task-locals in place of the current ones, restoring
+    // the previous task-locals afterwards.
+    pub fn enter<F, R>(&self, f: F) -> R
+        where F: FnOnce() -> R
+    {
+        struct Reset(Locals);
+
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                let prev = mem::replace(&mut self.0, Locals::empty());
+                LOCALS.with(|l| *l.borrow_mut() = prev);
+            }
+        }
+
+        let prev = LOCALS.with(|l| {
+            mem::replace(&mut *l.borrow_mut(), self.clone())
+        });
+        let _reset = Reset(prev);
+        f()
+    }
+
+    // Returns a snapshot with `value` set on top of these task-locals.
+    pub fn push<T>(&self, value: Arc<T>) -> Locals
+        where T: Send + Sync + 'static,
+    {
+        Locals {
+            head: Some(Arc::new(Node {
+                id: TypeId::of::<T>(),
+                value: value,
+                next: self.head.clone(),
+            })),
+        }
+    }
+
+    fn find<T: 'static>(&self) -> Option<&T> {
+        let mut cur = self.head.as_ref();
+        while let Some(node) = cur {
+            if node.id == TypeId::of::<T>() {
+                return node.value.downcast_ref()
+            }
+            cur = node.next.as_ref();
+        }
+        None
+    }
+}
@@ -24,21 +24,33 @@ impl<A, B, F> Future for Then<A, B, F>
     type Item = B::Item;
     type Error = B::Error;
 
+    fn poll(&mut self) -> Option<PollResult<B::Item, B::Error>> {
+        self.state.poll(then)
+    }
+
     fn schedule<G>(&mut self, g: G)
         where G: FnOnce(PollResult<B::Item, B::Error>) + Send + 'static
     {
-        self.state.schedule(g, |a, f| {
-            let ret = match a {
-                Ok(e) => util::recover(|| f(Ok(e))),
-                Err(PollError::Other(e)) => util::recover(|| f(Err(e))),
-                Err(PollError::Panicked(e)) => Err(PollError::Panicked(e)),
-                Err(PollError::Canceled) => Err(PollError::Canceled),
-            };
-            ret.map(|b| Err(b.into_future()))
-        })
+        self.state.schedule(g, then)
     }
 
     fn schedule_boxed(&mut self, cb: Box<Callback<B::Item, B::Error>>) {
         self.schedule(|r| cb.call(r))
     }
 }
+
+fn then<T, E, B, F>(a: PollResult<T, E>, f: F)
+                    -> PollResult<Result<B::Item, B::Future>, B::Error>
+    where F: FnOnce(Result<T, E>) -> B + Send + 'static,
+          B: IntoFuture,
+          T: Send + 'static,
+          E: Send + 'static,
+{
+    let ret = match a {
+        Ok(e) => util::recover(|| f(Ok(e))),
+        Err(PollError::Other(e)) => util::recover(|| f(Err(e))),
+        Err(PollError::Panicked(e)) => Err(PollError::Panicked(e)),
+        Err(PollError::Canceled) => Err(PollError::Canceled),
+    };
+    ret.map(|b| Err(b.into_future()))
+}
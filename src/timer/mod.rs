@@ -0,0 +1,245 @@
+use std::error::Error;
+use std::fmt;
+use std::ptr;
+use std::sync::{Arc, Mutex, Once};
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use {Future, Callback, PollResult, Promise, Complete, promise};
+use stream::{Stream, StreamResult};
+
+mod wheel;
+pub use self::wheel::Wheel;
+
+/// A handle to a background thread which drives a timer wheel.
+///
+/// The thread exits once all handles to it have been dropped and every delay
+/// created from it has fired.
+#[derive(Clone)]
+pub struct Timer {
+    tx: Arc<Mutex<mpsc::Sender<Message>>>,
+}
+
+enum Message {
+    Add(Instant, Complete<(), ()>),
+}
+
+/// A future which completes once a point in time has been reached.
+pub struct Delay {
+    inner: Promise<(), ()>,
+}
+
+/// A stream which yields `()` once per period.
+pub struct Interval {
+    timer: Timer,
+    next: Instant,
+    period: Duration,
+    pending: Option<Delay>,
+}
+
+/// A future which resolves to the value of another future, or to an error if
+/// that future takes too long.
+pub struct Timeout<F> where F: Future {
+    inner: Box<Future<Item=F::Item, Error=TimeoutError<F::Error>>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TimeoutError<E> {
+    TimedOut,
+    Other(E),
+}
+
+/// Returns a future which completes after `dur` using the global timer.
+pub fn delay(dur: Duration) -> Delay {
+    global().delay(dur)
+}
+
+/// Returns a stream which fires every `period` using the global timer.
+pub fn interval(period: Duration) -> Interval {
+    global().interval(period)
+}
+
+/// Returns a future which resolves to the value of `f` unless `dur` elapses
+/// first, using the global timer.
+pub fn timeout<F: Future>(f: F, dur: Duration) -> Timeout<F> {
+    global().timeout(f, dur)
+}
+
+fn global() -> Timer {
+    static INIT: Once = Once::new();
+    static TIMER: AtomicPtr<Timer> = AtomicPtr::new(ptr::null_mut());
+
+    INIT.call_once(|| {
+        let timer = Box::into_raw(Box::new(Timer::new()));
+        TIMER.store(timer, Ordering::Release);
+    });
+    // The timer is leaked, so once it's been stored it lives forever
+    unsafe { (*TIMER.load(Ordering::Acquire)).clone() }
+}
+
+impl Timer {
+    pub fn new() -> Timer {
+        let (tx, rx) = mpsc::channel();
+        thread::Builder::new().name("futures-timer".to_string()).spawn(|| {
+            run(rx)
+        }).expect("failed to spawn timer thread");
+        Timer { tx: Arc::new(Mutex::new(tx)) }
+    }
+
+    pub fn delay(&self, dur: Duration) -> Delay {
+        self.delay_until(Instant::now() + dur)
+    }
+
+    pub fn delay_until(&self, at: Instant) -> Delay {
+        let (p, c) = promise();
+        // If the timer thread is gone then `c` is dropped here and the delay
+        // resolves as canceled.
+        drop(self.tx.lock().unwrap().send(Message::Add(at, c)));
+        Delay { inner: p }
+    }
+
+    pub fn interval(&self, period: Duration) -> Interval {
+        Interval {
+            timer: self.clone(),
+            next: Instant::now() + period,
+            period: period,
+            pending: None,
+        }
+    }
+
+    pub fn timeout<F: Future>(&self, f: F, dur: Duration) -> Timeout<F> {
+        let f = f.map_err(TimeoutError::Other);
+        let delay = self.delay(dur).then(|_| Err(TimeoutError::TimedOut));
+        Timeout {
+            inner: f.select(delay).then(|res| {
+                // dropping the other half cancels it
+                match res {
+                    Ok((t, _next)) => Ok(t),
+                    Err((e, _next)) => Err(e),
+                }
+            }).boxed(),
+        }
+    }
+}
+
+impl Default for Timer {
+    fn default() -> Timer {
+        Timer::new()
+    }
+}
+
+fn run(rx: mpsc::Receiver<Message>) {
+    let mut wheel = Wheel::<Complete<(), ()>>::new();
+    // Once every handle is gone nothing new can be added, but the delays
+    // already in the wheel still have to fire before we can exit.
+    let mut connected = true;
+    loop {
+        let now = Instant::now();
+        while let Some(c) = wheel.poll(now) {
+            c.finish(());
+        }
+        let msg = match wheel.next_timeout() {
+            Some(at) => {
+                let dur = if at > now {at - now} else {Duration::new(0, 0)};
+                if !connected {
+                    thread::sleep(dur);
+                    continue
+                }
+                match rx.recv_timeout(dur) {
+                    Ok(msg) => msg,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    Err(RecvTimeoutError::Disconnected) => {
+                        connected = false;
+                        continue
+                    }
+                }
+            }
+            None if !connected => break,
+            None => {
+                match rx.recv() {
+                    Ok(msg) => msg,
+                    Err(..) => break,
+                }
+            }
+        };
+        match msg {
+            Message::Add(at, c) => wheel.insert(at, c),
+        }
+    }
+}
+
+impl Future for Delay {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Option<PollResult<(), ()>> {
+        self.inner.poll()
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<(), ()>) + Send + 'static
+    {
+        self.inner.schedule(g)
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<(), ()>>) {
+        self.inner.schedule_boxed(cb)
+    }
+}
+
+impl Stream for Interval {
+    type Item = ();
+    type Error = ();
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(StreamResult<(), ()>) + Send + 'static
+    {
+        let mut delay = self.timer.delay_until(self.next);
+        self.next += self.period;
+        delay.schedule(move |r| g(r.map(Some)));
+        self.pending = Some(delay);
+    }
+
+    fn schedule_boxed(&mut self, g: Box<Callback<Option<()>, ()>>) {
+        self.schedule(|r| g.call(r))
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for TimeoutError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TimeoutError::TimedOut => f.write_str("future timed out"),
+            TimeoutError::Other(ref e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E: Error + 'static> Error for TimeoutError<E> {
+    fn source(&self) -> Option<&(Error + 'static)> {
+        match *self {
+            TimeoutError::Other(ref e) => Some(e),
+            TimeoutError::TimedOut => None,
+        }
+    }
+}
+
+impl<F: Future> Future for Timeout<F> {
+    type Item = F::Item;
+    type Error = TimeoutError<F::Error>;
+
+    fn poll(&mut self) -> Option<PollResult<Self::Item, Self::Error>> {
+        self.inner.poll()
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<Self::Item, Self::Error>) + Send + 'static
+    {
+        self.inner.schedule(g)
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<Self::Item, Self::Error>>) {
+        self.inner.schedule_boxed(cb)
+    }
+}
@@ -0,0 +1,194 @@
+use std::time::{Duration, Instant};
+
+const NUM_SLOTS: usize = 256;
+
+/// A hashed timer wheel.
+///
+/// Each entry is placed into one of a fixed number of slots based on the tick
+/// at which it expires, and entries are handed back out of `poll` once the
+/// wheel has been advanced past that tick. Time is measured in ticks of a
+/// fixed duration (one millisecond by default), and entries never fire early
+/// but may fire up to one tick late.
+pub struct Wheel<T> {
+    start: Instant,
+    tick: Duration,
+    // all entries with a tick less than `cur` have been returned from `poll`
+    cur: u64,
+    slots: Vec<Vec<(u64, T)>>,
+    len: usize,
+}
+
+impl<T> Wheel<T> {
+    pub fn new() -> Wheel<T> {
+        Wheel::with_tick(Duration::from_millis(1))
+    }
+
+    pub fn with_tick(tick: Duration) -> Wheel<T> {
+        assert!(tick > Duration::new(0, 0), "tick duration must be nonzero");
+        Wheel {
+            start: Instant::now(),
+            tick: tick,
+            cur: 0,
+            slots: (0..NUM_SLOTS).map(|_| Vec::new()).collect(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Inserts `t` into this wheel, to be returned from `poll` at some point
+    /// after `at`.
+    pub fn insert(&mut self, at: Instant, t: T) {
+        let tick = self.tick_after(at);
+        let tick = if tick < self.cur {self.cur} else {tick};
+        self.slots[(tick % NUM_SLOTS as u64) as usize].push((tick, t));
+        self.len += 1;
+    }
+
+    /// Returns one entry which has expired as of `now`, if any.
+    pub fn poll(&mut self, now: Instant) -> Option<T> {
+        let now = self.tick_before(now);
+        if self.len == 0 {
+            if self.cur <= now {
+                self.cur = now + 1;
+            }
+            return None
+        }
+        while self.cur <= now {
+            let cur = self.cur;
+            let slot = &mut self.slots[(cur % NUM_SLOTS as u64) as usize];
+            if let Some(i) = slot.iter().position(|&(tick, _)| tick <= cur) {
+                self.len -= 1;
+                return Some(slot.swap_remove(i).1)
+            }
+            self.cur += 1;
+        }
+        None
+    }
+
+    /// Returns the earliest instant at which an entry in this wheel will be
+    /// ready to be returned from `poll`.
+    pub fn next_timeout(&self) -> Option<Instant> {
+        self.slots.iter()
+            .flat_map(|slot| slot.iter().map(|&(tick, _)| tick))
+            .min()
+            .map(|tick| self.start + mul(self.tick, tick))
+    }
+
+    /// Removes all entries from this wheel, whether they've expired or not.
+    pub fn drain(&mut self) -> Vec<T> {
+        self.len = 0;
+        self.slots.iter_mut()
+            .flat_map(|slot| slot.drain(..))
+            .map(|(_, t)| t)
+            .collect()
+    }
+
+    // Number of whole ticks needed to reach `at`, rounded up so we never fire
+    // early.
+    fn tick_after(&self, at: Instant) -> u64 {
+        if at <= self.start {
+            return 0
+        }
+        let elapsed = nanos(at - self.start);
+        let tick = nanos(self.tick);
+        elapsed.div_ceil(tick)
+    }
+
+    // Number of whole ticks that have elapsed by `at`.
+    fn tick_before(&self, at: Instant) -> u64 {
+        if at <= self.start {
+            return 0
+        }
+        nanos(at - self.start) / nanos(self.tick)
+    }
+}
+
+impl<T> Default for Wheel<T> {
+    fn default() -> Wheel<T> {
+        Wheel::new()
+    }
+}
+
+fn nanos(d: Duration) -> u64 {
+    d.as_secs() * 1_000_000_000 + d.subsec_nanos() as u64
+}
+
+fn mul(d: Duration, n: u64) -> Duration {
+    let nanos = nanos(d) * n;
+    Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::Wheel;
+
+    #[test]
+    fn smoke() {
+        let mut wheel = Wheel::new();
+        let start = Instant::now();
+        assert!(wheel.poll(start).is_none());
+        assert!(wheel.next_timeout().is_none());
+
+        wheel.insert(start + Duration::from_millis(10), 1);
+        wheel.insert(start + Duration::from_millis(5), 2);
+        wheel.insert(start + Duration::from_millis(1000), 3);
+        assert_eq!(wheel.len(), 3);
+        assert!(wheel.next_timeout().unwrap() >=
+                    start + Duration::from_millis(5));
+
+        assert!(wheel.poll(start).is_none());
+        assert_eq!(wheel.poll(start + Duration::from_millis(7)), Some(2));
+        assert!(wheel.poll(start + Duration::from_millis(7)).is_none());
+        assert_eq!(wheel.poll(start + Duration::from_millis(20)), Some(1));
+        assert!(wheel.poll(start + Duration::from_millis(20)).is_none());
+        assert_eq!(wheel.len(), 1);
+
+        // far beyond one revolution of the wheel
+        assert_eq!(wheel.poll(start + Duration::from_millis(2000)), Some(3));
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn past_deadlines() {
+        let mut wheel = Wheel::new();
+        let start = Instant::now();
+        assert!(wheel.poll(start + Duration::from_millis(100)).is_none());
+
+        // Inserting something that already expired shows up on the next tick
+        wheel.insert(start, 1);
+        assert_eq!(wheel.poll(start + Duration::from_millis(102)), Some(1));
+    }
+
+    #[test]
+    fn same_slot() {
+        let mut wheel = Wheel::new();
+        let start = Instant::now();
+        wheel.insert(start + Duration::from_millis(1), 1);
+        wheel.insert(start + Duration::from_millis(257), 2);
+        assert_eq!(wheel.poll(start + Duration::from_millis(100)), Some(1));
+        assert!(wheel.poll(start + Duration::from_millis(100)).is_none());
+        assert_eq!(wheel.poll(start + Duration::from_millis(300)), Some(2));
+    }
+
+    #[test]
+    fn drain() {
+        let mut wheel = Wheel::new();
+        let start = Instant::now();
+        wheel.insert(start + Duration::from_millis(1), 1);
+        wheel.insert(start + Duration::from_millis(2), 2);
+        let mut v = wheel.drain();
+        v.sort();
+        assert_eq!(v, vec![1, 2]);
+        assert!(wheel.is_empty());
+        assert!(wheel.next_timeout().is_none());
+    }
+}
@@ -1,9 +1,6 @@
 use std::panic::{self, AssertUnwindSafe};
 
-use {PollResult, PollError};
-
-// TODO: reexport this?
-struct ReuseFuture;
+use {PollResult, PollError, Reused};
 
 pub fn recover<F, R, E>(f: F) -> PollResult<R, E>
     where F: FnOnce() -> R + Send + 'static
@@ -12,7 +9,7 @@ pub fn recover<F, R, E>(f: F) -> PollResult<R, E>
 }
 
 pub fn reused<E>() -> PollError<E> {
-    PollError::Panicked(Box::new(ReuseFuture))
+    PollError::Panicked(Box::new(Reused))
 }
 
 pub fn opt2poll<T, E>(t: Option<T>) -> PollResult<T, E> {
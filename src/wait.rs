@@ -0,0 +1,27 @@
+use std::sync::Arc;
+use std::thread;
+
+use {Future, PollError, WaitError};
+use slot::Slot;
+
+pub fn wait<F: Future>(mut f: F) -> Result<F::Item, WaitError<F::Error>> {
+    let result = match f.poll() {
+        Some(result) => result,
+        None => {
+            let slot = Arc::new(Slot::new(None));
+            let slot2 = slot.clone();
+            let me = thread::current();
+            f.schedule(move |r| {
+                slot2.try_produce(r).ok().expect("wait slot already full");
+                me.unpark();
+            });
+            loop {
+                match slot.try_consume() {
+                    Ok(result) => break result,
+                    Err(..) => thread::park(),
+                }
+            }
+        }
+    };
+    result.or_else(PollError::into_result)
+}
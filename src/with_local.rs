@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use {Future, PollResult, Callback};
+use executor::{self, Executor};
+use task::Locals;
+
+pub struct WithLocal<A, T> {
+    future: A,
+    value: Arc<T>,
+}
+
+pub fn new<A, T>(future: A, value: T) -> WithLocal<A, T> {
+    WithLocal {
+        future: future,
+        value: Arc::new(value),
+    }
+}
+
+impl<A, T> Future for WithLocal<A, T>
+    where A: Future,
+          T: Send + Sync + 'static,
+{
+    type Item = A::Item;
+    type Error = A::Error;
+
+    fn poll(&mut self) -> Option<PollResult<A::Item, A::Error>> {
+        let future = &mut self.future;
+        Locals::current().push(self.value.clone()).enter(|| future.poll())
+    }
+
+    fn schedule<G>(&mut self, g: G)
+        where G: FnOnce(PollResult<A::Item, A::Error>) + Send + 'static
+    {
+        // The value is only visible to the future itself, so its result is
+        // handed back with whatever task-locals we were scheduled with.
+        let executor = executor::current();
+        let future = &mut self.future;
+        Locals::current().push(self.value.clone()).enter(|| {
+            future.schedule(move |result| executor.execute(move || g(result)))
+        })
+    }
+
+    fn schedule_boxed(&mut self, cb: Box<Callback<A::Item, A::Error>>) {
+        self.schedule(|r| cb.call(r))
+    }
+}
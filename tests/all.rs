@@ -26,9 +26,9 @@ fn assert_done<T, F>(mut f: F, result: Result<T::Item, T::Error>)
           T::Error: Eq + fmt::Debug,
           F: FnMut() -> T,
 {
-    // let mut a = f();
-    // assert_eq!(&unwrap(a.poll().expect("future not ready")), &result);
-    // assert_bad(a.poll().expect("future should still be ready"));
+    let mut a = f();
+    assert_eq!(&unwrap(a.poll().expect("future not ready")), &result);
+    assert_bad(a.poll().expect("future should still be ready"));
 
     let (tx, rx) = channel();
     f().schedule(move |r| tx.send(r).unwrap());
@@ -42,14 +42,18 @@ fn assert_done<T, F>(mut f: F, result: Result<T::Item, T::Error>)
 }
 
 fn assert_empty<T: Future, F: FnMut() -> T>(mut f: F) {
-    // let mut a = f();
-    // assert!(a.poll().is_none());
-    // a.cancel();
-    // assert_cancel(a.poll().expect("cancel should force a finish"));
+    let mut a = f();
+    assert!(a.poll().is_none());
+    assert!(a.poll().is_none());
+    drop(a);
 
-    // let mut a = f();
-    // a.cancel();
-    // assert_cancel(a.poll().expect("cancel should force a finish2"));
+    let (tx, rx) = channel();
+    let mut a = f();
+    assert!(a.poll().is_none());
+    a.schedule(move |r| tx.send(r).unwrap());
+    assert_bad(a.poll().expect("poll after schedule should be ready"));
+    drop(a);
+    assert_cancel(rx.recv().unwrap());
 
     let (tx, rx) = channel();
     let mut a = f();
@@ -227,6 +231,131 @@ fn smoke_promise() {
     assert_panic(rx.recv().unwrap());
 }
 
+#[test]
+fn promise_cancel() {
+    let (p, c) = promise::<i32, u32>();
+    assert!(!c.is_canceled());
+    let mut f = c.on_cancel();
+    assert!(f.poll().is_none());
+    let (tx, rx) = channel();
+    f.schedule(move |r| tx.send(r).unwrap());
+    assert_eq!(rx.try_recv().err(), Some(TryRecvError::Empty));
+    drop(p);
+    assert!(c.is_canceled());
+    assert_eq!(unwrap(rx.recv().unwrap()), Ok(()));
+    assert_eq!(unwrap(c.on_cancel().poll().unwrap()), Ok(()));
+
+    // Canceling a scheduled promise counts too
+    let (mut p, c) = promise::<i32, u32>();
+    p.schedule(assert_cancel);
+    assert!(!c.is_canceled());
+    drop(p);
+    assert!(c.is_canceled());
+
+    // Taking the value doesn't
+    let (mut p, c) = promise::<i32, u32>();
+    let mut f = c.on_cancel();
+    c.finish(1);
+    assert_eq!(unwrap(p.poll().unwrap()), Ok(1));
+    drop(p);
+    assert_cancel(f.poll().unwrap());
+}
+
+#[test]
+fn abortable() {
+    assert_done(|| f_ok(1).abortable().0, ok(1));
+    assert_done(|| f_err(1).abortable().0, err(1));
+    assert_empty(|| empty::<i32, u32>().abortable().0);
+
+    let (mut f, handle) = f_ok(1).abortable();
+    handle.abort();
+    assert_cancel(f.poll().unwrap());
+
+    // Aborting drops the inner future and cancels whoever's waiting
+    let (p, c) = promise::<i32, u32>();
+    let (mut f, handle) = p.abortable();
+    let (tx, rx) = channel();
+    f.schedule(move |r| tx.send(r).unwrap());
+    assert!(!c.is_canceled());
+    std::thread::spawn(move || handle.clone().abort()).join().unwrap();
+    assert_cancel(rx.recv().unwrap());
+    assert!(c.is_canceled());
+
+    // Aborting afterwards has no effect
+    let (p, c) = promise::<i32, u32>();
+    let (mut f, handle) = p.abortable();
+    let (tx, rx) = channel();
+    f.schedule(move |r| tx.send(r).unwrap());
+    c.finish(1);
+    assert_eq!(unwrap(rx.recv().unwrap()), Ok(1));
+    handle.abort();
+}
+
+#[test]
+fn poll_error() {
+    let (mut p, _c) = promise::<i32, u32>();
+    p.schedule(|_| ());
+    let e = p.poll().unwrap().err().unwrap();
+    assert!(e.is_reused());
+    assert_eq!(e.panic_message(), None);
+    assert_eq!(format!("{:?}", e), "Panicked(Reused)");
+    assert_eq!(e.to_string(), "future polled or scheduled after being consumed");
+
+    let e = f_ok(1).map(|_| -> i32 { panic!("boom") }).poll().unwrap();
+    let e = e.err().unwrap();
+    assert!(!e.is_reused());
+    assert_eq!(e.panic_message(), Some("boom"));
+    assert_eq!(format!("{:?}", e), "Panicked(\"boom\")");
+    assert_eq!(e.to_string(), "future panicked: boom");
+
+    assert_eq!(PollError::<u32>::Canceled.to_string(), "future canceled");
+    assert_eq!(PollError::Other(3).to_string(), "3");
+    assert_eq!(format!("{:?}", PollError::Other(3)), "Other(3)");
+
+    assert_eq!(Err::<i32, _>(PollError::Other(3)).or_else(PollError::into_result),
+               Err(WaitError::Other(3)));
+    assert_eq!(Err::<i32, _>(PollError::<u32>::Canceled)
+                   .or_else(PollError::into_result),
+               Err(WaitError::Canceled));
+    let res = std::panic::catch_unwind(|| {
+        PollError::<u32>::Panicked(Box::new("boom")).into_result::<i32>()
+    });
+    assert_eq!(res.err().unwrap().downcast_ref::<&str>(), Some(&"boom"));
+}
+
+#[test]
+fn from_err() {
+    assert_eq!(f_ok(1).from_err::<u64>().wait(), Ok(1));
+    assert_eq!(f_err(1).from_err::<u64>().wait(), Err(WaitError::Other(1)));
+
+    // Different error types can be chained through a `BoxFuture`
+    let a: BoxFuture<i32> = finished::<i32, String>(1).from_err().boxed();
+    let b = a.and_then(|_| failed::<i32, fmt::Error>(fmt::Error).from_err());
+    match b.wait() {
+        Err(WaitError::Other(e)) => assert_eq!(e.to_string(),
+                                               fmt::Error.to_string()),
+        _ => panic!("expected an error"),
+    }
+}
+
+#[test]
+fn err_into() {
+    // Only `Into` is implemented, so `from_err` couldn't do this
+    struct Code(u32);
+    #[allow(clippy::from_over_into)]
+    impl Into<u64> for Code {
+        fn into(self) -> u64 {
+            self.0 as u64
+        }
+    }
+
+    assert_eq!(f_ok(1).err_into::<u64>().wait(), Ok(1));
+    let f = failed::<i32, Code>(Code(2)).err_into::<u64>();
+    assert_eq!(f.wait(), Err(WaitError::Other(2)));
+    let a: BoxFuture<i32> = finished::<i32, String>(1).err_into().boxed();
+    assert_eq!(a.wait().ok(), Some(1));
+}
+
 #[test]
 fn select_cancels() {
     let ((a, b), (c, d)) = (promise::<i32, u32>(), promise::<i32, u32>());
@@ -235,12 +364,12 @@ fn select_cancels() {
     let c = c.map(move |c| { ctx.send(c).unwrap(); c });
 
     let mut f = a.select(c).then(unselect);
-    // assert!(f.poll().is_none());
+    assert!(f.poll().is_none());
     assert!(arx.try_recv().is_err());
     assert!(crx.try_recv().is_err());
     b.finish(1);
     f.schedule(|_| ());
-    // assert!(f.poll().is_some());
+    assert!(f.poll().is_some());
     assert_eq!(arx.recv().unwrap(), 1);
     drop((d, f));
     assert!(crx.recv().is_err());
@@ -253,7 +382,7 @@ fn select_cancels() {
     let mut f = a.select(c).then(unselect);
     f.schedule(|_| ());
     f.schedule(assert_panic);
-    // assert_panic(f.poll().unwrap());
+    assert_panic(f.poll().unwrap());
     b.finish(1);
     drop((d, f));
     assert!(crx.recv().is_err());
@@ -269,7 +398,7 @@ fn join_cancels() {
     let mut f = a.join(c);
     b.fail(1);
     f.schedule(|_| ());
-    // assert!(f.poll().is_some());
+    assert!(f.poll().is_some());
     drop((d, f));
     assert!(crx.recv().is_err());
 
@@ -290,7 +419,7 @@ fn join_cancels() {
 fn join_incomplete() {
     let (a, b) = promise::<i32, u32>();
     let mut f = f_ok(1).join(a);
-    // assert!(f.poll().is_none());
+    assert!(f.poll().is_none());
     let (tx, rx) = channel();
     f.schedule(move |r| tx.send(r).unwrap());
     assert!(rx.try_recv().is_err());
@@ -299,7 +428,7 @@ fn join_incomplete() {
 
     let (a, b) = promise::<i32, u32>();
     let mut f = a.join(f_ok(2));
-    // assert!(f.poll().is_none());
+    assert!(f.poll().is_none());
     let (tx, rx) = channel();
     f.schedule(move |r| tx.send(r).unwrap());
     assert!(rx.try_recv().is_err());
@@ -308,7 +437,7 @@ fn join_incomplete() {
 
     let (a, b) = promise::<i32, u32>();
     let mut f = f_ok(1).join(a);
-    // assert!(f.poll().is_none());
+    assert!(f.poll().is_none());
     let (tx, rx) = channel();
     f.schedule(move |r| tx.send(r).unwrap());
     assert!(rx.try_recv().is_err());
@@ -317,7 +446,7 @@ fn join_incomplete() {
 
     let (a, b) = promise::<i32, u32>();
     let mut f = a.join(f_ok(2));
-    // assert!(f.poll().is_none());
+    assert!(f.poll().is_none());
     let (tx, rx) = channel();
     f.schedule(move |r| tx.send(r).unwrap());
     assert!(rx.try_recv().is_err());
@@ -348,6 +477,68 @@ fn collect_collects() {
     // TODO: needs more tests
 }
 
+#[test]
+fn collect_concurrent_collects() {
+    assert_done(|| collect_concurrent(vec![f_ok(1), f_ok(2)], 1), Ok(vec![1, 2]));
+    assert_done(|| collect_concurrent(vec![f_ok(1), f_ok(2)], 5), Ok(vec![1, 2]));
+    assert_done(|| collect_concurrent(Vec::<Result<i32, u32>>::new(), 2),
+                Ok(vec![]));
+    assert_done(|| collect_concurrent(vec![f_ok(1), f_err(2)], 2), Err(2));
+    assert_empty(|| {
+        collect_concurrent(vec![f_ok(1).boxed(), empty().boxed()], 2)
+    });
+
+    // only `limit` futures are created at once, results are in order
+    let (a, b) = promise::<i32, u32>();
+    let (c, d) = promise::<i32, u32>();
+    let (e, g) = promise::<i32, u32>();
+    let (tx, rx) = channel();
+    let futures = vec![a, c, e].into_iter().enumerate().map(move |(i, p)| {
+        tx.send(i).unwrap();
+        p
+    });
+    let mut f = collect_concurrent(futures, 2);
+    let (tx2, rx2) = channel();
+    f.schedule(move |r| tx2.send(r).unwrap());
+    assert_eq!(rx.try_recv(), Ok(0));
+    assert_eq!(rx.try_recv(), Ok(1));
+    assert!(rx.try_recv().is_err());
+    d.finish(2);
+    assert_eq!(rx.try_recv(), Ok(2));
+    g.finish(3);
+    assert!(rx2.try_recv().is_err());
+    b.finish(1);
+    assert_eq!(unwrap(rx2.recv().unwrap()), Ok(vec![1, 2, 3]));
+}
+
+#[test]
+fn collect_concurrent_cancels() {
+    let ((a, b), (c, d)) = (promise::<i32, u32>(), promise::<i32, u32>());
+    let (ctx, crx) = channel();
+    let c = c.map(move |c| { ctx.send(c).unwrap(); c });
+
+    let mut f = collect_concurrent(vec![a.boxed(), c.boxed()], 2);
+    let (tx, rx) = channel();
+    f.schedule(move |r| tx.send(r).unwrap());
+    b.fail(1);
+    assert_eq!(unwrap(rx.recv().unwrap()), Err(1));
+    d.finish(2);
+    assert!(crx.recv().is_err());
+    drop(f);
+
+    let ((a, _b), (c, d)) = (promise::<i32, u32>(), promise::<i32, u32>());
+    let (ctx, crx) = channel();
+    let c = c.map(move |c| { ctx.send(c).unwrap(); c });
+
+    let mut f = collect_concurrent(vec![a.boxed(), c.boxed()], 2);
+    let (tx, rx) = channel();
+    f.schedule(move |r| tx.send(r).unwrap());
+    drop(f);
+    assert_cancel(rx.recv().unwrap());
+    d.finish(1);
+    assert!(crx.recv().is_err());
+}
+
 #[test]
 fn select2() {
     fn d<T, U, E>(r: Result<(T, U), (E, U)>) -> Result<T, E> {
@@ -386,6 +577,23 @@ fn select2() {
             Err(PollError::Other(2)) => {}
             _ => panic!("wrong error"),
         }
+        assert_panic(next.poll().unwrap());
+    }
+
+    // Taking the second half's result with `poll` means there's nothing left
+    // for `schedule`
+    {
+        let ((a, b), (c, d)) = (promise::<i32, u32>(), promise::<i32, u32>());
+        let mut f = a.select(c);
+        let (tx, rx) = channel();
+        f.schedule(move |r| tx.send(r).unwrap());
+        b.finish(1);
+        let (_, mut next) = rx.recv().unwrap().ok().unwrap();
+        d.finish(2);
+        assert_eq!(unwrap(next.poll().unwrap()), Ok(2));
+        let (tx, rx) = channel();
+        next.schedule(move |r| tx.send(r).unwrap());
+        assert_panic(rx.recv().unwrap());
     }
 
     // Fail the second half and ensure that we see the first one finish
@@ -458,3 +666,259 @@ fn select2() {
         assert!(rx.recv().is_err());
     }
 }
+
+#[test]
+fn poll_promise() {
+    let (mut p, c) = promise::<i32, u32>();
+    assert!(p.poll().is_none());
+    assert!(p.poll().is_none());
+    c.finish(1);
+    assert_eq!(unwrap(p.poll().expect("promise should be ready")), Ok(1));
+    assert_bad(p.poll().expect("promise should still be ready"));
+
+    let (p, c) = promise::<i32, u32>();
+    let mut f = p.map(|a| a + 1).join(f_ok(2));
+    assert!(f.poll().is_none());
+    c.finish(1);
+    assert_eq!(unwrap(f.poll().expect("join should be ready")), Ok((2, 2)));
+
+    let (p, c) = promise::<i32, u32>();
+    let mut f = p.and_then(|a| Ok(a + 1));
+    assert!(f.poll().is_none());
+    let (tx, rx) = channel();
+    f.schedule(move |r| tx.send(r).unwrap());
+    c.finish(1);
+    assert_eq!(unwrap(rx.recv().unwrap()), Ok(2));
+}
+
+#[test]
+fn join_all_joins() {
+    assert_done(|| join_all(vec![f_ok(1), f_ok(2)]), Ok(vec![1, 2]));
+    assert_done(|| join_all(vec![f_ok(1)]), Ok(vec![1]));
+    assert_done(|| join_all(Vec::<Result<i32, u32>>::new()), Ok(vec![]));
+    assert_done(|| join_all(vec![f_ok(1), f_err(2)]), Err(2));
+    assert_empty(|| join_all(vec![f_ok(1).boxed(), empty().boxed()]));
+
+    // values come back in input order regardless of completion order
+    let ((a, b), (c, d)) = (promise::<i32, u32>(), promise::<i32, u32>());
+    let mut f = join_all(vec![a, c]);
+    let (tx, rx) = channel();
+    f.schedule(move |r| tx.send(r).unwrap());
+    d.finish(2);
+    assert!(rx.try_recv().is_err());
+    b.finish(1);
+    assert_eq!(unwrap(rx.recv().unwrap()), Ok(vec![1, 2]));
+}
+
+#[test]
+fn join_all_cancels() {
+    let ((a, b), (c, d)) = (promise::<i32, u32>(), promise::<i32, u32>());
+    let (ctx, crx) = channel();
+    let c = c.map(move |c| { ctx.send(c).unwrap(); c });
+
+    let mut f = join_all(vec![a.boxed(), c.boxed()]);
+    let (tx, rx) = channel();
+    f.schedule(move |r| tx.send(r).unwrap());
+    b.fail(1);
+    assert_eq!(unwrap(rx.recv().unwrap()), Err(1));
+    drop(d);
+    assert!(crx.recv().is_err());
+    drop(f);
+
+    let ((a, _b), (c, d)) = (promise::<i32, u32>(), promise::<i32, u32>());
+    let (ctx, crx) = channel();
+    let c = c.map(move |c| { ctx.send(c).unwrap(); c });
+
+    let mut f = join_all(vec![a.boxed(), c.boxed()]);
+    let (tx, rx) = channel();
+    f.schedule(move |r| tx.send(r).unwrap());
+    drop(f);
+    assert_cancel(rx.recv().unwrap());
+    d.finish(1);
+    assert!(crx.recv().is_err());
+}
+
+#[test]
+fn select_all_selects() {
+    fn first<T, U, E>(r: Result<(T, usize, U), (E, usize, U)>)
+                      -> Result<(T, usize), (E, usize)> {
+        match r {
+            Ok((t, i, _rest)) => Ok((t, i)),
+            Err((e, i, _rest)) => Err((e, i)),
+        }
+    }
+
+    assert_done(|| select_all(vec![f_ok(1), f_ok(2)]).then(first), Ok((1, 0)));
+    assert_done(|| select_all(vec![empty().boxed(), f_err(2).boxed()]).then(first),
+                Err((2, 1)));
+    assert_empty(|| select_all(vec![empty::<i32, u32>(), empty()]));
+
+    let ((a, b), (c, d), (e, g)) = (promise::<i32, u32>(),
+                                    promise::<i32, u32>(),
+                                    promise::<i32, u32>());
+    let mut f = select_all(vec![a, c, e]);
+    let (tx, rx) = channel();
+    f.schedule(move |r| tx.send(r).unwrap());
+    d.finish(2);
+    let (val, i, mut rest) = unwrap(rx.recv().unwrap()).ok().unwrap();
+    assert_eq!((val, i, rest.len()), (2, 1, 2));
+
+    // the rest can be waited on again
+    let mut last = rest.pop().unwrap();
+    g.finish(3);
+    assert_eq!(unwrap(last.poll().unwrap()), Ok(3));
+
+    // ... but only once
+    assert_panic(last.poll().unwrap());
+    let (tx, rx) = channel();
+    last.schedule(move |r| tx.send(r).unwrap());
+    assert_panic(rx.recv().unwrap());
+    let (tx, rx) = channel();
+    rest[0].schedule(move |r| tx.send(r).unwrap());
+    b.fail(1);
+    assert_eq!(unwrap(rx.recv().unwrap()), Err(1));
+}
+
+#[test]
+fn select_all_cancels() {
+    let ((a, b), (c, d)) = (promise::<i32, u32>(), promise::<i32, u32>());
+    let (ctx, crx) = channel();
+    let c = c.map(move |c| { ctx.send(c).unwrap(); c });
+
+    let mut f = select_all(vec![a.boxed(), c.boxed()]);
+    let (tx, rx) = channel();
+    f.schedule(move |r| tx.send(r).unwrap());
+    b.finish(1);
+    let (_, _, rest) = unwrap(rx.recv().unwrap()).ok().unwrap();
+    drop((f, rest));
+    d.finish(2);
+    assert!(crx.recv().is_err());
+
+    let ((a, b), (c, d)) = (promise::<i32, u32>(), promise::<i32, u32>());
+    let ((atx, arx), (ctx, crx)) = (channel(), channel());
+    let a = a.map(move |a| { atx.send(a).unwrap(); a });
+    let c = c.map(move |c| { ctx.send(c).unwrap(); c });
+
+    let mut f = select_all(vec![a.boxed(), c.boxed()]);
+    let (tx, rx) = channel();
+    f.schedule(move |r| tx.send(r).unwrap());
+    drop(f);
+    assert_cancel(rx.recv().unwrap());
+    b.finish(1);
+    d.finish(2);
+    assert!(arx.recv().is_err());
+    assert!(crx.recv().is_err());
+}
+
+#[test]
+fn shared_smoke() {
+    assert_done(|| f_ok(1).shared(), Ok(1));
+    assert_done(|| f_err(1).shared(), Err(1));
+    assert_empty(|| empty::<i32, u32>().shared());
+
+    let (a, b) = promise::<i32, u32>();
+    let a = a.shared();
+    let (tx, rx) = channel();
+    let mut handles = (0..3).map(|_| a.clone()).collect::<Vec<_>>();
+    for h in handles.iter_mut() {
+        let tx = tx.clone();
+        h.schedule(move |r| tx.send(unwrap(r)).unwrap());
+    }
+    assert!(rx.try_recv().is_err());
+    b.finish(1);
+    for _ in 0..3 {
+        assert_eq!(rx.recv().unwrap(), Ok(1));
+    }
+
+    // handles created or polled after completion see the result as well
+    let mut late = a.clone();
+    assert_eq!(unwrap(late.poll().unwrap()), Ok(1));
+    let (tx, rx) = channel();
+    a.clone().schedule(move |r| tx.send(unwrap(r)).unwrap());
+    assert_eq!(rx.recv().unwrap(), Ok(1));
+}
+
+#[test]
+fn shared_cancels_on_last_drop() {
+    let (a, b) = promise::<i32, u32>();
+    let (atx, arx) = channel();
+    let a = a.map(move |a| { atx.send(a).unwrap(); a }).shared();
+
+    let mut a1 = a.clone();
+    let (tx, rx) = channel();
+    a1.schedule(move |r| tx.send(r).unwrap());
+    let a2 = a1.clone();
+
+    // dropping a scheduled handle cancels just that handle
+    drop(a1);
+    assert_cancel(rx.recv().unwrap());
+    drop(a);
+    assert!(arx.try_recv().is_err());
+
+    // ... and the last handle cancels the future itself
+    drop(a2);
+    b.finish(1);
+    assert!(arx.recv().is_err());
+}
+
+#[test]
+fn shared_panics() {
+    let mut a = f_ok(1).map(|_| -> i32 { panic!("boom") }).shared();
+    let mut b = a.clone();
+    let (tx, rx) = channel();
+    a.schedule(move |r| tx.send(r).unwrap());
+    match rx.recv().unwrap() {
+        Err(PollError::Panicked(p)) => {
+            assert_eq!(p.downcast_ref::<String>().unwrap(), "boom");
+        }
+        _ => panic!("expected a panic"),
+    }
+    assert_panic(b.poll().unwrap());
+}
+
+#[test]
+fn task_locals() {
+    #[derive(Clone, Debug, PartialEq)]
+    struct Id(u32);
+
+    // Values are only set within their scope, and can be hidden
+    assert_eq!(task::get::<Id>(), None);
+    task::scope(Id(1), || {
+        assert_eq!(task::get::<Id>(), Some(Id(1)));
+        task::scope(Id(2), || assert_eq!(task::get::<Id>(), Some(Id(2))));
+        assert_eq!(task::with(|id: Option<&Id>| id.map(|id| id.0)), Some(1));
+    });
+    assert_eq!(task::get::<Id>(), None);
+
+    // Callbacks see the values from when they were scheduled, not those of
+    // the thread completing them
+    let (p, c) = promise::<i32, u32>();
+    let mut f = p.map(|i| (i, task::get::<Id>()));
+    let (tx, rx) = channel();
+    task::scope(Id(3), || {
+        f.schedule(move |r| tx.send((unwrap(r), task::get::<Id>())).unwrap())
+    });
+    std::thread::spawn(move || {
+        task::scope(Id(4), || c.finish(1))
+    }).join().unwrap();
+    assert_eq!(rx.recv().unwrap(), (Ok((1, Some(Id(3)))), Some(Id(3))));
+
+    // ... including when hopping over to another executor
+    let pool = executor::ThreadPool::new(1);
+    let mut f = f_ok(1).on(pool).map(|_| task::get::<Id>());
+    let (tx, rx) = channel();
+    task::scope(Id(5), || f.schedule(move |r| tx.send(unwrap(r)).unwrap()));
+    assert_eq!(rx.recv().unwrap(), Ok(Some(Id(5))));
+
+    // `with_local` only sets the value for the future it wraps
+    let mut f = f_ok(1).map(|_| task::get::<Id>())
+                       .with_local(Id(6))
+                       .map(|id| (id, task::get::<Id>()));
+    assert_eq!(unwrap(f.poll().unwrap()), Ok((Some(Id(6)), None)));
+    let mut f = f_ok(1).map(|_| task::get::<Id>())
+                       .with_local(Id(7))
+                       .map(|id| (id, task::get::<Id>()));
+    let (tx, rx) = channel();
+    task::scope(Id(8), || f.schedule(move |r| tx.send(unwrap(r)).unwrap()));
+    assert_eq!(rx.recv().unwrap(), Ok((Some(Id(7)), Some(Id(8)))));
+}
@@ -38,6 +38,10 @@ struct FutureData<F, T> {
 impl<F: Future, T: Send + 'static> Future for FutureData<F, T> {
     type Item = F::Item;
     type Error = F::Error;
+    fn poll(&mut self) -> Option<PollResult<Self::Item, Self::Error>> {
+        self.future.poll()
+    }
+
     fn schedule<G>(&mut self, g: G)
         where G: FnOnce(PollResult<Self::Item, Self::Error>) + Send + 'static,
     {
@@ -1,5 +1,7 @@
 extern crate futures;
 
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc::channel;
 use std::thread;
 
@@ -312,3 +314,88 @@ fn select4() {
 
     t.join().unwrap();
 }
+
+#[test]
+fn wait1() {
+    assert_eq!(finished::<i32, i32>(1).wait(), Ok(1));
+    assert_eq!(failed::<i32, i32>(1).wait(), Err(WaitError::Other(1)));
+    assert_eq!(finished::<i32, i32>(1).map(|a| a + 1).wait(), Ok(2));
+}
+
+#[test]
+fn wait2() {
+    let (p, c) = promise::<i32, i32>();
+    let t = thread::spawn(|| c.finish(1));
+    assert_eq!(p.map(|a| a + 1).wait(), Ok(2));
+    t.join().unwrap();
+
+    let (p1, c1) = promise::<i32, i32>();
+    let (p2, c2) = promise::<i32, i32>();
+    let t = thread::spawn(|| {
+        c2.finish(2);
+        c1.finish(1);
+    });
+    assert_eq!(p1.join(p2).wait(), Ok((1, 2)));
+    t.join().unwrap();
+}
+
+#[test]
+fn wait_canceled() {
+    let (p, c) = promise::<i32, i32>();
+    let t = thread::spawn(|| drop(c));
+    assert_eq!(p.wait(), Err(WaitError::Canceled));
+    t.join().unwrap();
+}
+
+#[test]
+#[should_panic]
+fn wait_panics() {
+    finished::<i32, i32>(1).map(|_| -> i32 { panic!() }).wait().unwrap();
+}
+
+#[test]
+fn on_pool() {
+    let pool = Arc::new(executor::ThreadPool::builder()
+                                  .size(1)
+                                  .name_prefix("on-pool-")
+                                  .create());
+    let (p, c) = promise::<i32, i32>();
+    let (tx, rx) = channel();
+    p.on(pool.clone()).map(move |v| {
+        let name = thread::current().name().map(|s| s.to_string());
+        tx.send((v, name)).unwrap();
+    }).forget();
+    c.finish(1);
+    let (v, name) = rx.recv().unwrap();
+    assert_eq!(v, 1);
+    assert_eq!(name, Some("on-pool-0".to_string()));
+}
+
+#[test]
+fn on_threads_executor_through() {
+    struct Counting(AtomicUsize);
+
+    impl executor::Executor for Counting {
+        fn execute_boxed(&self, f: Box<executor::ExecuteCallback>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            f.call()
+        }
+    }
+
+    let exec = Arc::new(Counting(AtomicUsize::new(0)));
+    let (p, c) = promise::<i32, i32>();
+    let (tx, rx) = channel();
+    p.map(|a| a + 1)
+     .and_then(|a| finished(a + 1))
+     .join(finished(1))
+     .on(exec.clone())
+     .map(move |v| tx.send(v).unwrap())
+     .forget();
+    let t = thread::spawn(|| c.finish(1));
+    assert_eq!(rx.recv(), Ok((3, 1)));
+    t.join().unwrap();
+
+    // map, the inner finished, the joined finished, join itself, and then the
+    // final callback of `on`
+    assert_eq!(exec.0.load(Ordering::SeqCst), 5);
+}
@@ -0,0 +1,170 @@
+extern crate futures;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use futures::*;
+
+fn ms(n: u64) -> Duration {
+    Duration::from_millis(n)
+}
+
+fn counter() -> (Arc<AtomicUsize>, Arc<AtomicUsize>) {
+    let a = Arc::new(AtomicUsize::new(0));
+    (a.clone(), a)
+}
+
+#[test]
+fn succeeds_eventually() {
+    let (cnt, cnt2) = counter();
+    let f = retry(RetryPolicy::fixed(ms(0)), move || {
+        if cnt2.fetch_add(1, Ordering::SeqCst) < 3 {
+            Err(1)
+        } else {
+            Ok(2)
+        }
+    });
+    assert_eq!(f.wait(), Ok::<i32, WaitError<i32>>(2));
+    assert_eq!(cnt.load(Ordering::SeqCst), 4);
+}
+
+#[test]
+fn max_attempts() {
+    let (cnt, cnt2) = counter();
+    let policy = RetryPolicy::fixed(ms(1)).max_attempts(3);
+    let f = retry(policy, move || {
+        failed::<i32, usize>(cnt2.fetch_add(1, Ordering::SeqCst))
+    });
+    assert_eq!(f.wait(), Err(WaitError::Other(2)));
+    assert_eq!(cnt.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn retry_if() {
+    let (cnt, cnt2) = counter();
+    let policy = RetryPolicy::fixed(ms(0)).retry_if(|e: &usize| *e < 2);
+    let f = retry(policy, move || {
+        failed::<i32, usize>(cnt2.fetch_add(1, Ordering::SeqCst))
+    });
+    assert_eq!(f.wait(), Err(WaitError::Other(2)));
+    assert_eq!(cnt.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn backoff_waits() {
+    let start = Instant::now();
+    let policy = RetryPolicy::exponential(ms(10), ms(1000)).max_attempts(3);
+    let f = retry(policy, || failed::<i32, i32>(1));
+    assert_eq!(f.wait(), Err(WaitError::Other(1)));
+    // 10ms + 20ms
+    assert!(start.elapsed() >= ms(30));
+
+    let start = Instant::now();
+    let policy = RetryPolicy::jittered(ms(10), ms(20)).max_attempts(4);
+    let f = retry(policy, || failed::<i32, i32>(1));
+    assert_eq!(f.wait(), Err(WaitError::Other(1)));
+    assert!(start.elapsed() < ms(1000));
+}
+
+#[test]
+fn scheduled() {
+    let (cnt, cnt2) = counter();
+    let policy = RetryPolicy::fixed(ms(1));
+    let mut f = retry(policy, move || {
+        if cnt2.fetch_add(1, Ordering::SeqCst) < 2 {
+            failed(1).boxed()
+        } else {
+            finished(2).boxed()
+        }
+    });
+    let (tx, rx) = channel();
+    f.schedule(move |r| tx.send(r.ok()).unwrap());
+    assert_eq!(rx.recv().unwrap(), Some(2));
+    assert_eq!(cnt.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn drop_cancels() {
+    let (tx, rx) = channel();
+    let (tx2, rx2) = channel();
+    let mut f = retry(RetryPolicy::fixed(ms(0)), move || {
+        let (p, c) = promise::<i32, i32>();
+        tx.send(c).unwrap();
+        let tx2 = tx2.clone();
+        p.map(move |a| tx2.send(a).unwrap())
+    });
+    let (tx3, rx3) = channel();
+    f.schedule(move |r| tx3.send(r).unwrap());
+    let c = rx.recv().unwrap();
+    drop(f);
+    match rx3.recv().unwrap() {
+        Err(PollError::Canceled) => {}
+        _ => panic!("expected cancellation"),
+    }
+    c.finish(1);
+    assert!(rx2.recv().is_err());
+}
+
+#[test]
+fn drop_cancels_backoff() {
+    let (cnt, cnt2) = counter();
+    let mut f = retry(RetryPolicy::fixed(ms(20)), move || {
+        cnt2.fetch_add(1, Ordering::SeqCst);
+        failed::<i32, i32>(1)
+    });
+    let (tx, rx) = channel();
+    f.schedule(move |r| tx.send(r).unwrap());
+    drop(f);
+    match rx.recv().unwrap() {
+        Err(PollError::Canceled) => {}
+        _ => panic!("expected cancellation"),
+    }
+    std::thread::sleep(ms(50));
+    assert_eq!(cnt.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn panics_not_retried() {
+    let (cnt, cnt2) = counter();
+    let mut f = retry(RetryPolicy::fixed(ms(0)), move || {
+        cnt2.fetch_add(1, Ordering::SeqCst);
+        finished::<i32, i32>(1).map(|_| -> i32 { panic!() })
+    });
+    let (tx, rx) = channel();
+    f.schedule(move |r| tx.send(r).unwrap());
+    match rx.recv().unwrap() {
+        Err(PollError::Panicked(_)) => {}
+        _ => panic!("expected panic"),
+    }
+    assert_eq!(cnt.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn results_go_through_the_executor() {
+    let pool = Arc::new(executor::ThreadPool::new(1));
+    let me = thread::current().id();
+
+    // A factory panicking right away is still reported on the executor
+    let mut f = retry(RetryPolicy::fixed(ms(0)), || -> Finished<i32, i32> {
+        panic!()
+    });
+    let (tx, rx) = channel();
+    executor::Current::new(pool.clone()).enter(|| {
+        f.schedule(move |r| tx.send((r, thread::current().id())).unwrap())
+    });
+    let (r, id) = rx.recv().unwrap();
+    assert!(r.is_err());
+    assert!(id != me);
+
+    // ... as is reusing it
+    let (tx, rx) = channel();
+    executor::Current::new(pool).enter(|| {
+        f.schedule(move |r| tx.send((r, thread::current().id())).unwrap())
+    });
+    let (r, id) = rx.recv().unwrap();
+    assert!(r.is_err());
+    assert!(id != me);
+}
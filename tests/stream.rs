@@ -1,3 +1,665 @@
+extern crate futures;
+
+use std::sync::{mpsc, Arc, Mutex};
+
+use futures::*;
+use futures::stream::{self, Stream, StreamResult, TrySendError, IntoStream};
+use futures::stream::Sink;
+use futures::stream::{broadcast, watch};
+
+fn unwrap<T, E>(r: StreamResult<T, E>) -> Result<Option<T>, E> {
+    match r {
+        Ok(t) => Ok(t),
+        Err(PollError::Other(e)) => Err(e),
+        Err(PollError::Panicked(_)) => panic!("panicked"),
+        Err(PollError::Canceled) => panic!("canceled"),
+    }
+}
+
+fn next<S: Stream>(s: &mut S) -> Result<Option<S::Item>, S::Error> {
+    let (tx, rx) = mpsc::channel();
+    s.schedule(move |r| tx.send(r).unwrap());
+    unwrap(rx.recv().unwrap())
+}
+
+fn items<S: Stream>(mut s: S) -> Vec<Result<S::Item, S::Error>> {
+    let mut ret = Vec::new();
+    loop {
+        match next(&mut s) {
+            Ok(Some(t)) => ret.push(Ok(t)),
+            Ok(None) => return ret,
+            Err(e) => ret.push(Err(e)),
+        }
+    }
+}
+
+#[test]
+fn channel_smoke() {
+    let (tx, rx) = stream::channel::<i32, u32>(2);
+    tx.send(Ok(1))
+      .and_then(|tx| tx.send(Err(2)))
+      .and_then(|tx| tx.send(Ok(3)))
+      .forget();
+    assert_eq!(items(rx), vec![Ok(1), Err(2), Ok(3)]);
+}
+
+#[test]
+fn channel_backpressure() {
+    let (tx, mut rx) = stream::channel::<i32, u32>(1);
+    assert!(tx.try_send(Ok(1)).is_ok());
+    let (done_tx, done_rx) = mpsc::channel();
+    let mut f = tx.send(Ok(2));
+    f.schedule(move |r| done_tx.send(r.is_ok()).unwrap());
+    assert!(done_rx.try_recv().is_err());
+
+    assert_eq!(next(&mut rx), Ok(Some(1)));
+    assert_eq!(done_rx.recv(), Ok(true));
+    assert_eq!(next(&mut rx), Ok(Some(2)));
+    assert_eq!(next(&mut rx), Ok(None));
+}
+
+#[test]
+fn channel_try_send() {
+    let (tx, rx) = stream::channel::<i32, u32>(1);
+    assert!(tx.try_send(Ok(1)).is_ok());
+    match tx.try_send(Ok(2)) {
+        Err(TrySendError::Full(Ok(2))) => {}
+        _ => panic!("should be full"),
+    }
+    drop(rx);
+    match tx.try_send(Ok(3)) {
+        Err(e @ TrySendError::Disconnected(..)) => {
+            assert_eq!(e.into_inner(), Ok(3))
+        }
+        _ => panic!("should be disconnected"),
+    }
+}
+
+#[test]
+fn channel_closes_with_senders() {
+    let (tx, rx) = stream::channel::<i32, u32>(4);
+    let tx2 = tx.clone();
+    tx.try_send(Ok(1)).unwrap();
+    drop(tx);
+    tx2.try_send(Ok(2)).unwrap();
+
+    let (tx3, rx3) = mpsc::channel();
+    let mut rx = rx;
+    rx.schedule(move |r| tx3.send(r).unwrap());
+    assert_eq!(rx3.recv().map(unwrap), Ok(Ok(Some(1))));
+    assert_eq!(next(&mut rx), Ok(Some(2)));
+
+    let (tx3, rx3) = mpsc::channel();
+    rx.schedule(move |r| tx3.send(r).unwrap());
+    assert!(rx3.try_recv().is_err());
+    drop(tx2);
+    assert_eq!(rx3.recv().map(unwrap), Ok(Ok(None)));
+}
+
+#[test]
+fn channel_rx_drop() {
+    let (tx, rx) = stream::channel::<i32, u32>(0);
+    let (done_tx, done_rx) = mpsc::channel();
+    let mut f = tx.clone().send(Ok(1));
+    f.schedule(move |r| {
+        let msg = match r {
+            Err(PollError::Other(e)) => e.into_inner(),
+            _ => panic!("should have failed"),
+        };
+        done_tx.send(msg).unwrap()
+    });
+    drop(rx);
+    assert_eq!(done_rx.recv(), Ok(Ok(1)));
+    assert!(tx.send(Ok(2)).wait().is_err());
+}
+
+#[test]
+fn channel_rendezvous() {
+    let (tx, mut rx) = stream::channel::<i32, u32>(0);
+    match tx.try_send(Ok(1)) {
+        Err(TrySendError::Full(..)) => {}
+        _ => panic!("should be full"),
+    }
+
+    // A waiting receiver takes messages straight from `try_send`
+    let (tx2, rx2) = mpsc::channel();
+    rx.schedule(move |r| tx2.send(r).unwrap());
+    assert!(tx.try_send(Ok(2)).is_ok());
+    assert_eq!(rx2.recv().map(unwrap), Ok(Ok(Some(2))));
+
+    let (done_tx, done_rx) = mpsc::channel();
+    let mut f = tx.send(Ok(3));
+    f.schedule(move |r| done_tx.send(r.is_ok()).unwrap());
+    assert!(done_rx.try_recv().is_err());
+    assert_eq!(next(&mut rx), Ok(Some(3)));
+    assert_eq!(done_rx.recv(), Ok(true));
+}
+
+#[test]
+fn channel_send_canceled() {
+    let (tx, mut rx) = stream::channel::<i32, u32>(0);
+    let (done_tx, done_rx) = mpsc::channel();
+    let mut f = tx.send(Ok(1));
+    f.schedule(move |r| done_tx.send(r.is_err()).unwrap());
+    drop(f);
+    assert_eq!(done_rx.recv(), Ok(true));
+    assert_eq!(next(&mut rx), Ok(None));
+}
+
+fn list() -> stream::Receiver<i32, u32> {
+    let (tx, rx) = stream::channel(1);
+    tx.send(Ok(1))
+      .and_then(|tx| tx.send(Ok(2)))
+      .and_then(|tx| tx.send(Ok(3)))
+      .forget();
+    rx
+}
+
+fn err_list() -> stream::Receiver<i32, u32> {
+    let (tx, rx) = stream::channel(1);
+    tx.send(Ok(1))
+      .and_then(|tx| tx.send(Ok(2)))
+      .and_then(|tx| tx.send(Err(3)))
+      .forget();
+    rx
+}
+
+#[test]
+fn adapters() {
+    assert_eq!(list().map(|a| a + 1).collect().wait(), Ok(vec![2, 3, 4]));
+    assert_eq!(err_list().map_err(|a| a + 1).collect().wait(),
+               Err(WaitError::Other(4)));
+    assert_eq!(list().fold(0, |a, b| a + b).wait(), Ok(6));
+    assert_eq!(err_list().fold(0, |a, b| a + b).wait(),
+               Err(WaitError::Other(3)));
+    assert_eq!(list().filter(|a| *a % 2 == 0).collect().wait(), Ok(vec![2]));
+    assert_eq!(list().and_then(|a| Ok(a + 1)).collect().wait(),
+               Ok(vec![2, 3, 4]));
+    assert_eq!(err_list().and_then(|a| failed::<i32, _>(a as u32)).collect()
+                         .wait(),
+               Err(WaitError::Other(1)));
+    assert_eq!(err_list().or_else(|a| {
+        finished::<i32, u32>(a as i32)
+    }).collect().wait(), Ok(vec![1, 2, 3]));
+    assert_eq!(list().map(|_| list()).flat_map().collect().wait(),
+               Ok(vec![1, 2, 3, 1, 2, 3, 1, 2, 3]));
+    assert_eq!(list().map(finished::<_, u32>).flatten().collect().wait(),
+               Ok(vec![1, 2, 3]));
+}
+
+#[test]
+fn and_then_waits() {
+    let (tx, rx) = stream::channel::<i32, u32>(1);
+    let (p, c) = promise::<i32, u32>();
+    let mut p = Some(p);
+    let mut rx = rx.and_then(move |a| p.take().unwrap().map(move |b| a + b));
+
+    let (tx2, rx2) = mpsc::channel();
+    rx.schedule(move |r| tx2.send(r).unwrap());
+    tx.try_send(Ok(1)).unwrap();
+    assert!(rx2.try_recv().is_err());
+    c.finish(2);
+    assert_eq!(rx2.recv().map(unwrap), Ok(Ok(Some(3))));
+}
+
+#[test]
+fn and_then_panics() {
+    let mut s = list().and_then(|a| -> Result<i32, u32> {
+        if a == 2 {
+            panic!("two")
+        }
+        Ok(a)
+    });
+    assert_eq!(next(&mut s), Ok(Some(1)));
+    let (tx, rx) = mpsc::channel();
+    s.schedule(move |r| tx.send(r).unwrap());
+    match rx.recv().unwrap() {
+        Err(PollError::Panicked(_)) => {}
+        _ => panic!("should have panicked"),
+    }
+}
+
+#[test]
+fn collect_cancels() {
+    let (tx, rx) = stream::channel::<i32, u32>(1);
+    let (done_tx, done_rx) = mpsc::channel();
+    let mut c = rx.collect();
+    c.schedule(move |r| {
+        if let Err(PollError::Canceled) = r {
+            done_tx.send(()).unwrap()
+        }
+    });
+    tx.try_send(Ok(1)).unwrap();
+    drop(c);
+    assert_eq!(done_rx.recv(), Ok(()));
+    assert!(tx.send(Ok(2)).wait().is_err());
+}
+
+#[test]
+fn flat_map_cancels() {
+    let (tx, rx) = stream::channel::<i32, u32>(1);
+    let (tx2, rx2) = stream::channel::<stream::Receiver<i32, u32>, u32>(1);
+    tx2.try_send(Ok(rx)).unwrap();
+    let mut s = rx2.flat_map();
+    let (done_tx, done_rx) = mpsc::channel();
+    s.schedule(move |r| done_tx.send(r.is_err()).unwrap());
+    drop(s);
+    assert_eq!(done_rx.recv(), Ok(true));
+    assert!(tx.send(Ok(1)).wait().is_err());
+    assert!(tx2.try_send(Ok(stream::channel(1).1)).is_err());
+}
+
+#[test]
+fn iter() {
+    assert_eq!(items(stream::iter(vec![Ok(1), Err(2), Ok(3)])),
+               vec![Ok(1), Err(2), Ok(3)]);
+    assert_eq!(vec![Ok::<i32, u32>(1), Ok(2)].into_stream().collect().wait(),
+               Ok(vec![1, 2]));
+
+    // Plenty of elements which are all ready shouldn't blow the stack
+    let s = stream::iter((0..100_000).map(Ok::<u64, u32>));
+    assert_eq!(s.fold(0, |a, b| a + b).wait(), Ok(4_999_950_000));
+}
+
+#[test]
+fn once() {
+    assert_eq!(items(stream::once(finished::<i32, u32>(1))), vec![Ok(1)]);
+    assert_eq!(items(stream::once(Err::<i32, u32>(2))), vec![Err(2)]);
+    assert_eq!(items(failed::<i32, u32>(3).into_stream()), vec![Err(3)]);
+
+    let (p, c) = promise::<i32, u32>();
+    let mut s = p.into_stream();
+    let (tx, rx) = mpsc::channel();
+    s.schedule(move |r| tx.send(r).unwrap());
+    assert!(rx.try_recv().is_err());
+    c.finish(4);
+    assert_eq!(rx.recv().map(unwrap), Ok(Ok(Some(4))));
+    assert_eq!(next(&mut s), Ok(None));
+}
+
+#[test]
+fn repeat_and_empty() {
+    let mut s = stream::repeat::<i32, u32>(1);
+    for _ in 0..3 {
+        assert_eq!(next(&mut s), Ok(Some(1)));
+    }
+    assert_eq!(items(stream::empty::<i32, u32>()), Vec::new());
+}
+
+fn range(n: i32) -> stream::Iter<std::vec::IntoIter<Result<i32, u32>>> {
+    stream::iter((1..n + 1).map(Ok).collect::<Vec<_>>())
+}
+
+#[test]
+fn take_and_skip() {
+    assert_eq!(range(5).take(2).collect().wait(), Ok(vec![1, 2]));
+    assert_eq!(range(5).take(0).collect().wait(), Ok(vec![]));
+    assert_eq!(range(2).take(5).collect().wait(), Ok(vec![1, 2]));
+    assert_eq!(range(5).skip(2).collect().wait(), Ok(vec![3, 4, 5]));
+    assert_eq!(range(2).skip(5).collect().wait(), Ok(vec![]));
+    assert_eq!(items(stream::iter(vec![Ok(1), Err(2), Ok(3), Ok(4)]).skip(1)),
+               vec![Err(2), Ok(3), Ok(4)]);
+    assert_eq!(items(stream::iter(vec![Err(2), Ok(3), Ok(4)]).take(1)),
+               vec![Err(2), Ok(3)]);
+}
+
+#[test]
+fn take_while_and_skip_while() {
+    assert_eq!(range(5).take_while(|a| *a < 3).collect().wait(),
+               Ok(vec![1, 2]));
+    assert_eq!(range(5).skip_while(|a| *a < 3).collect().wait(),
+               Ok(vec![3, 4, 5]));
+
+    // Once the predicate fails it's never consulted again
+    let s = stream::iter(vec![Ok::<i32, u32>(1), Ok(5), Ok(2)]);
+    assert_eq!(s.skip_while(|a| *a < 3).collect().wait(), Ok(vec![5, 2]));
+    let s = stream::iter(vec![Ok::<i32, u32>(1), Ok(5), Ok(2)]);
+    assert_eq!(s.take_while(|a| *a < 3).collect().wait(), Ok(vec![1]));
+}
+
+#[test]
+fn chunks() {
+    assert_eq!(range(5).chunks(2).collect().wait(),
+               Ok(vec![vec![1, 2], vec![3, 4], vec![5]]));
+    assert_eq!(range(4).chunks(2).collect().wait(),
+               Ok(vec![vec![1, 2], vec![3, 4]]));
+    assert_eq!(range(0).chunks(2).collect().wait(), Ok(vec![]));
+    assert_eq!(items(stream::iter(vec![Ok(1), Err(2), Ok(3)]).chunks(2)),
+               vec![Err(2), Ok(vec![1, 3])]);
+
+    // Chunks fill up as elements arrive
+    let (tx, rx) = stream::channel::<i32, u32>(4);
+    let mut s = rx.chunks(2);
+    let (tx2, rx2) = mpsc::channel();
+    s.schedule(move |r| tx2.send(r).unwrap());
+    tx.try_send(Ok(1)).unwrap();
+    assert!(rx2.try_recv().is_err());
+    tx.try_send(Ok(2)).unwrap();
+    assert_eq!(rx2.recv().map(unwrap), Ok(Ok(Some(vec![1, 2]))));
+}
+
+#[test]
+fn chain() {
+    assert_eq!(range(2).chain(range(3)).collect().wait(),
+               Ok(vec![1, 2, 1, 2, 3]));
+    assert_eq!(range(0).chain(range(2)).collect().wait(), Ok(vec![1, 2]));
+    assert_eq!(items(err_list().chain(list())),
+               vec![Ok(1), Ok(2), Err(3), Ok(1), Ok(2), Ok(3)]);
+}
+
+#[test]
+fn zip() {
+    assert_eq!(range(3).zip(range(3).map(|i| i * 10)).collect().wait(),
+               Ok(vec![(1, 10), (2, 20), (3, 30)]));
+    assert_eq!(range(5).zip(range(2)).collect().wait(),
+               Ok(vec![(1, 1), (2, 2)]));
+    assert_eq!(range(0).zip(list()).collect().wait(), Ok(vec![]));
+    assert_eq!(items(range(5).zip(err_list())),
+               vec![Ok((1, 1)), Ok((2, 2)), Err(3)]);
+
+    // Pairs only go out once both halves are in
+    let (tx1, rx1) = stream::channel::<i32, u32>(4);
+    let (tx2, rx2) = stream::channel::<i32, u32>(4);
+    let mut s = rx1.zip(rx2);
+    let (tx, rx) = mpsc::channel();
+    s.schedule(move |r| tx.send(r).unwrap());
+    tx1.try_send(Ok(1)).unwrap();
+    assert!(rx.try_recv().is_err());
+    tx2.try_send(Ok(2)).unwrap();
+    assert_eq!(rx.recv().map(unwrap), Ok(Ok(Some((1, 2)))));
+}
+
+#[test]
+fn merge() {
+    let mut all = range(3).merge(list()).collect().wait().ok().unwrap();
+    all.sort();
+    assert_eq!(all, vec![1, 1, 2, 2, 3, 3]);
+
+    let mut all = items(err_list().merge(range(2)));
+    all.sort();
+    assert_eq!(all, vec![Ok(1), Ok(1), Ok(2), Ok(2), Err(3)]);
+
+    // Elements come out in the order they arrive
+    let (tx1, rx1) = stream::channel::<i32, u32>(4);
+    let (tx2, rx2) = stream::channel::<i32, u32>(4);
+    let mut s = rx1.merge(rx2);
+    tx2.try_send(Ok(2)).unwrap();
+    assert_eq!(next(&mut s), Ok(Some(2)));
+    tx1.try_send(Ok(1)).unwrap();
+    assert_eq!(next(&mut s), Ok(Some(1)));
+    drop(tx1);
+    tx2.try_send(Ok(3)).unwrap();
+    assert_eq!(next(&mut s), Ok(Some(3)));
+    drop(tx2);
+    assert_eq!(next(&mut s), Ok(None));
+}
+
+#[test]
+fn broadcast() {
+    let (tx, rx1) = broadcast::channel::<i32>(4);
+    tx.send(1).unwrap();
+    let rx2 = tx.subscribe();
+    assert_eq!(tx.receiver_count(), 2);
+    tx.send(2).unwrap();
+    drop(tx);
+
+    // Later subscribers only see what's sent after they turn up
+    assert_eq!(items(rx1), vec![Ok(1), Ok(2)]);
+    assert_eq!(items(rx2), vec![Ok(2)]);
+}
+
+#[test]
+fn broadcast_wakes_everyone() {
+    let (tx, mut rx1) = broadcast::channel::<i32>(1);
+    let mut rx2 = tx.subscribe();
+    let (tx1, rx1_done) = mpsc::channel();
+    let (tx2, rx2_done) = mpsc::channel();
+    rx1.schedule(move |r| tx1.send(unwrap(r)).unwrap());
+    rx2.schedule(move |r| tx2.send(unwrap(r)).unwrap());
+    assert!(rx1_done.try_recv().is_err());
+
+    tx.send(3).unwrap();
+    assert_eq!(rx1_done.recv().unwrap(), Ok(Some(3)));
+    assert_eq!(rx2_done.recv().unwrap(), Ok(Some(3)));
+
+    drop(rx1);
+    drop(rx2);
+    assert_eq!(tx.send(4).err().unwrap().into_inner(), 4);
+}
+
+#[test]
+fn broadcast_lagged() {
+    let (tx, mut rx) = broadcast::channel::<i32>(2);
+    for i in 0..5 {
+        tx.send(i).unwrap();
+    }
+    let e = next(&mut rx).err().unwrap();
+    assert_eq!(e, broadcast::RecvError::Lagged(3));
+    let e: BoxError = Box::new(e);
+    assert_eq!(e.to_string(), "receiver lagged behind, missing 3 messages");
+    assert_eq!(next(&mut rx), Ok(Some(3)));
+    tx.send(5).unwrap();
+    drop(tx);
+    assert_eq!(items(rx), vec![Ok(4), Ok(5)]);
+}
+
+#[test]
+fn watch() {
+    let (tx, mut rx) = watch::channel("a");
+    assert_eq!(rx.get(), "a");
+    let mut rx2 = rx.clone();
+
+    // Only the latest value is seen
+    tx.send("b");
+    tx.send("c");
+    assert_eq!(next(&mut rx), Ok(Some("c")));
+    let (done_tx, done_rx) = mpsc::channel();
+    rx.schedule(move |r| done_tx.send(unwrap(r)).unwrap());
+    assert!(done_rx.try_recv().is_err());
+    tx.send("d");
+    assert_eq!(done_rx.recv().unwrap(), Ok(Some("d")));
+
+    assert_eq!(rx2.get(), "d");
+    let rx3 = tx.subscribe();
+    tx.send("e");
+    drop(tx);
+    assert_eq!(next(&mut rx2), Ok(Some("e")));
+    assert_eq!(next(&mut rx2), Ok(None));
+    assert_eq!(items(rx3), vec![Ok("e")]);
+}
+
+#[test]
+fn select_all() {
+    assert_eq!(stream::select_all(Vec::<stream::BoxStream<i32, u32>>::new())
+                   .collect().wait(),
+               Ok(vec![]));
+
+    let mut s = stream::select_all(vec![range(2).boxed(), range(0).boxed()]);
+    let (tx, rx) = stream::channel::<i32, u32>(1);
+    s.push(rx.boxed());
+    tx.try_send(Ok(10)).unwrap();
+    drop(tx);
+    let mut all = items(s);
+    all.sort();
+    assert_eq!(all, vec![Ok(1), Ok(2), Ok(10)]);
+
+    // Dropping the set cancels anyone waiting on it
+    let (_tx, rx) = stream::channel::<i32, u32>(1);
+    let mut s = stream::select_all(vec![rx.boxed()]);
+    let (tx, rx) = mpsc::channel();
+    s.schedule(move |r| tx.send(r).unwrap());
+    drop(s);
+    match rx.recv().unwrap() {
+        Err(PollError::Canceled) => {}
+        _ => panic!("not canceled"),
+    }
+}
+
+#[test]
+fn buffered() {
+    let (tx, rx) = stream::channel::<Promise<i32, u32>, u32>(4);
+    let (c1, c2, c3) = {
+        let (p1, c1) = promise();
+        let (p2, c2) = promise();
+        let (p3, c3) = promise();
+        tx.try_send(Ok(p1)).unwrap();
+        tx.try_send(Ok(p2)).unwrap();
+        tx.try_send(Ok(p3)).unwrap();
+        (c1, c2, c3)
+    };
+    drop(tx);
+    let mut s = rx.buffered(2);
+
+    // Results come out in order even if they finish out of order
+    let (tx, rx) = mpsc::channel();
+    s.schedule(move |r| tx.send(r).unwrap());
+    c2.finish(2);
+    assert!(rx.try_recv().is_err());
+    c1.finish(1);
+    assert_eq!(rx.recv().map(unwrap), Ok(Ok(Some(1))));
+    assert_eq!(next(&mut s), Ok(Some(2)));
+    c3.finish(3);
+    assert_eq!(next(&mut s), Ok(Some(3)));
+    assert_eq!(next(&mut s), Ok(None));
+
+    assert_eq!(items(err_list().map(finished::<i32, u32>).buffered(2)),
+               vec![Ok(1), Ok(2), Err(3)]);
+}
+
+#[test]
+fn buffer_unordered() {
+    let (tx, rx) = stream::channel::<Promise<i32, u32>, u32>(4);
+    let (p1, c1) = promise();
+    let (p2, c2) = promise();
+    let (p3, c3) = promise();
+    tx.try_send(Ok(p1)).unwrap();
+    tx.try_send(Ok(p2)).unwrap();
+    tx.try_send(Ok(p3)).unwrap();
+    drop(tx);
+    let mut s = rx.buffer_unordered(2);
+
+    // Only the first two are running, so the third can't jump the queue
+    let (tx, rx) = mpsc::channel();
+    s.schedule(move |r| tx.send(r).unwrap());
+    c3.finish(3);
+    assert!(rx.try_recv().is_err());
+    c2.finish(2);
+    assert_eq!(rx.recv().map(unwrap), Ok(Ok(Some(2))));
+    assert_eq!(next(&mut s), Ok(Some(3)));
+    c1.finish(1);
+    assert_eq!(next(&mut s), Ok(Some(1)));
+    assert_eq!(next(&mut s), Ok(None));
+}
+
+#[test]
+fn buffered_cancels() {
+    let data = Arc::new(());
+    let (tx, rx) = stream::channel::<Promise<i32, u32>, u32>(4);
+    let mut completes = Vec::new();
+    for _ in 0..3 {
+        let (p, c) = promise();
+        tx.try_send(Ok(p)).unwrap();
+        completes.push(c);
+    }
+    let data2 = data.clone();
+    let mut s = rx.map(move |p| {
+        let data = data2.clone();
+        p.map(move |i| { drop(data); i })
+    }).buffered(2);
+    let (tx2, rx2) = mpsc::channel();
+    s.schedule(move |r| tx2.send(r).unwrap());
+    assert!(Arc::strong_count(&data) > 2);
+
+    // Dropping the stream cancels the futures in flight, and the one waiting
+    // for a result
+    drop(s);
+    match rx2.recv().unwrap() {
+        Err(PollError::Canceled) => {}
+        _ => panic!("not canceled"),
+    }
+    assert_eq!(Arc::strong_count(&data), 1);
+    drop(tx);
+}
+
+struct Recorder(Arc<Mutex<Vec<Option<i32>>>>);
+
+// Records each element sent, and a `None` for every flush
+impl Sink for Recorder {
+    type Item = i32;
+    type Error = u32;
+
+    fn start_send(&mut self, i: i32) -> Box<Future<Item=(), Error=u32>> {
+        self.0.lock().unwrap().push(Some(i));
+        finished(()).boxed()
+    }
+
+    fn flush(&mut self) -> Box<Future<Item=(), Error=u32>> {
+        self.0.lock().unwrap().push(None);
+        finished(()).boxed()
+    }
+
+    fn close(&mut self) -> Box<Future<Item=(), Error=u32>> {
+        self.flush()
+    }
+}
+
+#[test]
+fn forward() {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    assert!(range(3).forward(Recorder(log.clone())).wait().is_ok());
+    assert_eq!(*log.lock().unwrap(), vec![Some(1), Some(2), Some(3), None]);
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    match err_list().forward(Recorder(log.clone())).wait() {
+        Err(WaitError::Other(3)) => {}
+        _ => panic!("expected an error"),
+    }
+    assert_eq!(*log.lock().unwrap(), vec![Some(1), Some(2)]);
+
+    // Sends wait for the sink to accept each element
+    let (mut tx, mut rx) = stream::channel::<i32, u32>(0);
+    let mut f = tx.start_send(Ok(1));
+    let (done_tx, done_rx) = mpsc::channel();
+    f.schedule(move |r| done_tx.send(r.is_ok()).unwrap());
+    assert!(done_rx.try_recv().is_err());
+    assert_eq!(next(&mut rx), Ok(Some(1)));
+    assert_eq!(done_rx.recv(), Ok(true));
+    assert!(tx.flush().wait().is_ok());
+}
+
+// Panics as soon as anything is sent to it
+struct Explode;
+
+impl Sink for Explode {
+    type Item = i32;
+    type Error = u32;
+
+    fn start_send(&mut self, _: i32) -> Box<Future<Item=(), Error=u32>> {
+        panic!("boom")
+    }
+
+    fn flush(&mut self) -> Box<Future<Item=(), Error=u32>> {
+        finished(()).boxed()
+    }
+
+    fn close(&mut self) -> Box<Future<Item=(), Error=u32>> {
+        self.flush()
+    }
+}
+
+#[test]
+fn forward_sink_panics() {
+    let mut f = range(3).forward(Explode);
+    let (tx, rx) = mpsc::channel();
+    f.schedule(move |r| tx.send(r).unwrap());
+    match rx.recv().unwrap() {
+        Err(PollError::Panicked(p)) => {
+            assert_eq!(p.downcast_ref::<&str>(), Some(&"boom"));
+        }
+        _ => panic!("expected a panic"),
+    }
+}
+
 // extern crate futures;
 //
 // use std::thread;
@@ -7,41 +669,6 @@
 // use futures::stream::{Stream, PollError};
 // use futures::bufstream::bufstream;
 //
-// #[test]
-// fn smoke() {
-//     let (tx, rx) = channel::<i32, u32>();
-//     tx.send(Ok(1))
-//       .and_then(|tx| tx.send(Ok(2)))
-//       .and_then(|tx| tx.send(Ok(3)))
-//       .schedule(|r| assert!(r.is_ok()));
-//     assert_eq!(rx.collect().await(), Ok(vec![1, 2, 3]));
-//
-//     let (tx, rx) = channel::<i32, u32>();
-//     tx.send(Ok(1))
-//       .and_then(|tx| tx.send(Err(2)))
-//       .and_then(|tx| tx.send(Ok(3)))
-//       .schedule(|r| assert!(r.is_ok()));
-//     assert_eq!(rx.collect().await(), Err(2));
-// }
-//
-// fn list() -> Receiver<i32, u32> {
-//     let (tx, rx) = channel();
-//     tx.send(Ok(1))
-//       .and_then(|tx| tx.send(Ok(2)))
-//       .and_then(|tx| tx.send(Ok(3)))
-//       .schedule(|r| assert!(r.is_ok()));
-//     return rx
-// }
-//
-// fn err_list() -> Receiver<i32, u32> {
-//     let (tx, rx) = channel();
-//     tx.send(Ok(1))
-//       .and_then(|tx| tx.send(Ok(2)))
-//       .and_then(|tx| tx.send(Err(3)))
-//       .schedule(|_| {});
-//     return rx
-// }
-//
 // fn collect_poll<S: Stream>(mut s: S) -> Result<Vec<S::Item>, S::Error> {
 //     let mut base = Vec::new();
 //     loop {
@@ -55,23 +682,6 @@
 // }
 //
 // #[test]
-// fn adapters() {
-//     assert_eq!(list().map(|a| a + 1).collect().await(), Ok(vec![2, 3, 4]));
-//     assert_eq!(err_list().map_err(|a| a + 1).collect().await(), Err(4));
-//     assert_eq!(list().fold(0, |a, b| a + b).await(), Ok(6));
-//     assert_eq!(list().filter(|a| *a % 2 == 0).collect().await(), Ok(vec![2]));
-//     assert_eq!(list().and_then(|a| Ok(a + 1)).collect().await(),
-//                Ok(vec![2, 3, 4]));
-//     assert_eq!(err_list().or_else(|a| {
-//         finished::<i32, u32>(a as i32)
-//     }).collect().await(), Ok(vec![1, 2, 3]));
-//     assert_eq!(list().map(|_| list()).flat_map().collect().await(),
-//                Ok(vec![1, 2, 3, 1, 2, 3, 1, 2, 3]));
-//     assert_eq!(list().map(|i| finished::<_, u32>(i)).flatten().collect().await(),
-//                Ok(vec![1, 2, 3]));
-// }
-//
-// #[test]
 // fn adapters_poll() {
 //     assert_eq!(collect_poll(list().map(|a| a + 1)), Ok(vec![2, 3, 4]));
 //     assert_eq!(collect_poll(err_list().map_err(|a| a + 1)), Err(4));
@@ -137,13 +747,6 @@
 // }
 //
 // #[test]
-// fn rxdrop() {
-//     let (tx, rx) = channel::<i32, u32>();
-//     drop(rx);
-//     assert!(tx.send(Ok(1)).await().is_err());
-// }
-//
-// #[test]
 // fn bufstream_smoke() {
 //     let (tx, mut rx) = bufstream::<i32, u32>(4);
 //     let (vrx, mut vtx): (Vec<_>, Vec<_>) = (0..4).map(|_| {
@@ -0,0 +1,216 @@
+extern crate futures;
+
+use std::cell::Cell;
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use futures::*;
+use futures::sync::{Mutex, MutexGuard, RwLock, Semaphore, RateLimiter};
+
+type Results<F> = Receiver<PollResult<<F as Future>::Item, <F as Future>::Error>>;
+
+fn wait_for<F: Future>(mut f: F) -> (F, Results<F>) {
+    let (tx, rx) = channel();
+    f.schedule(move |r| tx.send(r).unwrap());
+    (f, rx)
+}
+
+fn assert_cancel<T, E>(r: PollResult<T, E>) {
+    match r {
+        Err(PollError::Canceled) => {}
+        _ => panic!("not canceled"),
+    }
+}
+
+#[test]
+fn mutex_smoke() {
+    let m = Mutex::new(1);
+    let mut g = m.lock().poll().unwrap().ok().unwrap();
+    *g += 1;
+    assert!(m.lock().poll().is_none());
+    assert!(m.try_lock().is_none());
+    drop(g);
+    assert_eq!(*m.lock().wait().ok().unwrap(), 2);
+    assert_eq!(*m.clone().try_lock().unwrap(), 2);
+}
+
+#[test]
+fn mutex_fifo() {
+    let m = Mutex::new(0);
+    let g = m.try_lock().unwrap();
+    let (_f1, rx1) = wait_for(m.lock());
+    let (_f2, rx2) = wait_for(m.lock());
+
+    // Nobody gets to jump the queue
+    assert!(m.try_lock().is_none());
+    assert!(rx1.try_recv().is_err());
+    drop(g);
+    let g1 = rx1.recv().unwrap().ok().unwrap();
+    assert!(rx2.try_recv().is_err());
+    drop(g1);
+    assert!(rx2.recv().unwrap().is_ok());
+}
+
+#[test]
+fn mutex_cancel() {
+    let m = Mutex::new(0);
+    let g = m.try_lock().unwrap();
+    let (f1, rx1) = wait_for(m.lock());
+    let (_f2, rx2) = wait_for(m.lock());
+
+    // Dropping a waiter takes it out of line
+    drop(f1);
+    assert_cancel(rx1.recv().unwrap());
+    drop(g);
+    drop(rx2.recv().unwrap().ok().unwrap());
+    assert!(m.try_lock().is_some());
+}
+
+#[test]
+fn mutex_guard_send_sync() {
+    fn is_send<T: Send>() {}
+    fn is_sync<T: Sync>() {}
+
+    // Only one of these impls applies unless `T: Sync`, in which case naming
+    // `some_item` is ambiguous and this fails to compile.
+    trait AmbiguousIfSync<A> {
+        fn some_item() {}
+    }
+    impl<T: ?Sized> AmbiguousIfSync<()> for T {}
+    impl<T: ?Sized + Sync> AmbiguousIfSync<u8> for T {}
+
+    is_send::<MutexGuard<Cell<i32>>>();
+    is_sync::<MutexGuard<i32>>();
+    let _ = <MutexGuard<Cell<i32>> as AmbiguousIfSync<_>>::some_item;
+}
+
+#[test]
+fn rwlock_readers_and_writers() {
+    let l = RwLock::new(1);
+    let r1 = l.try_read().unwrap();
+    let r2 = l.read().poll().unwrap().ok().unwrap();
+    assert_eq!(*r1 + *r2, 2);
+    assert!(l.try_write().is_none());
+
+    // Once a writer is waiting, new readers queue up behind it
+    let (_w, wrx) = wait_for(l.write());
+    let (_r3, rrx3) = wait_for(l.read());
+    let (_r4, rrx4) = wait_for(l.read());
+    assert!(l.try_read().is_none());
+    drop(r1);
+    assert!(wrx.try_recv().is_err());
+    drop(r2);
+    let mut w = wrx.recv().unwrap().ok().unwrap();
+    *w += 1;
+    assert!(rrx3.try_recv().is_err());
+
+    // ... and are all let in together after it
+    drop(w);
+    let r3 = rrx3.recv().unwrap().ok().unwrap();
+    let r4 = rrx4.recv().unwrap().ok().unwrap();
+    assert_eq!((*r3, *r4), (2, 2));
+}
+
+#[test]
+fn rwlock_cancel() {
+    let l = RwLock::new(0);
+    let r = l.try_read().unwrap();
+    let (w, wrx) = wait_for(l.write());
+    let (_r2, rrx2) = wait_for(l.read());
+
+    // Giving up on the write lets the reader behind it in
+    drop(w);
+    assert_cancel(wrx.recv().unwrap());
+    assert!(rrx2.recv().unwrap().is_ok());
+    drop(r);
+}
+
+#[test]
+fn semaphore_fifo() {
+    let s = Semaphore::new(3);
+    let p = s.acquire(2).wait().ok().unwrap();
+    assert_eq!(p.amount(), 2);
+    assert_eq!(s.available_permits(), 1);
+    let (_f1, rx1) = wait_for(s.acquire(2));
+    let (_f2, rx2) = wait_for(s.acquire(1));
+
+    // The small request waits behind the big one
+    assert!(s.try_acquire(1).is_none());
+    assert!(rx2.try_recv().is_err());
+    drop(p);
+    let p1 = rx1.recv().unwrap().ok().unwrap();
+    let p2 = rx2.recv().unwrap().ok().unwrap();
+    assert_eq!(s.available_permits(), 0);
+    drop((p1, p2));
+    assert_eq!(s.available_permits(), 3);
+}
+
+#[test]
+fn semaphore_cancel() {
+    let s = Semaphore::new(1);
+    let p = s.try_acquire(1).unwrap();
+    let (f1, rx1) = wait_for(s.acquire(1));
+    let (_f2, rx2) = wait_for(s.acquire(1));
+
+    drop(f1);
+    assert_cancel(rx1.recv().unwrap());
+    drop(p);
+    drop(rx2.recv().unwrap().ok().unwrap());
+    assert_eq!(s.available_permits(), 1);
+}
+
+#[test]
+fn rate_limiter() {
+    let period = Duration::from_millis(20);
+    let r = RateLimiter::new(2, period);
+    let start = Instant::now();
+
+    // The bucket starts out full, then lets one more through per period
+    assert!(r.ready().wait().is_ok());
+    assert!(r.clone().try_ready());
+    assert!(!r.try_ready());
+    assert!(r.ready().wait().is_ok());
+    assert!(r.ready().wait().is_ok());
+    assert!(start.elapsed() >= period * 2);
+}
+
+#[test]
+fn rate_limiter_cancel() {
+    let r = RateLimiter::new(1, Duration::from_millis(20));
+    assert!(r.try_ready());
+    let (f1, rx1) = wait_for(r.ready());
+    let (_f2, rx2) = wait_for(r.ready());
+
+    drop(f1);
+    assert_cancel(rx1.recv().unwrap());
+    assert!(rx2.recv().unwrap().is_ok());
+    assert!(!r.try_ready());
+}
+
+#[test]
+fn rate_limiter_drop_races() {
+    let r = RateLimiter::new(4, Duration::from_secs(3600));
+
+    // A token granted to a waiter which never sees it goes back
+    drop(r.ready());
+    assert_eq!((0..5).filter(|_| r.try_ready()).count(), 4);
+
+    // ... even if the waiter goes away while it's being handed out
+    let r = RateLimiter::new(4, Duration::from_secs(3600));
+    let threads = (0..4).map(|_| {
+        let r = r.clone();
+        thread::spawn(move || {
+            for _ in 0..1000 {
+                let a = r.ready();
+                let b = r.ready();
+                drop(a);
+                drop(b);
+            }
+        })
+    }).collect::<Vec<_>>();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert_eq!((0..5).filter(|_| r.try_ready()).count(), 4);
+}
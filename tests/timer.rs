@@ -0,0 +1,91 @@
+extern crate futures;
+
+use std::sync::mpsc::channel;
+use std::time::{Duration, Instant};
+
+use futures::*;
+use futures::stream::Stream;
+use futures::timer::{self, Timer, TimeoutError};
+
+fn ms(n: u64) -> Duration {
+    Duration::from_millis(n)
+}
+
+#[test]
+fn delay() {
+    let start = Instant::now();
+    assert_eq!(timer::delay(ms(50)).wait(), Ok(()));
+    assert!(start.elapsed() >= ms(50));
+}
+
+#[test]
+fn delay_ordering() {
+    let timer = Timer::new();
+    let (tx, rx) = channel();
+    for &i in [30, 10, 20].iter() {
+        let tx = tx.clone();
+        timer.delay(ms(i)).map(move |()| tx.send(i).unwrap()).forget();
+    }
+    drop(tx);
+    assert_eq!(rx.iter().collect::<Vec<_>>(), vec![10, 20, 30]);
+}
+
+#[test]
+fn delay_dropped() {
+    let timer = Timer::new();
+    let (tx, rx) = channel();
+    let mut d = timer.delay(ms(10)).map(move |()| tx.send(()).unwrap());
+    d.schedule(|_| ());
+    drop(d);
+    assert!(rx.recv().is_err());
+}
+
+#[test]
+fn delay_outlives_timer() {
+    // Pending delays still fire after the last handle to the timer is gone
+    let start = Instant::now();
+    let a = Timer::new().delay(ms(20));
+    let b = Timer::new().delay(ms(0));
+    assert_eq!(a.join(b).wait(), Ok(((), ())));
+    assert!(start.elapsed() >= ms(20));
+}
+
+#[test]
+fn timeout() {
+    let (p, _c) = promise::<i32, u32>();
+    let start = Instant::now();
+    assert_eq!(p.timeout(ms(20)).wait(),
+               Err(WaitError::Other(TimeoutError::TimedOut)));
+    assert!(start.elapsed() >= ms(20));
+
+    assert_eq!(finished::<i32, u32>(1).timeout(ms(1000)).wait(), Ok(1));
+    assert_eq!(failed::<i32, u32>(1).timeout(ms(1000)).wait(),
+               Err(WaitError::Other(TimeoutError::Other(1))));
+
+    // The error reads like whatever it's wrapping
+    let e = TimeoutError::Other(WaitError::Other(1));
+    assert_eq!(e.to_string(), "1");
+    assert_eq!(TimeoutError::TimedOut::<u32>.to_string(), "future timed out");
+}
+
+#[test]
+fn timeout_cancels() {
+    let (p, c) = promise::<i32, u32>();
+    let (tx, rx) = channel();
+    let p = p.map(move |a| { tx.send(a).unwrap(); a });
+    assert!(p.timeout(ms(10)).wait().is_err());
+    c.finish(1);
+    assert!(rx.recv().is_err());
+}
+
+#[test]
+fn interval() {
+    let start = Instant::now();
+    let mut interval = timer::interval(ms(10));
+    for i in 1..4 {
+        let (tx, rx) = channel();
+        interval.schedule(move |r| tx.send(r.ok().unwrap()).unwrap());
+        assert_eq!(rx.recv().unwrap(), Some(()));
+        assert!(start.elapsed() >= ms(10 * i));
+    }
+}